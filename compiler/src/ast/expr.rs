@@ -1,30 +1,129 @@
-use super::Literal;
+use super::{Literal, Parameter, Stmt, Type};
+use crate::lexer::span::Span;
 use crate::lexer::TokenKind;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
-    Literal(Literal),
+    Literal {
+        value: Literal,
+        span: Span,
+    },
     Binary {
         left: Box<Expr>,
         op: TokenKind,
         right: Box<Expr>,
+        span: Span,
+    },
+    Variable {
+        name: String,
+        span: Span,
     },
-    Variable(String),
     Call {
         callee: Box<Expr>,
         args: Vec<Expr>,
+        span: Span,
     },
     Unary {
         op: TokenKind,
         right: Box<Expr>,
+        span: Span,
     },
 
+    // `op` is set for compound assignment: `x += 1` carries `+`
     Assign {
-        name: String,
+        target: Target,
+        op: Option<TokenKind>,
         value: Box<Expr>,
+        span: Span,
+    },
+    // `x++`, `--x` and the like; `op` is `++` or `--`
+    Update {
+        target: Target,
+        op: TokenKind,
+        prefix: bool,
+        span: Span,
     },
     Get {
         object: Box<Expr>,
         name: String,
+        span: Span,
+    },
+    Array {
+        elements: Vec<Expr>,
+        span: Span,
+    },
+    // key/value pairs in source order
+    Dict {
+        entries: Vec<(Expr, Expr)>,
+        span: Span,
+    },
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+        span: Span,
+    },
+    // `func(x) { ... }`, or `(x) => value` with a body that returns `value`
+    Lambda {
+        params: Vec<Parameter>,
+        return_ty: Option<Type>,
+        body: Vec<Stmt>,
+        span: Span,
+    },
+    // `"Hello {name}"`: the text around the embedded expressions is kept
+    // as string literals, and every part is shown the way println shows it
+    Interpolation {
+        parts: Vec<Expr>,
+        span: Span,
+    },
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Literal { span, .. }
+            | Expr::Binary { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Assign { span, .. }
+            | Expr::Update { span, .. }
+            | Expr::Get { span, .. }
+            | Expr::Array { span, .. }
+            | Expr::Dict { span, .. }
+            | Expr::Index { span, .. }
+            | Expr::Lambda { span, .. }
+            | Expr::Interpolation { span, .. } => *span,
+        }
+    }
+}
+
+// what `=`, `+=` and the like, `++` and `--` can write to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Variable {
+        name: String,
+        span: Span,
+    },
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+        span: Span,
+    },
+    Field {
+        object: Box<Expr>,
+        name: String,
+        span: Span,
     },
 }
+
+impl Target {
+    // the assignable form of `expr`, if it has one
+    pub fn from_expr(expr: Expr) -> Result<Target, Expr> {
+        match expr {
+            Expr::Variable { name, span } => Ok(Target::Variable { name, span }),
+            Expr::Index { object, index, span } => Ok(Target::Index { object, index, span }),
+            Expr::Get { object, name, span } => Ok(Target::Field { object, name, span }),
+            other => Err(other),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use super::{floatText, Expr, Literal, Parameter, Stmt, Target, Type};
+use crate::diagnostics::render::jsonString;
+use crate::lexer::span::Span;
+use crate::lexer::TokenKind;
+
+// syntax trees as JSON, for `rey ast --json`
+// every node is an object with a "node" name, its "span" in byte offsets
+// and one member per field
+
+pub fn statementsJson(statements: &[Stmt]) -> String {
+    array(statements.iter().map(stmtJson))
+}
+
+fn stmtJson(stmt: &Stmt) -> String {
+    match stmt {
+        Stmt::VarDecl {
+            doc,
+            name,
+            ty,
+            initializer,
+            span,
+        } => node(
+            "VarDecl",
+            *span,
+            &[
+                ("doc", docJson(doc.as_deref())),
+                ("name", jsonString(name)),
+                ("type", typeJson(ty.as_ref())),
+                ("initializer", exprJson(initializer)),
+            ],
+        ),
+        Stmt::FuncDecl {
+            doc,
+            name,
+            params,
+            return_ty,
+            body,
+            span,
+        } => node(
+            "FuncDecl",
+            *span,
+            &[
+                ("doc", docJson(doc.as_deref())),
+                ("name", jsonString(name)),
+                ("params", array(params.iter().map(paramJson))),
+                ("returnType", typeJson(return_ty.as_ref())),
+                ("body", statementsJson(body)),
+            ],
+        ),
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+            span,
+        } => node(
+            "If",
+            *span,
+            &[
+                ("condition", exprJson(condition)),
+                ("then", statementsJson(then_branch)),
+                (
+                    "else",
+                    else_branch
+                        .as_ref()
+                        .map_or("null".to_string(), |branch| statementsJson(branch)),
+                ),
+            ],
+        ),
+        Stmt::While { condition, body, span } => node(
+            "While",
+            *span,
+            &[("condition", exprJson(condition)), ("body", statementsJson(body))],
+        ),
+        Stmt::For {
+            variable,
+            start,
+            end,
+            body,
+            span,
+        } => node(
+            "For",
+            *span,
+            &[
+                ("variable", jsonString(variable)),
+                ("start", exprJson(start)),
+                ("end", exprJson(end)),
+                ("body", statementsJson(body)),
+            ],
+        ),
+        Stmt::ForEach {
+            variable,
+            iterable,
+            body,
+            span,
+        } => node(
+            "ForEach",
+            *span,
+            &[
+                ("variable", jsonString(variable)),
+                ("iterable", exprJson(iterable)),
+                ("body", statementsJson(body)),
+            ],
+        ),
+        Stmt::Break { span } => node("Break", *span, &[]),
+        Stmt::Continue { span } => node("Continue", *span, &[]),
+        Stmt::Return { value, span } => node("Return", *span, &[("value", exprJson(value))]),
+        Stmt::Import { path, names, span } => node(
+            "Import",
+            *span,
+            &[
+                ("path", jsonString(path)),
+                (
+                    "names",
+                    names.as_ref().map_or("null".to_string(), |names| {
+                        array(names.iter().map(|name| {
+                            node("ImportedName", name.span, &[("name", jsonString(&name.name))])
+                        }))
+                    }),
+                ),
+            ],
+        ),
+        Stmt::Struct {
+            doc,
+            name,
+            fields,
+            span,
+        } => node(
+            "Struct",
+            *span,
+            &[
+                ("doc", docJson(doc.as_deref())),
+                ("name", jsonString(name)),
+                (
+                    "fields",
+                    array(fields.iter().map(|field| {
+                        node(
+                            "Field",
+                            field.span,
+                            &[
+                                ("doc", docJson(field.doc.as_deref())),
+                                ("name", jsonString(&field.name)),
+                                ("type", typeJson(field.ty.as_ref())),
+                            ],
+                        )
+                    })),
+                ),
+            ],
+        ),
+        Stmt::Impl { name, methods, span } => node(
+            "Impl",
+            *span,
+            &[("name", jsonString(name)), ("methods", statementsJson(methods))],
+        ),
+        Stmt::ExprStmt(expr) => node("ExprStmt", expr.span(), &[("expr", exprJson(expr))]),
+    }
+}
+
+fn exprJson(expr: &Expr) -> String {
+    match expr {
+        Expr::Literal { value, span } => {
+            let value = match value {
+                Literal::String(s) => jsonString(s),
+                Literal::Int(n) => n.to_string(),
+                Literal::BigInt(n) => n.to_string(),
+                Literal::Float(n) if n.is_finite() => floatText(*n),
+                Literal::Float(n) => jsonString(&n.to_string()),
+                Literal::Bool(b) => b.to_string(),
+                Literal::Null => "null".to_string(),
+            };
+            node("Literal", *span, &[("value", value)])
+        }
+        Expr::Variable { name, span } => node("Variable", *span, &[("name", jsonString(name))]),
+        Expr::Binary { left, op, right, span } => node(
+            "Binary",
+            *span,
+            &[
+                ("op", operatorJson(op)),
+                ("left", exprJson(left)),
+                ("right", exprJson(right)),
+            ],
+        ),
+        Expr::Unary { op, right, span } => node(
+            "Unary",
+            *span,
+            &[("op", operatorJson(op)), ("right", exprJson(right))],
+        ),
+        Expr::Assign { target, op, value, span } => node(
+            "Assign",
+            *span,
+            &[
+                ("target", targetJson(target)),
+                ("op", op.as_ref().map_or("null".to_string(), operatorJson)),
+                ("value", exprJson(value)),
+            ],
+        ),
+        Expr::Update { target, op, prefix, span } => node(
+            "Update",
+            *span,
+            &[
+                ("target", targetJson(target)),
+                ("op", operatorJson(op)),
+                ("prefix", prefix.to_string()),
+            ],
+        ),
+        Expr::Call { callee, args, span } => node(
+            "Call",
+            *span,
+            &[("callee", exprJson(callee)), ("args", array(args.iter().map(exprJson)))],
+        ),
+        Expr::Get { object, name, span } => node(
+            "Get",
+            *span,
+            &[("object", exprJson(object)), ("name", jsonString(name))],
+        ),
+        Expr::Array { elements, span } => node(
+            "Array",
+            *span,
+            &[("elements", array(elements.iter().map(exprJson)))],
+        ),
+        Expr::Dict { entries, span } => {
+            let entries = array(entries.iter().map(|(key, value)| {
+                format!("{{\"key\":{},\"value\":{}}}", exprJson(key), exprJson(value))
+            }));
+            node("Dict", *span, &[("entries", entries)])
+        }
+        Expr::Index { object, index, span } => node(
+            "Index",
+            *span,
+            &[("object", exprJson(object)), ("index", exprJson(index))],
+        ),
+        Expr::Lambda {
+            params,
+            return_ty,
+            body,
+            span,
+        } => node(
+            "Lambda",
+            *span,
+            &[
+                ("params", array(params.iter().map(paramJson))),
+                ("returnType", typeJson(return_ty.as_ref())),
+                ("body", statementsJson(body)),
+            ],
+        ),
+        Expr::Interpolation { parts, span } => node(
+            "Interpolation",
+            *span,
+            &[("parts", array(parts.iter().map(exprJson)))],
+        ),
+    }
+}
+
+// targets are written as the expressions they were parsed from
+fn targetJson(target: &Target) -> String {
+    match target {
+        Target::Variable { name, span } => node("Variable", *span, &[("name", jsonString(name))]),
+        Target::Index { object, index, span } => node(
+            "Index",
+            *span,
+            &[("object", exprJson(object)), ("index", exprJson(index))],
+        ),
+        Target::Field { object, name, span } => node(
+            "Get",
+            *span,
+            &[("object", exprJson(object)), ("name", jsonString(name))],
+        ),
+    }
+}
+
+fn paramJson(param: &Parameter) -> String {
+    node(
+        "Parameter",
+        param.span,
+        &[("name", jsonString(&param.name)), ("type", typeJson(param.ty.as_ref()))],
+    )
+}
+
+fn typeJson(ty: Option<&Type>) -> String {
+    ty.map_or("null".to_string(), |ty| jsonString(&ty.name))
+}
+
+fn docJson(doc: Option<&str>) -> String {
+    doc.map_or("null".to_string(), jsonString)
+}
+
+// operators are written as they appear in source, e.g. "+"
+fn operatorJson(op: &TokenKind) -> String {
+    jsonString(op.lexeme().unwrap_or_default())
+}
+
+fn node(name: &str, span: Span, fields: &[(&str, String)]) -> String {
+    let mut out = format!(
+        "{{\"node\":{},\"span\":{{\"start\":{},\"end\":{}}}",
+        jsonString(name),
+        span.start,
+        span.end
+    );
+    for (key, value) in fields {
+        out.push_str(&format!(",{}:{}", jsonString(key), value));
+    }
+    out.push('}');
+    out
+}
+
+fn array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
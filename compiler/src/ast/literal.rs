@@ -1,7 +1,46 @@
+use crate::bigint::BigInt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     String(String),
-    Number(f64),
+    Int(i64),
+    // an int literal too large for 64 bits
+    BigInt(BigInt),
+    Float(f64),
     Bool(bool),
     Null,
 }
+
+// a float written the way source and println show it: whole floats keep a
+// `.0` so `2.0` can't be mistaken for the int `2`
+pub fn floatText(n: f64) -> String {
+    if n.is_finite() && n.fract() == 0.0 {
+        format!("{:.1}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+// a string written as a source literal
+pub fn stringText(s: &str) -> String {
+    format!("\"{}\"", escapeString(s))
+}
+
+// the text of a string literal, escaping what can't appear as is
+pub fn escapeString(s: &str) -> String {
+    let mut text = String::new();
+    for ch in s.chars() {
+        match ch {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '{' => text.push_str("\\{"),
+            '\n' => text.push_str("\\n"),
+            '\t' => text.push_str("\\t"),
+            '\r' => text.push_str("\\r"),
+            '\0' => text.push_str("\\0"),
+            ch if ch.is_control() => text.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+            ch => text.push(ch),
+        }
+    }
+    text
+}
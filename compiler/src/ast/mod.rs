@@ -1,9 +1,10 @@
 pub mod expr;
+pub mod json;
 pub mod literal;
 pub mod stmt;
 pub mod ty;
 
-pub use expr::Expr;
-pub use literal::Literal;
-pub use stmt::{Parameter, Stmt};
+pub use expr::{Expr, Target};
+pub use literal::{escapeString, floatText, stringText, Literal};
+pub use stmt::{Field, ImportedName, Parameter, Stmt};
 pub use ty::Type;
@@ -1,41 +1,126 @@
 use super::{Expr, Type};
+use crate::lexer::span::Span;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Parameter {
     pub name: String,
     pub ty: Option<Type>,
+    pub span: Span,
 }
 
+// a name listed in `import a, b from "file.rey"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedName {
+    pub name: String,
+    pub span: Span,
+}
+
+// a field listed in `struct Name { field: type, ... }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub doc: Option<String>,
+    pub name: String,
+    pub ty: Option<Type>,
+    pub span: Span,
+}
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Stmt {
+    // `doc` holds the `///` lines written above a declaration
     VarDecl {
+        doc: Option<String>,
         name: String,
         ty: Option<Type>,
         initializer: Expr,
+        span: Span,
     },
+    // span covers the function name only, which is where diagnostics point
     FuncDecl {
+        doc: Option<String>,
         name: String,
         params: Vec<Parameter>,
         return_ty: Option<Type>,
         body: Vec<Stmt>,
+        span: Span,
     },
     If {
         condition: Expr,
         then_branch: Vec<Stmt>,
         else_branch: Option<Vec<Stmt>>,
+        span: Span,
     },
     While {
         condition: Expr,
         body: Vec<Stmt>,
+        span: Span,
     },
     For {
         variable: String,
         start: Expr,
         end: Expr,
         body: Vec<Stmt>,
+        span: Span,
+    },
+    // `for x in collection`: array elements or dictionary keys
+    ForEach {
+        variable: String,
+        iterable: Expr,
+        body: Vec<Stmt>,
+        span: Span,
+    },
+    Break {
+        span: Span,
+    },
+    Continue {
+        span: Span,
+    },
+    Return {
+        value: Expr,
+        span: Span,
+    },
+    // `import "file.rey"` binds the module itself; with `names`, only
+    // those exports are bound
+    // span covers the path, which is where diagnostics point
+    Import {
+        path: String,
+        names: Option<Vec<ImportedName>>,
+        span: Span,
+    },
+    // span covers the struct name only, like a function's
+    Struct {
+        doc: Option<String>,
+        name: String,
+        fields: Vec<Field>,
+        span: Span,
+    },
+    // `impl Name { func method(self, ...) { ... } ... }` adds methods to a
+    // struct; `methods` holds only `FuncDecl`s, whose first parameter is
+    // the receiver
+    Impl {
+        name: String,
+        methods: Vec<Stmt>,
+        span: Span,
     },
-    Break,
-    Continue,
-    Return(Expr),
     ExprStmt(Expr),
 }
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::VarDecl { span, .. }
+            | Stmt::FuncDecl { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::For { span, .. }
+            | Stmt::ForEach { span, .. }
+            | Stmt::Break { span }
+            | Stmt::Continue { span }
+            | Stmt::Return { span, .. }
+            | Stmt::Import { span, .. }
+            | Stmt::Struct { span, .. }
+            | Stmt::Impl { span, .. } => *span,
+            Stmt::ExprStmt(expr) => expr.span(),
+        }
+    }
+}
@@ -1,4 +1,7 @@
+use crate::lexer::span::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Type {
     pub name: String,
+    pub span: Span,
 }
@@ -0,0 +1,431 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// an integer of any size, for the int values that don't fit in 64 bits
+// the magnitude is stored in base 2^32, least significant limb first, with
+// no trailing zero limbs; zero has no limbs and is never negative, so equal
+// numbers always have equal representations
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+const BASE: u64 = 1 << 32;
+// the largest power of ten that fits in a limb, for converting to and
+// from decimal nine digits at a time
+const DECIMAL_CHUNK: u32 = 1_000_000_000;
+const DECIMAL_DIGITS: usize = 9;
+
+impl BigInt {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    // `magnitude` is least significant limb first, as returned by `parts`
+    pub fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        trim(&mut magnitude);
+        let negative = negative && !magnitude.is_empty();
+        Self { negative, magnitude }
+    }
+
+    pub fn parts(&self) -> (bool, &[u32]) {
+        (self.negative, &self.magnitude)
+    }
+
+    // decimal digits with an optional sign
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let mut magnitude = Vec::new();
+        // the first chunk takes the leftover digits so the rest are full
+        let first = match digits.len() % DECIMAL_DIGITS {
+            0 => DECIMAL_DIGITS,
+            n => n,
+        };
+        let mut start = 0;
+        let mut end = first;
+        while start < digits.len() {
+            let chunk: u32 = digits[start..end].parse().ok()?;
+            let scale = 10u32.pow((end - start) as u32);
+            mulSmallAdd(&mut magnitude, scale, chunk);
+            start = end;
+            end += DECIMAL_DIGITS;
+        }
+        Some(Self::from_parts(negative, magnitude))
+    }
+
+    // digits in base 2 to 36, without a sign or prefix
+    pub fn parse_radix(digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+        let mut magnitude = Vec::new();
+        for ch in digits.chars() {
+            mulSmallAdd(&mut magnitude, radix, ch.to_digit(radix)?);
+        }
+        Some(Self::from_parts(false, magnitude))
+    }
+
+    // the integer a float is exactly equal to, if it is a whole number
+    pub fn from_f64(n: f64) -> Option<Self> {
+        if !n.is_finite() || n.fract() != 0.0 {
+            return None;
+        }
+        if n == 0.0 {
+            return Some(Self::zero());
+        }
+        // a whole float other than zero is normal, so its value is the
+        // 53-bit mantissa with the implicit leading one, scaled by a power of two
+        let bits = n.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i64 - 1075;
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let magnitude = if exponent >= 0 {
+            shiftLeft(&[mantissa as u32, (mantissa >> 32) as u32], exponent as usize)
+        } else {
+            let shifted = mantissa >> -exponent;
+            vec![shifted as u32, (shifted >> 32) as u32]
+        };
+        Some(Self::from_parts(n < 0.0, magnitude))
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let low = self.magnitude.first().copied().unwrap_or(0) as u64;
+        let high = self.magnitude.get(1).copied().unwrap_or(0) as u64;
+        let value = (high << 32) | low;
+        if self.negative {
+            // i64::MIN has no positive counterpart, so negate in i128
+            i64::try_from(-(value as i128)).ok()
+        } else {
+            i64::try_from(value).ok()
+        }
+    }
+
+    // the nearest float; too large a magnitude gives an infinity
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.magnitude.iter().rev().fold(0.0, |total, &limb| total * BASE as f64 + limb as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::from_parts(false, self.magnitude.clone())
+    }
+
+    // quotient rounded toward zero, and the remainder with the sign of
+    // the dividend; None when dividing by zero
+    pub fn div_rem(&self, divisor: &Self) -> Option<(Self, Self)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = divRem(&self.magnitude, &divisor.magnitude);
+        Some((
+            Self::from_parts(self.negative != divisor.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut result = Self::from(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> Self {
+        let magnitude = n.unsigned_abs();
+        Self::from_parts(n < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, addMagnitudes(&self.magnitude, &other.magnitude));
+        }
+        // opposite signs: the larger magnitude decides the sign
+        match compareMagnitudes(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(other.negative, subMagnitudes(&other.magnitude, &self.magnitude)),
+            _ => BigInt::from_parts(self.negative, subMagnitudes(&self.magnitude, &other.magnitude)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(self.negative != other.negative, mulMagnitudes(&self.magnitude, &other.magnitude))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compareMagnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => compareMagnitudes(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // peel off nine digits at a time, least significant first
+        let mut chunks = Vec::new();
+        let mut rest = self.magnitude.clone();
+        while !rest.is_empty() {
+            let (quotient, remainder) = divRemSmall(&rest, DECIMAL_CHUNK);
+            chunks.push(remainder);
+            rest = quotient;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn compareMagnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn addMagnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut sum = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &limb) in long.iter().enumerate() {
+        let total = limb as u64 + short.get(i).copied().unwrap_or(0) as u64 + carry;
+        sum.push(total as u32);
+        carry = total >> 32;
+    }
+    if carry > 0 {
+        sum.push(carry as u32);
+    }
+    sum
+}
+
+// `a - b` where `a` is at least `b`
+fn subMagnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &limb) in a.iter().enumerate() {
+        let mut total = limb as i64 - b.get(i).copied().unwrap_or(0) as i64 - borrow;
+        borrow = 0;
+        if total < 0 {
+            total += BASE as i64;
+            borrow = 1;
+        }
+        difference.push(total as u32);
+    }
+    trim(&mut difference);
+    difference
+}
+
+fn mulMagnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let total = product[i + j] as u64 + x as u64 * y as u64 + carry;
+            product[i + j] = total as u32;
+            carry = total >> 32;
+        }
+        product[i + b.len()] = carry as u32;
+    }
+    trim(&mut product);
+    product
+}
+
+// magnitude * factor + addend, in place
+fn mulSmallAdd(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for limb in magnitude.iter_mut() {
+        let total = *limb as u64 * factor as u64 + carry;
+        *limb = total as u32;
+        carry = total >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn divRemSmall(dividend: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder = 0u64;
+    for (i, &limb) in dividend.iter().enumerate().rev() {
+        let current = (remainder << 32) | limb as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    trim(&mut quotient);
+    (quotient, remainder as u32)
+}
+
+fn shiftLeft(magnitude: &[u32], bits: usize) -> Vec<u32> {
+    let (limbs, bits) = (bits / 32, bits % 32);
+    let mut shifted = vec![0u32; limbs];
+    let mut carry = 0u32;
+    for &limb in magnitude {
+        if bits == 0 {
+            shifted.push(limb);
+        } else {
+            shifted.push((limb << bits) | carry);
+            carry = limb >> (32 - bits);
+        }
+    }
+    if carry > 0 {
+        shifted.push(carry);
+    }
+    trim(&mut shifted);
+    shifted
+}
+
+// long division of magnitudes (Knuth, TAOCP vol. 2, 4.3.1, algorithm D)
+fn divRem(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if compareMagnitudes(dividend, divisor) == Ordering::Less {
+        return (Vec::new(), dividend.to_vec());
+    }
+    if divisor.len() == 1 {
+        let (quotient, remainder) = divRemSmall(dividend, divisor[0]);
+        let mut remainder = vec![remainder];
+        trim(&mut remainder);
+        return (quotient, remainder);
+    }
+
+    // normalize so the divisor's top limb has its high bit set, which keeps
+    // each estimated quotient digit at most two too large
+    let shift = divisor[divisor.len() - 1].leading_zeros() as usize;
+    let v = shiftLeft(divisor, shift);
+    let mut u = shiftLeft(dividend, shift);
+    u.resize(dividend.len() + 1, 0);
+    let n = v.len();
+    let m = dividend.len() - n;
+    let mut quotient = vec![0u32; m + 1];
+
+    for j in (0..=m).rev() {
+        let top = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let mut estimate = top / v[n - 1] as u64;
+        let mut rest = top % v[n - 1] as u64;
+        while estimate >= BASE || estimate * v[n - 2] as u64 > ((rest << 32) | u[j + n - 2] as u64) {
+            estimate -= 1;
+            rest += v[n - 1] as u64;
+            if rest >= BASE {
+                break;
+            }
+        }
+
+        // subtract estimate * v from the current window of u
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0..n {
+            let product = estimate * v[i] as u64 + carry;
+            carry = product >> 32;
+            let total = u[i + j] as i64 - borrow - (product & 0xffff_ffff) as i64;
+            u[i + j] = total as u32;
+            borrow = if total < 0 { 1 } else { 0 };
+        }
+        let total = u[j + n] as i64 - borrow - carry as i64;
+        u[j + n] = total as u32;
+
+        // the estimate was one too large: add v back
+        if total < 0 {
+            estimate -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = estimate as u32;
+    }
+
+    trim(&mut quotient);
+    // undo the normalization on what is left of u
+    let mut remainder = vec![0u32; n];
+    for i in 0..n {
+        remainder[i] = if shift == 0 {
+            u[i]
+        } else {
+            (u[i] >> shift) | (u[i + 1] << (32 - shift))
+        };
+    }
+    trim(&mut remainder);
+    (quotient, remainder)
+}
@@ -0,0 +1,6 @@
+pub mod bigint;
+
+pub use bigint::BigInt;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,182 @@
+use super::BigInt;
+
+fn big(text: &str) -> BigInt {
+    BigInt::parse(text).expect("valid decimal")
+}
+
+// i128 arithmetic is exact over every value these tests build, so it is the
+// reference the limb arithmetic is checked against
+fn wide(n: i128) -> BigInt {
+    big(&n.to_string())
+}
+
+// a fixed spread of values from one to four limbs, both signs
+fn samples() -> Vec<i128> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut samples = vec![1, -1, u32::MAX as i128, 1 << 32, i64::MIN as i128, i64::MAX as i128, 1 << 95, -(1 << 126)];
+    for i in 0..60 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let high = (state as i128) << 63;
+        let value = (high ^ state as i128) >> (i * 2);
+        samples.push(if i % 3 == 0 { -value } else { value });
+    }
+    samples.retain(|&n| n != 0);
+    samples
+}
+
+#[test]
+fn addition_carries_across_limbs() {
+    let sum = &wide(u32::MAX as i128) + &BigInt::from(1);
+    assert_eq!(sum.parts(), (false, &[0, 1][..]));
+
+    let sum = &wide(u64::MAX as i128) + &BigInt::from(1);
+    assert_eq!(sum.parts(), (false, &[0, 0, 1][..]));
+    assert_eq!(sum.to_string(), "18446744073709551616");
+}
+
+#[test]
+fn subtraction_borrows_across_limbs() {
+    let difference = &wide(1 << 64) - &BigInt::from(1);
+    assert_eq!(difference.parts(), (false, &[u32::MAX, u32::MAX][..]));
+
+    // equal magnitudes cancel to a zero that is not negative
+    let difference = &wide(-(1 << 64)) + &wide(1 << 64);
+    assert_eq!(difference, BigInt::zero());
+    assert!(!difference.is_negative());
+}
+
+#[test]
+fn multiplication_carries_across_limbs() {
+    let max = wide(u64::MAX as i128);
+    assert_eq!((&max * &max).to_string(), (u64::MAX as u128 * u64::MAX as u128).to_string());
+    assert_eq!(big("-4294967296").pow(3).to_string(), "-79228162514264337593543950336");
+}
+
+#[test]
+fn arithmetic_matches_i128() {
+    let samples = samples();
+    for &a in &samples {
+        for &b in &samples {
+            if let Some(sum) = a.checked_add(b) {
+                assert_eq!(&wide(a) + &wide(b), wide(sum), "{} + {}", a, b);
+            }
+            if let Some(difference) = a.checked_sub(b) {
+                assert_eq!(&wide(a) - &wide(b), wide(difference), "{} - {}", a, b);
+            }
+            if let Some(product) = a.checked_mul(b) {
+                assert_eq!(&wide(a) * &wide(b), wide(product), "{} * {}", a, b);
+            }
+            assert_eq!(wide(a).cmp(&wide(b)), a.cmp(&b), "{} <=> {}", a, b);
+        }
+    }
+}
+
+#[test]
+fn division_truncates_toward_zero() {
+    for (a, b) in [(7, 2), (-7, 2), (7, -2), (-7, -2), (1 << 100, 3), (-(1 << 100), 3), (3, 1 << 100), (-3, 1 << 100)] {
+        let (quotient, remainder) = wide(a).div_rem(&wide(b)).expect("non-zero divisor");
+        assert_eq!(quotient, wide(a / b), "{} / {}", a, b);
+        // the remainder takes the sign of the dividend
+        assert_eq!(remainder, wide(a % b), "{} % {}", a, b);
+    }
+}
+
+#[test]
+fn division_matches_i128() {
+    let samples = samples();
+    for &a in &samples {
+        for &b in &samples {
+            let (quotient, remainder) = wide(a).div_rem(&wide(b)).expect("non-zero divisor");
+            assert_eq!(quotient, wide(a / b), "{} / {}", a, b);
+            assert_eq!(remainder, wide(a % b), "{} % {}", a, b);
+        }
+    }
+}
+
+#[test]
+fn division_corrects_an_estimate_that_is_too_large() {
+    // a quotient digit estimated one too large, so the divisor is added back
+    let dividend = BigInt::from_parts(false, vec![0, 0, 0x8000_0000, 0x7fff_ffff]);
+    let divisor = BigInt::from_parts(false, vec![1, 0, 0x8000_0000]);
+    let (quotient, remainder) = dividend.div_rem(&divisor).expect("non-zero divisor");
+    assert_eq!(quotient.parts(), (false, &[0xffff_fffe][..]));
+    assert_eq!(remainder.parts(), (false, &[2, 0xffff_ffff, 0x7fff_ffff][..]));
+}
+
+#[test]
+fn division_by_zero_has_no_result() {
+    assert_eq!(big("12345678901234567890").div_rem(&BigInt::zero()), None);
+}
+
+#[test]
+fn i64_min_edges() {
+    let min = BigInt::from(i64::MIN);
+    assert_eq!(min.to_string(), "-9223372036854775808");
+    assert_eq!(min.to_i64(), Some(i64::MIN));
+    assert_eq!(big("-9223372036854775808").to_i64(), Some(i64::MIN));
+
+    // one past either end no longer fits
+    assert_eq!((-&min).to_string(), "9223372036854775808");
+    assert_eq!((-&min).to_i64(), None);
+    assert_eq!((&min - &BigInt::from(1)).to_i64(), None);
+    assert_eq!((&BigInt::from(i64::MAX) + &BigInt::from(1)).to_i64(), None);
+
+    let (quotient, remainder) = min.div_rem(&BigInt::from(-1)).expect("non-zero divisor");
+    assert_eq!(quotient.to_string(), "9223372036854775808");
+    assert_eq!(remainder, BigInt::zero());
+    assert_eq!(min.abs(), -&min);
+}
+
+#[test]
+fn decimal_round_trips() {
+    for text in [
+        "0",
+        "1",
+        "-1",
+        "999999999",
+        "1000000000",
+        "-1000000000000000000",
+        "4294967295",
+        "4294967296",
+        "340282366920938463463374607431768211456",
+        "-123456789012345678901234567890123456789",
+    ] {
+        assert_eq!(big(text).to_string(), text);
+    }
+}
+
+#[test]
+fn decimal_parsing_normalizes() {
+    assert_eq!(big("-0").to_string(), "0");
+    assert!(!big("-0").is_negative());
+    assert_eq!(big("+42").to_string(), "42");
+    assert_eq!(big("000000000000123").to_string(), "123");
+    for text in ["", "-", "+", "1_000", "12a", "--1", " 1"] {
+        assert_eq!(BigInt::parse(text), None, "{:?}", text);
+    }
+}
+
+#[test]
+fn radix_parsing() {
+    assert_eq!(BigInt::parse_radix("ff", 16), Some(BigInt::from(255)));
+    assert_eq!(BigInt::parse_radix("FF", 16), Some(BigInt::from(255)));
+    assert_eq!(BigInt::parse_radix(&"1".repeat(64), 2), Some(wide(u64::MAX as i128)));
+    assert_eq!(BigInt::parse_radix("1000000000000000000000", 16), Some(wide(1 << 84)));
+    assert_eq!(BigInt::parse_radix("z", 36), Some(BigInt::from(35)));
+    assert_eq!(BigInt::parse_radix("2", 2), None);
+    assert_eq!(BigInt::parse_radix("", 10), None);
+}
+
+#[test]
+fn whole_floats_convert_exactly() {
+    assert_eq!(BigInt::from_f64(2f64.powi(80)), Some(wide(1 << 80)));
+    assert_eq!(BigInt::from_f64(-3.0), Some(BigInt::from(-3)));
+    assert_eq!(BigInt::from_f64(9007199254740993.0), Some(BigInt::from(9007199254740992)));
+    assert_eq!(BigInt::from_f64(-0.0), Some(BigInt::zero()));
+    assert_eq!(BigInt::from_f64(1e300).map(|n| n.to_f64()), Some(1e300));
+    for n in [0.5, -2.25, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert_eq!(BigInt::from_f64(n), None, "{}", n);
+    }
+}
@@ -0,0 +1,697 @@
+use std::collections::HashMap;
+
+use super::error::TypeError;
+use super::types::{FunctionSignature, TypeKind};
+use crate::ast::{Expr, Field, Literal, Parameter, Stmt, Target, Type};
+use crate::lexer::span::Span;
+use crate::lexer::TokenKind;
+use crate::modules::moduleName;
+
+// type of a name in scope and, when annotated, where the annotation is
+struct Binding {
+    ty: TypeKind,
+    annotation: Option<Span>,
+}
+
+// the names declared in one function body, or at the top level; blocks
+// share the scope of their function, as they do at runtime
+#[derive(Default)]
+struct Scope {
+    bindings: HashMap<String, Binding>,
+    // where the code being checked declared each name
+    declarations: HashMap<String, Span>,
+}
+
+// what is known of a declared struct
+#[derive(Default)]
+struct StructInfo {
+    // each field's type and, when annotated, where the annotation is
+    fields: Vec<(String, TypeKind, Option<Span>)>,
+    // signatures without the receiver
+    methods: HashMap<String, FunctionSignature>,
+}
+
+// return type of the function whose body is being checked
+struct FunctionContext {
+    name: String,
+    return_ty: TypeKind,
+    annotation: Option<Span>,
+}
+
+//static pass run between parsing and interpretation
+//enforces annotated variables, parameters and return types
+pub struct TypeChecker {
+    scopes: Vec<Scope>,
+    // number of blocks around the statement being checked
+    nesting: usize,
+    functions: Vec<FunctionContext>,
+    // structs by name, which is how annotations refer to them
+    structs: HashMap<String, StructInfo>,
+    errors: Vec<TypeError>,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![Scope::default()],
+            nesting: 0,
+            functions: Vec::new(),
+            structs: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn check(&mut self, statements: &[Stmt]) -> Result<(), Vec<TypeError>> {
+        // names kept from earlier REPL entries may be declared again
+        self.scopes[0].declarations.clear();
+        self.checkBody(statements);
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    // every name a body declares is in scope from its start, so functions
+    // can be called, and variables assigned from closures, before their
+    // declarations
+    fn checkBody(&mut self, statements: &[Stmt]) {
+        self.declare(statements);
+        for stmt in statements {
+            self.checkStatement(stmt);
+        }
+    }
+
+    // structs come first, since annotations in the other declarations may
+    // name them; the declarations of nested blocks belong to this body too
+    fn declare(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            if let Stmt::Struct { name, .. } = stmt {
+                self.structs.insert(name.clone(), StructInfo::default());
+            }
+        }
+        for stmt in statements {
+            if let Stmt::Struct { name, fields, span, .. } = stmt {
+                self.hoistStruct(name, fields, *span);
+            }
+        }
+        for stmt in statements {
+            if let Stmt::Impl { name, methods, span } = stmt {
+                self.hoistMethods(name, methods, *span);
+            }
+        }
+        for stmt in statements {
+            match stmt {
+                Stmt::FuncDecl {
+                    name,
+                    params,
+                    return_ty,
+                    span,
+                    ..
+                } => {
+                    for ty in params.iter().filter_map(|param| param.ty.as_ref()) {
+                        self.resolveAnnotation(Some(ty));
+                    }
+                    self.resolveAnnotation(return_ty.as_ref());
+                    let ty = TypeKind::Function(self.signature(params, return_ty));
+                    self.declareName(name, ty, None, *span);
+                }
+                Stmt::VarDecl { name, ty, span, .. } => {
+                    let declared = self.resolveAnnotation(ty.as_ref());
+                    self.declareName(name, declared, ty.as_ref().map(|ty| ty.span), *span);
+                }
+                Stmt::If {
+                    then_branch,
+                    else_branch,
+                    ..
+                } => {
+                    self.declare(then_branch);
+                    if let Some(else_branch) = else_branch {
+                        self.declare(else_branch);
+                    }
+                }
+                Stmt::While { body, .. } | Stmt::For { body, .. } | Stmt::ForEach { body, .. } => {
+                    self.declare(body);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn checkBlock(&mut self, statements: &[Stmt]) {
+        self.nesting += 1;
+        for stmt in statements {
+            self.checkStatement(stmt);
+        }
+        self.nesting -= 1;
+    }
+
+    fn checkStatement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl {
+                name,
+                ty,
+                initializer,
+                ..
+            } => {
+                // the annotation was already reported while declaring
+                let found = self.inferExpr(initializer);
+                let declared = self.annotationType(ty.as_ref());
+                if !declared.accepts(&found) {
+                    self.errors.push(TypeError::IncompatibleAssignment {
+                        name: name.clone(),
+                        expected: declared.clone(),
+                        found,
+                        span: initializer.span(),
+                        declared: ty.as_ref().map(|ty| ty.span),
+                    });
+                }
+            }
+            Stmt::FuncDecl {
+                name,
+                params,
+                return_ty,
+                body,
+                span,
+                ..
+            } => {
+                // annotations were already reported while hoisting
+                self.checkFunction(name, params, return_ty, body, *span);
+            }
+            // fields and method signatures were taken in while hoisting
+            Stmt::Struct { .. } => {}
+            Stmt::Impl { name, methods, .. } => {
+                for method in methods {
+                    if let Stmt::FuncDecl {
+                        name: method,
+                        params,
+                        return_ty,
+                        body,
+                        span,
+                        ..
+                    } = method
+                    {
+                        let params = Self::withReceiver(name, params);
+                        self.checkFunction(method, &params, return_ty, body, *span);
+                    }
+                }
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.inferExpr(condition);
+                self.checkBlock(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.checkBlock(else_branch);
+                }
+            }
+            Stmt::While { condition, body, .. } => {
+                self.inferExpr(condition);
+                self.checkBlock(body);
+            }
+            Stmt::For {
+                variable,
+                start,
+                end,
+                body,
+                span,
+            } => {
+                self.inferExpr(start);
+                self.inferExpr(end);
+                self.defineLoopVariable(variable, TypeKind::Int, *span);
+                self.checkBlock(body);
+            }
+            Stmt::ForEach {
+                variable,
+                iterable,
+                body,
+                span,
+            } => {
+                self.inferExpr(iterable);
+                self.defineLoopVariable(variable, TypeKind::Unknown, *span);
+                self.checkBlock(body);
+            }
+            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            Stmt::Return { value, .. } => {
+                let found = self.inferExpr(value);
+                if let Some(function) = self.functions.last() {
+                    if !function.return_ty.accepts(&found) {
+                        self.errors.push(TypeError::IncompatibleReturn {
+                            function: function.name.clone(),
+                            expected: function.return_ty.clone(),
+                            found,
+                            span: value.span(),
+                            declared: function.annotation,
+                        });
+                    }
+                }
+            }
+            Stmt::Import { path, names, span } => {
+                if self.scopes.len() > 1 || self.nesting > 0 {
+                    self.errors.push(TypeError::MisplacedImport { span: *span });
+                }
+                // what a module exports is only known when it runs
+                match names {
+                    Some(names) => {
+                        for imported in names {
+                            self.define(&imported.name, TypeKind::Unknown, None);
+                        }
+                    }
+                    None => {
+                        if let Some(name) = moduleName(path) {
+                            self.define(&name, TypeKind::Unknown, None);
+                        }
+                    }
+                }
+            }
+            Stmt::ExprStmt(expr) => {
+                self.inferExpr(expr);
+            }
+        }
+    }
+
+    // the struct's constructor takes the fields in order
+    fn hoistStruct(&mut self, name: &str, fields: &[Field], span: Span) {
+        let fields: Vec<(String, TypeKind, Option<Span>)> = fields
+            .iter()
+            .map(|field| {
+                let ty = self.resolveAnnotation(field.ty.as_ref());
+                (field.name.clone(), ty, field.ty.as_ref().map(|ty| ty.span))
+            })
+            .collect();
+        let constructor = FunctionSignature {
+            params: fields.iter().map(|(_, ty, _)| ty.clone()).collect(),
+            return_ty: Box::new(TypeKind::Struct(name.to_string())),
+        };
+        self.declareName(name, TypeKind::Function(constructor), None, span);
+        if let Some(info) = self.structs.get_mut(name) {
+            info.fields = fields;
+        }
+    }
+
+    fn hoistMethods(&mut self, name: &str, methods: &[Stmt], span: Span) {
+        if !self.structs.contains_key(name) {
+            self.errors.push(TypeError::UnknownType {
+                name: name.to_string(),
+                span,
+            });
+            return;
+        }
+        for method in methods {
+            if let Stmt::FuncDecl {
+                name: method,
+                params,
+                return_ty,
+                ..
+            } = method
+            {
+                for ty in params.iter().filter_map(|param| param.ty.as_ref()) {
+                    self.resolveAnnotation(Some(ty));
+                }
+                self.resolveAnnotation(return_ty.as_ref());
+                let mut signature = self.signature(params, return_ty);
+                signature.params.remove(0);
+                if let Some(info) = self.structs.get_mut(name) {
+                    info.methods.insert(method.clone(), signature);
+                }
+            }
+        }
+    }
+
+    // a method's parameters, with the receiver typed as the struct unless
+    // it is annotated
+    fn withReceiver(name: &str, params: &[Parameter]) -> Vec<Parameter> {
+        let mut params = params.to_vec();
+        if let Some(receiver) = params.first_mut() {
+            receiver.ty.get_or_insert(Type {
+                name: name.to_string(),
+                span: receiver.span,
+            });
+        }
+        params
+    }
+
+    // the type of `ty.name`, for a field or a method; None for neither
+    fn memberType(&self, ty: &str, name: &str) -> Option<TypeKind> {
+        let info = self.structs.get(ty)?;
+        if let Some((_, field, _)) = info.fields.iter().find(|(field, _, _)| field == name) {
+            return Some(field.clone());
+        }
+        info.methods.get(name).map(|signature| TypeKind::Function(signature.clone()))
+    }
+
+    // checks a function body against its annotations and returns its signature
+    fn checkFunction(&mut self, name: &str, params: &[Parameter], return_ty: &Option<Type>, body: &[Stmt], span: Span) -> FunctionSignature {
+        let signature = self.signature(params, return_ty);
+
+        self.scopes.push(Scope::default());
+        for (param, ty) in params.iter().zip(&signature.params) {
+            self.define(&param.name, ty.clone(), param.ty.as_ref().map(|ty| ty.span));
+        }
+        let expected = (*signature.return_ty).clone();
+        let annotation = return_ty.as_ref().map(|ty| ty.span);
+        self.functions.push(FunctionContext {
+            name: name.to_string(),
+            return_ty: expected.clone(),
+            annotation,
+        });
+        self.checkBody(body);
+        self.functions.pop();
+        self.scopes.pop();
+
+        let needsValue = return_ty.is_some()
+            && !matches!(expected, TypeKind::Void | TypeKind::Null | TypeKind::Unknown);
+        if needsValue && !Self::alwaysReturns(body) {
+            self.errors.push(TypeError::MissingReturn {
+                function: name.to_string(),
+                expected,
+                span,
+                declared: annotation,
+            });
+        }
+        signature
+    }
+
+    fn inferExpr(&mut self, expr: &Expr) -> TypeKind {
+        match expr {
+            Expr::Literal { value, .. } => match value {
+                Literal::String(_) => TypeKind::String,
+                Literal::Int(_) | Literal::BigInt(_) => TypeKind::Int,
+                Literal::Float(_) => TypeKind::Float,
+                Literal::Bool(_) => TypeKind::Bool,
+                Literal::Null => TypeKind::Null,
+            },
+            Expr::Variable { name, .. } => self
+                .lookup(name)
+                .map(|binding| binding.ty.clone())
+                .unwrap_or(TypeKind::Unknown),
+            Expr::Binary { left, op, right, .. } => {
+                let left = self.inferExpr(left);
+                let right = self.inferExpr(right);
+                Self::binaryResult(&left, op, &right)
+            }
+            Expr::Unary { op, right, .. } => {
+                let right = self.inferExpr(right);
+                match op {
+                    TokenKind::Minus if right.is_numeric() => right,
+                    TokenKind::Tilde if right == TypeKind::Int => right,
+                    TokenKind::Bang => TypeKind::Bool,
+                    _ => TypeKind::Unknown,
+                }
+            }
+            Expr::Assign { target, op, value, .. } => {
+                let binding = self.targetBinding(target);
+                let mut found = self.inferExpr(value);
+                if let Some(op) = op {
+                    let current = binding.as_ref().map_or(TypeKind::Unknown, |(_, binding)| binding.ty.clone());
+                    let result = Self::binaryResult(&current, op, &found);
+                    // both sides are known, so the operator can't take them
+                    if let (Some((name, binding)), TypeKind::Unknown) = (&binding, &result) {
+                        if current != TypeKind::Unknown && found != TypeKind::Unknown {
+                            self.errors.push(TypeError::InvalidOperator {
+                                op: format!("{}=", op.lexeme().unwrap_or_default()),
+                                name: name.clone(),
+                                ty: current,
+                                operand: Some(found),
+                                span: value.span(),
+                                declared: binding.annotation,
+                            });
+                        }
+                    }
+                    found = result;
+                }
+                if let Some((name, binding)) = binding {
+                    if !binding.ty.accepts(&found) {
+                        self.errors.push(TypeError::IncompatibleAssignment {
+                            name,
+                            expected: binding.ty,
+                            found: found.clone(),
+                            span: value.span(),
+                            declared: binding.annotation,
+                        });
+                    }
+                }
+                found
+            }
+            Expr::Update { target, op, span, .. } => match self.targetBinding(target) {
+                Some((_, binding)) if binding.ty.is_numeric() => binding.ty,
+                Some((name, binding)) if binding.ty != TypeKind::Unknown => {
+                    self.errors.push(TypeError::InvalidOperator {
+                        op: op.lexeme().unwrap_or_default().to_string(),
+                        name,
+                        ty: binding.ty,
+                        operand: None,
+                        span: *span,
+                        declared: binding.annotation,
+                    });
+                    TypeKind::Unknown
+                }
+                _ => TypeKind::Unknown,
+            },
+            Expr::Call { callee, args, .. } => {
+                let calleeTy = self.inferExpr(callee);
+                let argTypes: Vec<(TypeKind, Span)> = args
+                    .iter()
+                    .map(|arg| (self.inferExpr(arg), arg.span()))
+                    .collect();
+
+                let signature = match calleeTy {
+                    TypeKind::Function(signature) => signature,
+                    _ => return TypeKind::Unknown,
+                };
+                let function = match callee.as_ref() {
+                    Expr::Variable { name, .. } | Expr::Get { name, .. } => name.clone(),
+                    _ => "<function>".to_string(),
+                };
+                for (i, (expected, (found, span))) in signature.params.iter().zip(argTypes).enumerate() {
+                    if !expected.accepts(&found) {
+                        self.errors.push(TypeError::IncompatibleArgument {
+                            function: function.clone(),
+                            position: i + 1,
+                            expected: expected.clone(),
+                            found,
+                            span,
+                        });
+                    }
+                }
+                match *signature.return_ty {
+                    // a Void function evaluates to null at runtime
+                    TypeKind::Void => TypeKind::Null,
+                    ty => ty,
+                }
+            }
+            // a method reads as a function without its receiver
+            Expr::Get { object, name, span } => match self.inferExpr(object) {
+                TypeKind::Struct(ty) => self.memberType(&ty, name).unwrap_or_else(|| {
+                    self.errors.push(TypeError::UnknownField {
+                        ty,
+                        name: name.clone(),
+                        span: *span,
+                    });
+                    TypeKind::Unknown
+                }),
+                _ => TypeKind::Unknown,
+            },
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.inferExpr(element);
+                }
+                TypeKind::Array
+            }
+            Expr::Dict { entries, .. } => {
+                for (key, value) in entries {
+                    self.inferExpr(key);
+                    self.inferExpr(value);
+                }
+                TypeKind::Dict
+            }
+            // elements are untyped, so indexing yields a dynamic value
+            Expr::Index { object, index, .. } => {
+                self.inferExpr(object);
+                self.inferExpr(index);
+                TypeKind::Unknown
+            }
+            Expr::Lambda {
+                params,
+                return_ty,
+                body,
+                span,
+            } => {
+                for ty in params.iter().filter_map(|param| param.ty.as_ref()) {
+                    self.resolveAnnotation(Some(ty));
+                }
+                self.resolveAnnotation(return_ty.as_ref());
+                TypeKind::Function(self.checkFunction("<lambda>", params, return_ty, body, *span))
+            }
+            // any value can be shown, so the parts only need checking
+            Expr::Interpolation { parts, .. } => {
+                for part in parts {
+                    self.inferExpr(part);
+                }
+                TypeKind::String
+            }
+        }
+    }
+
+    // the binding an assignment to `target` is checked against, named the
+    // way diagnostics refer to it; elements are untyped, so they have none
+    fn targetBinding(&mut self, target: &Target) -> Option<(String, Binding)> {
+        match target {
+            Target::Variable { name, .. } => self.lookup(name).map(|binding| {
+                let binding = Binding {
+                    ty: binding.ty.clone(),
+                    annotation: binding.annotation,
+                };
+                (name.clone(), binding)
+            }),
+            Target::Index { object, index, .. } => {
+                self.inferExpr(object);
+                self.inferExpr(index);
+                None
+            }
+            Target::Field { object, name, span } => {
+                let ty = match self.inferExpr(object) {
+                    TypeKind::Struct(ty) => ty,
+                    _ => return None,
+                };
+                let field = self.structs.get(&ty).and_then(|info| info.fields.iter().find(|(field, _, _)| field == name));
+                match field {
+                    Some((_, fieldTy, annotation)) => {
+                        let binding = Binding {
+                            ty: fieldTy.clone(),
+                            annotation: *annotation,
+                        };
+                        Some((format!("{}.{}", ty, name), binding))
+                    }
+                    None => {
+                        self.errors.push(TypeError::UnknownField {
+                            ty,
+                            name: name.clone(),
+                            span: *span,
+                        });
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    fn binaryResult(left: &TypeKind, op: &TokenKind, right: &TypeKind) -> TypeKind {
+        use TokenKind::*;
+
+        match op {
+            // ints stay ints, and an int mixed with a float is promoted
+            Plus | Minus | Star | Slash | Percent => match (left, right) {
+                (TypeKind::Int, TypeKind::Int) => TypeKind::Int,
+                (l, r) if l.is_numeric() && r.is_numeric() => TypeKind::Float,
+                (TypeKind::String, TypeKind::String) if *op == Plus => TypeKind::String,
+                _ => TypeKind::Unknown,
+            },
+            EqualEqual | BangEqual | Less | LessEqual | Greater | GreaterEqual | AndAnd | OrOr => {
+                TypeKind::Bool
+            }
+            _ => TypeKind::Unknown,
+        }
+    }
+
+    // true when every path through `statements` ends in a return
+    fn alwaysReturns(statements: &[Stmt]) -> bool {
+        statements.iter().any(|stmt| match stmt {
+            Stmt::Return { .. } => true,
+            Stmt::If {
+                then_branch,
+                else_branch: Some(else_branch),
+                ..
+            } => Self::alwaysReturns(then_branch) && Self::alwaysReturns(else_branch),
+            _ => false,
+        })
+    }
+
+    fn signature(&self, params: &[Parameter], return_ty: &Option<Type>) -> FunctionSignature {
+        FunctionSignature {
+            params: params
+                .iter()
+                .map(|param| self.annotationType(param.ty.as_ref()))
+                .collect(),
+            return_ty: Box::new(self.annotationType(return_ty.as_ref())),
+        }
+    }
+
+    // built-in names first, then declared structs; unannotated and unknown
+    // names are both treated as dynamic
+    fn annotationType(&self, ty: Option<&Type>) -> TypeKind {
+        ty.and_then(|ty| {
+            TypeKind::from_annotation(&ty.name)
+                .or_else(|| self.structs.contains_key(&ty.name).then(|| TypeKind::Struct(ty.name.clone())))
+        })
+        .unwrap_or(TypeKind::Unknown)
+    }
+
+    fn resolveAnnotation(&mut self, ty: Option<&Type>) -> TypeKind {
+        if let Some(ty) = ty {
+            if TypeKind::from_annotation(&ty.name).is_none() && !self.structs.contains_key(&ty.name) {
+                self.errors.push(TypeError::UnknownType {
+                    name: ty.name.clone(),
+                    span: ty.span,
+                });
+            }
+        }
+        self.annotationType(ty)
+    }
+
+    fn define(&mut self, name: &str, ty: TypeKind, annotation: Option<Span>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.bindings.insert(name.to_string(), Binding { ty, annotation });
+        }
+    }
+
+    // a later declaration replaces an earlier one at runtime, so declaring
+    // a name twice in one scope is an error once either declaration types
+    // it; the first declaration is kept
+    fn declareName(&mut self, name: &str, ty: TypeKind, annotation: Option<Span>, span: Span) {
+        let scope = match self.scopes.last_mut() {
+            Some(scope) => scope,
+            None => return,
+        };
+        if let Some(previous) = scope.declarations.get(name) {
+            let typed = ty != TypeKind::Unknown
+                || scope.bindings.get(name).is_some_and(|binding| binding.ty != TypeKind::Unknown);
+            if typed {
+                self.errors.push(TypeError::Redeclaration {
+                    name: name.to_string(),
+                    span,
+                    previous: *previous,
+                });
+                return;
+            }
+        }
+        scope.declarations.insert(name.to_string(), span);
+        scope.bindings.insert(name.to_string(), Binding { ty, annotation });
+    }
+
+    // each iteration assigns the loop variable, which lives on after the
+    // loop like any other variable of the function
+    fn defineLoopVariable(&mut self, name: &str, ty: TypeKind, span: Span) {
+        let existing = self.scopes.last().and_then(|scope| scope.bindings.get(name));
+        match existing {
+            Some(binding) if !binding.ty.accepts(&ty) => {
+                self.errors.push(TypeError::IncompatibleAssignment {
+                    name: name.to_string(),
+                    expected: binding.ty.clone(),
+                    found: ty,
+                    span,
+                    declared: binding.annotation,
+                });
+            }
+            Some(_) => {}
+            None => self.define(name, ty, None),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.bindings.get(name))
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+
+use super::types::TypeKind;
+use crate::diagnostics::Diagnostic;
+use crate::lexer::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError {
+    UnknownType {
+        name: String,
+        span: Span,
+    },
+
+    // `declared` is the span of the type annotation being violated, if any
+    IncompatibleAssignment {
+        name: String,
+        expected: TypeKind,
+        found: TypeKind,
+        span: Span,
+        declared: Option<Span>,
+    },
+
+    IncompatibleArgument {
+        function: String,
+        position: usize,
+        expected: TypeKind,
+        found: TypeKind,
+        span: Span,
+    },
+
+    IncompatibleReturn {
+        function: String,
+        expected: TypeKind,
+        found: TypeKind,
+        span: Span,
+        declared: Option<Span>,
+    },
+
+    MissingReturn {
+        function: String,
+        expected: TypeKind,
+        span: Span,
+        declared: Option<Span>,
+    },
+
+    MisplacedImport {
+        span: Span,
+    },
+
+    // a compound assignment or `++`/`--` on a typed target whose type the
+    // operator doesn't take; `operand` is the right-hand side's type, and
+    // None for `++` and `--`
+    InvalidOperator {
+        op: String,
+        name: String,
+        ty: TypeKind,
+        operand: Option<TypeKind>,
+        span: Span,
+        declared: Option<Span>,
+    },
+
+    // `previous` is where `name` was first declared in the same scope
+    Redeclaration {
+        name: String,
+        span: Span,
+        previous: Span,
+    },
+
+    // `ty` is the struct whose field `name` was read or written
+    UnknownField {
+        ty: String,
+        name: String,
+        span: Span,
+    },
+}
+
+impl TypeError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string());
+        let diagnostic = match self {
+            TypeError::UnknownType { span, .. } => diagnostic
+                .with_primary(*span, "not a known type")
+                .with_note("built-in types are int, float, string, bool, array, dict, null and Void, besides declared structs"),
+            TypeError::IncompatibleAssignment {
+                expected,
+                found,
+                span,
+                declared,
+                ..
+            }
+            | TypeError::IncompatibleReturn {
+                expected,
+                found,
+                span,
+                declared,
+                ..
+            } => {
+                let diagnostic = diagnostic.with_primary(*span, format!("this is '{}'", found));
+                match declared {
+                    Some(declared) => {
+                        diagnostic.with_secondary(*declared, format!("declared as '{}' here", expected))
+                    }
+                    None => diagnostic,
+                }
+            }
+            TypeError::IncompatibleArgument { found, span, .. } => {
+                diagnostic.with_primary(*span, format!("this is '{}'", found))
+            }
+            TypeError::MissingReturn { span, declared, .. } => {
+                let diagnostic = diagnostic.with_primary(*span, "not every path returns a value");
+                match declared {
+                    Some(declared) => diagnostic.with_secondary(*declared, "return type declared here"),
+                    None => diagnostic,
+                }
+            }
+            TypeError::MisplacedImport { span } => diagnostic
+                .with_primary(*span, "imported inside a block")
+                .with_note("move the import to the top level of the file"),
+            TypeError::UnknownField { span, .. } => diagnostic.with_primary(*span, "not a field or method"),
+            TypeError::Redeclaration { span, previous, .. } => diagnostic
+                .with_primary(*span, "declared again here")
+                .with_secondary(*previous, "first declared here")
+                .with_note("variables declared in a block belong to the whole function, or to the file at the top level"),
+            TypeError::InvalidOperator {
+                op,
+                ty,
+                operand,
+                span,
+                declared,
+                ..
+            } => {
+                let label = match operand {
+                    Some(operand) => format!("this is '{}'", operand),
+                    None => format!("'{}' needs an int or float", op),
+                };
+                let diagnostic = diagnostic.with_primary(*span, label);
+                match declared {
+                    Some(declared) => diagnostic.with_secondary(*declared, format!("declared as '{}' here", ty)),
+                    None => diagnostic,
+                }
+            }
+        };
+        match self.conversion() {
+            Some(note) => diagnostic.with_note(note),
+            None => diagnostic,
+        }
+    }
+
+    // how to fix an int given where a float is expected, or the reverse
+    fn conversion(&self) -> Option<&'static str> {
+        match self {
+            TypeError::IncompatibleAssignment { expected, found, .. }
+            | TypeError::IncompatibleArgument { expected, found, .. }
+            | TypeError::IncompatibleReturn { expected, found, .. } => match (expected, found) {
+                (TypeKind::Float, TypeKind::Int) => {
+                    Some("ints are not converted implicitly; use `toFloat()`, or write a literal as `2.0`")
+                }
+                (TypeKind::Int, TypeKind::Float) => {
+                    Some("floats are not converted implicitly; `toInt()` drops the fractional part")
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::UnknownType { name, .. } => write!(f, "Unknown type '{}'", name),
+            TypeError::IncompatibleAssignment {
+                name,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "Cannot assign a value of type '{}' to '{}' of type '{}'",
+                found, name, expected
+            ),
+            TypeError::IncompatibleArgument {
+                function,
+                position,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "Argument {} of '{}' expects '{}' but got '{}'",
+                position, function, expected, found
+            ),
+            TypeError::IncompatibleReturn {
+                function,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "Function '{}' must return '{}' but returns '{}'",
+                function, expected, found
+            ),
+            TypeError::MissingReturn {
+                function, expected, ..
+            } => write!(
+                f,
+                "Function '{}' must return '{}' on every path",
+                function, expected
+            ),
+            TypeError::MisplacedImport { .. } => write!(f, "Imports must be at the top level of a file"),
+            TypeError::UnknownField { ty, name, .. } => write!(f, "{} has no field '{}'", ty, name),
+            TypeError::Redeclaration { name, .. } => write!(f, "'{}' is already declared in this scope", name),
+            TypeError::InvalidOperator {
+                op,
+                name,
+                ty,
+                operand: Some(operand),
+                ..
+            } => write!(
+                f,
+                "Cannot apply '{}' to '{}' of type '{}' and a value of type '{}'",
+                op, name, ty, operand
+            ),
+            TypeError::InvalidOperator { op, name, ty, .. } => {
+                write!(f, "Cannot apply '{}' to '{}' of type '{}'", op, name, ty)
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod checker;
+pub mod error;
+pub mod types;
+
+pub use checker::TypeChecker;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,120 @@
+use super::TypeChecker;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+// the messages of the type errors in `source`
+fn errors(source: &str) -> Vec<String> {
+    let (tokens, lexErrors) = Lexer::new(source).tokenize();
+    assert!(lexErrors.is_empty(), "lexer errors: {:?}", lexErrors);
+    let (statements, parseErrors) = Parser::new(tokens).parse();
+    assert!(parseErrors.is_empty(), "parser errors: {:?}", parseErrors);
+    match TypeChecker::new().check(&statements) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(ToString::to_string).collect(),
+    }
+}
+
+#[test]
+fn compound_assignment_checks_its_operands() {
+    assert_eq!(
+        errors("var x: int = 1; x += \"s\";"),
+        ["Cannot apply '+=' to 'x' of type 'int' and a value of type 'string'"]
+    );
+    assert_eq!(
+        errors("var x: int = 1; x += 1.5;"),
+        ["Cannot assign a value of type 'float' to 'x' of type 'int'"]
+    );
+    assert!(errors("var x: int = 1; x += 2; var s: string = \"a\"; s += \"b\";").is_empty());
+}
+
+#[test]
+fn update_needs_a_numeric_target() {
+    assert_eq!(errors("var s: string = \"a\"; s++;"), ["Cannot apply '++' to 's' of type 'string'"]);
+    assert!(errors("var f: float = 1.0; f--; var u = \"a\"; u++;").is_empty());
+}
+
+#[test]
+fn null_is_a_type_name() {
+    assert!(errors("var n: null = null;").is_empty());
+}
+
+#[test]
+fn blocks_share_their_function_scope() {
+    assert_eq!(
+        errors("var y: int = 1;\nif true { var y = \"a\"; }\nvar z: int = y;"),
+        ["'y' is already declared in this scope"]
+    );
+    assert_eq!(
+        errors("func f() {\n    var y: int = 1;\n    while false { var y = \"a\"; }\n    return y;\n}"),
+        ["'y' is already declared in this scope"]
+    );
+    // untyped names may be declared again, and other functions have scopes of their own
+    assert!(errors("var t = 1; if true { var t = \"a\"; } func f() { var t: string = \"b\"; }").is_empty());
+}
+
+#[test]
+fn loop_variables_assign_the_function_variable() {
+    assert_eq!(
+        errors("var i: string = \"a\";\nfor i in range(0, 3) {}"),
+        ["Cannot assign a value of type 'int' to 'i' of type 'string'"]
+    );
+    assert!(errors("for i in range(0, 3) {}\nfor i in range(0, 3) {}\nvar j: int = i;").is_empty());
+}
+
+#[test]
+fn declarations_are_known_before_function_bodies() {
+    assert_eq!(
+        errors("func g() { y = \"s\"; }\nvar y: int = 1;"),
+        ["Cannot assign a value of type 'string' to 'y' of type 'int'"]
+    );
+    assert_eq!(errors("var x: int = 1;\nfunc x() {}"), ["'x' is already declared in this scope"]);
+    assert_eq!(errors("struct P { x }\nfunc P() {}"), ["'P' is already declared in this scope"]);
+}
+
+#[test]
+fn imports_inside_blocks_are_misplaced() {
+    assert_eq!(
+        errors("if true { import \"a.rey\"; }"),
+        ["Imports must be at the top level of a file"]
+    );
+}
+
+#[test]
+fn typed_parameters_check_their_arguments() {
+    assert_eq!(
+        errors("func add(a: int, b: int): int { return a + b; } add(1, \"two\");"),
+        ["Argument 2 of 'add' expects 'int' but got 'string'"]
+    );
+    assert_eq!(errors("func h(n: float) { return n; } h(1);"), ["Argument 1 of 'h' expects 'float' but got 'int'"]);
+    assert_eq!(errors("struct P { x: int, y } P(\"a\", 1);"), ["Argument 1 of 'P' expects 'int' but got 'string'"]);
+    // untyped results are not checked until they run
+    assert!(errors("func u(x: int) { return x; } func loose() { return \"x\"; } u(loose()); u(2);").is_empty());
+}
+
+#[test]
+fn typed_functions_check_what_they_return() {
+    assert_eq!(
+        errors("func name(): string { return 1; }"),
+        ["Function 'name' must return 'string' but returns 'int'"]
+    );
+    assert_eq!(errors("func k(): float { return 1; }"), ["Function 'k' must return 'float' but returns 'int'"]);
+    assert_eq!(errors("func v(): int { return null; }"), ["Function 'v' must return 'int' but returns 'null'"]);
+    assert_eq!(
+        errors("func var_only(): int { var x = 1; }"),
+        ["Function 'var_only' must return 'int' on every path"]
+    );
+}
+
+#[test]
+fn every_path_of_a_typed_function_returns() {
+    assert_eq!(
+        errors("func maybe(c): int { if c { return 1; } }"),
+        ["Function 'maybe' must return 'int' on every path"]
+    );
+    // a loop body may never run
+    assert_eq!(
+        errors("func w(): int { while true { return 1; } }"),
+        ["Function 'w' must return 'int' on every path"]
+    );
+    assert!(errors("func both(c): int { if c { return 1; } else { return 2; } }").is_empty());
+}
@@ -0,0 +1,93 @@
+use std::fmt;
+
+// static types known to the checker
+// Unknown is the type of every untyped (dynamic) binding and is
+// compatible with everything, so unannotated code is never rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeKind {
+    Int,
+    Float,
+    String,
+    Bool,
+    Null,
+    Void,
+    Array,
+    Dict,
+    Function(FunctionSignature),
+    // an instance of the struct with this name
+    Struct(String),
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    pub params: Vec<TypeKind>,
+    pub return_ty: Box<TypeKind>,
+}
+
+impl TypeKind {
+    //maps an annotation name to a type, None for names we don't know
+    pub fn from_annotation(name: &str) -> Option<TypeKind> {
+        match name {
+            "int" => Some(TypeKind::Int),
+            "float" => Some(TypeKind::Float),
+            "string" | "String" => Some(TypeKind::String),
+            "bool" => Some(TypeKind::Bool),
+            "null" => Some(TypeKind::Null),
+            "void" | "Void" => Some(TypeKind::Void),
+            "array" => Some(TypeKind::Array),
+            "dict" => Some(TypeKind::Dict),
+            _ => None,
+        }
+    }
+
+    // whether a value of type `found` may be stored in a binding of type `self`
+    // ints and floats are distinct values, so neither is accepted for the other
+    pub fn accepts(&self, found: &TypeKind) -> bool {
+        match (self, found) {
+            (TypeKind::Unknown, _) | (_, TypeKind::Unknown) => true,
+            (TypeKind::Void, TypeKind::Null) => true,
+            (TypeKind::Function(expected), TypeKind::Function(found)) => {
+                expected.params.len() == found.params.len()
+                    && expected
+                        .params
+                        .iter()
+                        .zip(&found.params)
+                        .all(|(e, f)| f.accepts(e))
+                    && expected.return_ty.accepts(&found.return_ty)
+            }
+            (expected, found) => expected == found,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, TypeKind::Int | TypeKind::Float)
+    }
+}
+
+impl fmt::Display for TypeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeKind::Int => write!(f, "int"),
+            TypeKind::Float => write!(f, "float"),
+            TypeKind::String => write!(f, "string"),
+            TypeKind::Bool => write!(f, "bool"),
+            TypeKind::Null => write!(f, "null"),
+            TypeKind::Void => write!(f, "Void"),
+            TypeKind::Array => write!(f, "array"),
+            TypeKind::Dict => write!(f, "dict"),
+            TypeKind::Function(signature) => {
+                write!(f, "func(")?;
+                for (i, param) in signature.params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", param)?;
+                }
+                write!(f, "): {}", signature.return_ty)
+            }
+            TypeKind::Struct(name) => write!(f, "{}", name),
+            TypeKind::Unknown => write!(f, "unknown"),
+        }
+    }
+}
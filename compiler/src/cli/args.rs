@@ -0,0 +1,207 @@
+use crate::diagnostics::RenderMode;
+
+pub const USAGE: &str = "\
+Usage: rey [command] [options] [file | - | -e <code>] [args...]
+
+Commands:
+  run      run a program (the default when a file is given)
+  check    lex, parse and type-check without running
+  tokens   print the tokens of a program
+  ast      print the syntax tree of a program
+  fmt      print a program in canonical layout
+  compile  compile a program to a bytecode module (.reyc)
+  disasm   print the bytecode of a program or .reyc module
+  test     run every .rey file under the given paths (default: tests)
+  repl     start the interactive prompt (the default with no arguments)
+
+Input:
+  <file>         read the program from a file; run and disasm also
+                 accept a compiled .reyc module
+  -              read the program from standard input
+  -e <code>      use <code> as the program
+  args...        run: passed to the program's main function; arguments
+                 after -- are passed on even if they look like options
+
+Options:
+  --json              machine-readable diagnostics, tokens and trees
+  --color=<when>      colour diagnostics: auto (default), always or never
+  --backend=<name>    run and test: execute with the tree walker (tree,
+                      the default) or the bytecode VM (vm)
+  -o <path>           compile: where to write the module (default: the
+                      source path with a .reyc extension)
+  -w, --write         fmt: rewrite the file in place
+  --check             fmt: only report whether the file is formatted
+  -h, --help          show this message
+
+Exit status is 0 on success, 1 when the program has errors and 2 for
+invalid usage or unreadable input. An int from 0 to 255 returned from
+main is used as the exit status instead.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Check,
+    Tokens,
+    Ast,
+    Fmt,
+    Compile,
+    Disasm,
+    Test,
+    Repl,
+    Help,
+}
+
+// how `run` executes a checked program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Tree,
+    Vm,
+}
+
+impl Backend {
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Tree => "tree",
+            Backend::Vm => "vm",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    File(String),
+    Stdin,
+    Inline(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub command: Command,
+    pub input: Option<Input>,
+    // extra paths given to `test`
+    pub paths: Vec<String>,
+    pub json: bool,
+    // None means decide from the terminal
+    pub color: Option<RenderMode>,
+    pub write: bool,
+    pub check: bool,
+    pub backend: Backend,
+    // compile: the module to write
+    pub output: Option<String>,
+    // run: the arguments main receives
+    pub program_args: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+        let mut args = args.into_iter().peekable();
+        let mut parsed = Args {
+            command: Command::Repl,
+            input: None,
+            paths: Vec::new(),
+            json: false,
+            color: None,
+            write: false,
+            check: false,
+            backend: Backend::Tree,
+            output: None,
+            program_args: Vec::new(),
+        };
+
+        // a leading file or option without a command means `run`
+        let mut explicit = false;
+        if let Some(first) = args.peek() {
+            if let Some(command) = Self::command(first) {
+                parsed.command = command;
+                explicit = true;
+                args.next();
+            } else {
+                parsed.command = Command::Run;
+            }
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => parsed.program_args.extend(args.by_ref()),
+                "--json" => parsed.json = true,
+                "--color=always" => parsed.color = Some(RenderMode::Ansi),
+                "--color=never" => parsed.color = Some(RenderMode::Plain),
+                "--color=auto" => parsed.color = None,
+                "--backend=tree" => parsed.backend = Backend::Tree,
+                "--backend=vm" => parsed.backend = Backend::Vm,
+                "-w" | "--write" => parsed.write = true,
+                "--check" => parsed.check = true,
+                "-h" | "--help" => parsed.command = Command::Help,
+                "-e" => {
+                    let code = args.next().ok_or("-e needs a program to run")?;
+                    parsed.setInput(Input::Inline(code))?;
+                }
+                "-o" => {
+                    let path = args.next().ok_or("-o needs a path to write to")?;
+                    parsed.output = Some(path);
+                }
+                "-" => parsed.setInput(Input::Stdin)?,
+                option if option.starts_with('-') => {
+                    return Err(format!("unknown option '{}'", option));
+                }
+                _ if parsed.command == Command::Test => parsed.paths.push(arg),
+                _ if parsed.input.is_some() && parsed.command == Command::Run => parsed.program_args.push(arg),
+                _ => parsed.setInput(Input::File(arg))?,
+            }
+        }
+
+        match parsed.command {
+            Command::Run
+            | Command::Check
+            | Command::Tokens
+            | Command::Ast
+            | Command::Fmt
+            | Command::Compile
+            | Command::Disasm
+                if parsed.input.is_none() =>
+            {
+                // `rey --json` alone still starts the prompt
+                if parsed.command == Command::Run && !explicit {
+                    parsed.command = Command::Repl;
+                } else {
+                    return Err("no program given; pass a file, '-' or -e <code>".to_string());
+                }
+            }
+            Command::Fmt if parsed.write && !matches!(parsed.input, Some(Input::File(_))) => {
+                return Err("--write needs a file to rewrite".to_string());
+            }
+            _ if !parsed.program_args.is_empty() && parsed.command != Command::Run => {
+                return Err("only run passes arguments to the program".to_string());
+            }
+            Command::Compile if parsed.output.is_none() && !matches!(parsed.input, Some(Input::File(_))) => {
+                return Err("compile needs -o <path> when the program is not a file".to_string());
+            }
+            _ => {}
+        }
+        Ok(parsed)
+    }
+
+    fn command(name: &str) -> Option<Command> {
+        match name {
+            "run" => Some(Command::Run),
+            "check" => Some(Command::Check),
+            "tokens" => Some(Command::Tokens),
+            "ast" => Some(Command::Ast),
+            "fmt" => Some(Command::Fmt),
+            "compile" => Some(Command::Compile),
+            "disasm" => Some(Command::Disasm),
+            "test" => Some(Command::Test),
+            "repl" => Some(Command::Repl),
+            "help" => Some(Command::Help),
+            _ => None,
+        }
+    }
+
+    fn setInput(&mut self, input: Input) -> Result<(), String> {
+        if self.input.is_some() {
+            return Err("only one program can be given".to_string());
+        }
+        self.input = Some(input);
+        Ok(())
+    }
+}
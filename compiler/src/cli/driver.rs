@@ -0,0 +1,472 @@
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command as Process;
+
+use super::args::{Args, Backend, Command, Input, USAGE};
+use crate::ast::json::statementsJson;
+use crate::ast::Stmt;
+use crate::diagnostics::render::jsonString;
+use crate::diagnostics::{Diagnostic, RenderMode, Renderer, SourceFile, SourceMap};
+use crate::formatter::Formatter;
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+use crate::lexer::{Comment, Lexer, Token};
+use crate::modules::{Loader, ModuleGraph};
+use crate::parser::Parser;
+use crate::repl::Repl;
+use crate::vm::{disassemble, Compiler, Module, Program, Vm};
+
+// exit statuses
+pub const SUCCESS: i32 = 0;
+pub const FAILURE: i32 = 1;
+pub const USAGE_ERROR: i32 = 2;
+
+// runs the command described by `args` and returns the exit status
+pub fn run(args: Args) -> i32 {
+    let mode = renderMode(&args);
+    match args.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            SUCCESS
+        }
+        Command::Repl => {
+            Repl::new(mode).run();
+            SUCCESS
+        }
+        Command::Test => runTests(&args),
+        _ => {
+            let input = args.input.clone().expect("commands other than test and repl have an input");
+            if let Input::File(path) = &input {
+                if isModule(path) {
+                    return runModule(path, &args, mode);
+                }
+            }
+            let file = match read(&input) {
+                Ok(file) => file,
+                Err(message) => {
+                    eprintln!("error: {}", message);
+                    return USAGE_ERROR;
+                }
+            };
+            let session = Session {
+                file,
+                mode,
+                from_file: matches!(input, Input::File(_)),
+            };
+            let ok = match args.command {
+                Command::Run => return session.run(args.backend, &args.program_args),
+                Command::Check => session.load().is_some(),
+                Command::Tokens => session.tokens(args.json),
+                Command::Ast => session.ast(args.json),
+                Command::Fmt => return session.fmt(&input, args.write, args.check),
+                Command::Compile => return session.compile(&input, args.output.as_deref()),
+                Command::Disasm => session.disasm(),
+                _ => unreachable!(),
+            };
+            if ok {
+                SUCCESS
+            } else {
+                FAILURE
+            }
+        }
+    }
+}
+
+// colour only when a person is likely reading, and never if NO_COLOR is set
+fn renderMode(args: &Args) -> RenderMode {
+    if args.json {
+        return RenderMode::Json;
+    }
+    args.color.unwrap_or_else(|| {
+        if io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none() {
+            RenderMode::Ansi
+        } else {
+            RenderMode::Plain
+        }
+    })
+}
+
+fn read(input: &Input) -> Result<SourceFile, String> {
+    match input {
+        Input::File(path) => fs::read_to_string(path)
+            .map(|text| SourceFile::new(path, &text))
+            .map_err(|err| format!("cannot read '{}': {}", path, err)),
+        Input::Stdin => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| format!("cannot read standard input: {}", err))?;
+            Ok(SourceFile::new("<stdin>", &text))
+        }
+        Input::Inline(code) => Ok(SourceFile::new("<inline>", code)),
+    }
+}
+
+// one program going through the pipeline; every stage reports its own
+// errors and returns None when it failed
+struct Session {
+    file: SourceFile,
+    mode: RenderMode,
+    // imports are resolved next to the file, or else the working directory
+    from_file: bool,
+}
+
+impl Session {
+    fn report(&self, diagnostic: Diagnostic) {
+        eprint!("{}", Renderer::new(&self.file, self.mode).render(&diagnostic));
+    }
+
+    fn lex(&self) -> Option<Vec<Token>> {
+        let (tokens, errors) = Lexer::new(&self.file.text).tokenize();
+        if errors.is_empty() {
+            return Some(tokens);
+        }
+        for err in errors {
+            self.report(err.to_diagnostic());
+        }
+        None
+    }
+
+    // comments are trivia the parser never sees, so they are lexed again
+    fn comments(&self) -> Vec<Comment> {
+        let mut lexer = Lexer::new(&self.file.text);
+        lexer.tokenize();
+        lexer.comments().to_vec()
+    }
+
+    fn parse(&self) -> Option<Vec<Stmt>> {
+        let (statements, errors) = Parser::new(self.lex()?).parse();
+        if errors.is_empty() {
+            return Some(statements);
+        }
+        for err in errors {
+            self.report(err.to_diagnostic());
+        }
+        None
+    }
+
+    // the program together with every file it imports, parsed and checked
+    fn load(&self) -> Option<ModuleGraph> {
+        let (graph, diagnostics) = Loader::new().load(&self.file, self.from_file);
+        if diagnostics.is_empty() {
+            return Some(graph);
+        }
+        for diagnostic in diagnostics {
+            self.reportIn(&graph.sources, diagnostic);
+        }
+        None
+    }
+
+    // renders against whichever of the program's files the diagnostic is in
+    fn reportIn(&self, sources: &SourceMap, diagnostic: Diagnostic) {
+        eprint!("{}", render(sources, self.mode, &diagnostic));
+    }
+
+    fn run(&self, backend: Backend, args: &[String]) -> i32 {
+        let graph = match self.load() {
+            Some(graph) => graph,
+            None => return FAILURE,
+        };
+        let result = match backend {
+            Backend::Tree => {
+                let mut interpreter = Interpreter::new();
+                interpreter.interpret_modules(&graph).and_then(|()| interpreter.call_main(args))
+            }
+            Backend::Vm => match self.bytecode(&graph) {
+                Some(program) => Vm::new().run(&program, args),
+                None => return FAILURE,
+            },
+        };
+        match result {
+            Ok(value) => exitStatus(&value),
+            Err(err) => {
+                self.reportIn(&graph.sources, err.to_diagnostic());
+                FAILURE
+            }
+        }
+    }
+
+    fn bytecode(&self, graph: &ModuleGraph) -> Option<Program> {
+        match Compiler::new().compile(graph) {
+            Ok(program) => Some(program),
+            Err(errors) => {
+                for err in errors {
+                    self.reportIn(&graph.sources, err.to_diagnostic());
+                }
+                None
+            }
+        }
+    }
+
+    // writes the compiled module next to the source unless `output` says otherwise
+    fn compile(&self, input: &Input, output: Option<&str>) -> i32 {
+        let graph = match self.load() {
+            Some(graph) => graph,
+            None => return FAILURE,
+        };
+        let program = match self.bytecode(&graph) {
+            Some(program) => program,
+            None => return FAILURE,
+        };
+        let path = match (output, input) {
+            (Some(output), _) => PathBuf::from(output),
+            (None, Input::File(path)) => Path::new(path).with_extension("reyc"),
+            (None, _) => unreachable!("argument parsing requires -o for other inputs"),
+        };
+        let bytes = Module::new(&graph.sources, program).encode();
+        if let Err(err) = fs::write(&path, bytes) {
+            eprintln!("error: cannot write '{}': {}", path.display(), err);
+            return USAGE_ERROR;
+        }
+        SUCCESS
+    }
+
+    fn disasm(&self) -> bool {
+        let graph = match self.load() {
+            Some(graph) => graph,
+            None => return false,
+        };
+        match self.bytecode(&graph) {
+            Some(program) => {
+                print!("{}", disassemble(&program, &graph.sources));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn tokens(&self, json: bool) -> bool {
+        let tokens = match self.lex() {
+            Some(tokens) => tokens,
+            None => return false,
+        };
+        if json {
+            let tokens: Vec<String> = tokens
+                .iter()
+                .map(|token| {
+                    // the variant name, without any payload
+                    let kind = format!("{:?}", token.kind);
+                    let kind = kind.split('(').next().unwrap_or_default();
+                    format!(
+                        "{{\"kind\":{},\"text\":{},\"span\":{{\"start\":{},\"end\":{}}}}}",
+                        jsonString(kind),
+                        jsonString(&self.file.text[token.span.start..token.span.end]),
+                        token.span.start,
+                        token.span.end
+                    )
+                })
+                .collect();
+            println!("[{}]", tokens.join(","));
+        } else {
+            for token in tokens {
+                let position = self.file.start(token.span);
+                println!("{}:{}\t{:?}", position.line, position.column, token.kind);
+            }
+        }
+        true
+    }
+
+    fn ast(&self, json: bool) -> bool {
+        let statements = match self.parse() {
+            Some(statements) => statements,
+            None => return false,
+        };
+        if json {
+            println!("{}", statementsJson(&statements));
+        } else {
+            for stmt in statements {
+                println!("{:#?}", stmt);
+            }
+        }
+        true
+    }
+
+    // prints the formatted program, or with `write` replaces the file;
+    // with `check`, fails if formatting would change anything
+    fn fmt(&self, input: &Input, write: bool, check: bool) -> i32 {
+        let statements = match self.parse() {
+            Some(statements) => statements,
+            None => return FAILURE,
+        };
+        let formatted = Formatter::new()
+            .with_comments(self.comments())
+            .with_source(&self.file.text)
+            .format(&statements);
+        if check {
+            if formatted == self.file.text {
+                return SUCCESS;
+            }
+            eprintln!("{} is not formatted", self.file.name);
+            return FAILURE;
+        }
+        match input {
+            Input::File(path) if write => {
+                if formatted != self.file.text {
+                    if let Err(err) = fs::write(path, formatted) {
+                        eprintln!("error: cannot write '{}': {}", path, err);
+                        return USAGE_ERROR;
+                    }
+                }
+            }
+            _ => print!("{}", formatted),
+        }
+        SUCCESS
+    }
+}
+
+// an int from 0 to 255 returned from main is the exit status, and a main
+// returning nothing succeeds; any other result is an error
+fn exitStatus(value: &Value) -> i32 {
+    match exit_status(value) {
+        Ok(status) => status,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            FAILURE
+        }
+    }
+}
+
+pub fn exit_status(value: &Value) -> Result<i32, String> {
+    match value {
+        Value::Null => Ok(SUCCESS),
+        Value::Int(n @ 0..=255) => Ok(*n as i32),
+        Value::Int(_) | Value::BigInt(_) => Err(format!("main returned {}, but an exit status must be from 0 to 255", value)),
+        other => Err(format!("main returned a {}, but an exit status must be an int", other.type_name())),
+    }
+}
+
+fn render(sources: &SourceMap, mode: RenderMode, diagnostic: &Diagnostic) -> String {
+    let offset = diagnostic.primary_span().map_or(0, |span| span.start);
+    Renderer::new(sources.find(offset), mode).render(diagnostic)
+}
+
+fn isModule(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext == "reyc")
+}
+
+// runs or disassembles a compiled module; errors point into the original
+// source when it is still there and unchanged
+fn runModule(path: &str, args: &Args, mode: RenderMode) -> i32 {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("error: cannot read '{}': {}", path, err);
+            return USAGE_ERROR;
+        }
+    };
+    let module = match Module::decode(&bytes) {
+        Ok(module) => module,
+        Err(err) => {
+            eprintln!("error: cannot load '{}': {}", path, err);
+            return FAILURE;
+        }
+    };
+    let sources = module.sources(|name| fs::read_to_string(name).ok());
+
+    match args.command {
+        Command::Run => match Vm::new().run(&module.program, &args.program_args) {
+            Ok(value) => exitStatus(&value),
+            Err(err) => {
+                eprint!("{}", render(&sources, mode, &err.to_diagnostic()));
+                FAILURE
+            }
+        },
+        Command::Disasm => {
+            print!("{}", disassemble(&module.program, &sources));
+            SUCCESS
+        }
+        _ => {
+            eprintln!("error: '{}' is compiled bytecode; only run and disasm accept it", path);
+            USAGE_ERROR
+        }
+    }
+}
+
+// runs each program in its own process, so output can be compared and a
+// crash in one program does not stop the others
+// a program passes when it exits successfully and, if a `.out` file sits
+// next to it, prints exactly that file's contents
+fn runTests(args: &Args) -> i32 {
+    let roots = if args.paths.is_empty() {
+        vec!["tests".to_string()]
+    } else {
+        args.paths.clone()
+    };
+    let mut programs = Vec::new();
+    for root in &roots {
+        let root = Path::new(root);
+        if !root.exists() {
+            eprintln!("error: '{}' does not exist", root.display());
+            return USAGE_ERROR;
+        }
+        collectPrograms(root, &mut programs);
+    }
+    programs.sort();
+
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(err) => {
+            eprintln!("error: cannot locate the rey executable: {}", err);
+            return USAGE_ERROR;
+        }
+    };
+
+    let mut failed = 0;
+    for program in &programs {
+        let output = Process::new(&exe)
+            .arg("run")
+            .arg("--color=never")
+            .arg(format!("--backend={}", args.backend.name()))
+            .arg(program)
+            .output();
+        let failure = match output {
+            Err(err) => Some(format!("could not start: {}", err)),
+            Ok(output) if !output.status.success() => {
+                Some(String::from_utf8_lossy(&output.stderr).trim_end().to_string())
+            }
+            Ok(output) => match fs::read_to_string(program.with_extension("out")) {
+                Ok(expected) if expected.as_bytes() != output.stdout.as_slice() => Some(format!(
+                    "expected output:\n{}\nactual output:\n{}",
+                    expected.trim_end(),
+                    String::from_utf8_lossy(&output.stdout).trim_end()
+                )),
+                _ => None,
+            },
+        };
+        match failure {
+            None => println!("PASS {}", program.display()),
+            Some(details) => {
+                failed += 1;
+                println!("FAIL {}", program.display());
+                for line in details.lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
+    }
+
+    println!(
+        "\n{} passed, {} failed",
+        programs.len() - failed,
+        failed
+    );
+    if failed == 0 {
+        SUCCESS
+    } else {
+        FAILURE
+    }
+}
+
+fn collectPrograms(path: &Path, programs: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collectPrograms(&entry.path(), programs);
+            }
+        }
+    } else if path.extension().is_some_and(|ext| ext == "rey") {
+        programs.push(path.to_path_buf());
+    }
+}
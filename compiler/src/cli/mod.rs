@@ -0,0 +1,8 @@
+pub mod args;
+pub mod driver;
+
+pub use args::{Args, USAGE};
+pub use driver::{run, USAGE_ERROR};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,80 @@
+use super::args::{Backend, Command, Input};
+use super::driver::exit_status;
+use super::Args;
+use crate::interpreter::value::Value;
+
+#[test]
+fn main_results_become_exit_statuses() {
+    assert_eq!(exit_status(&Value::Null), Ok(0));
+    assert_eq!(exit_status(&Value::Int(0)), Ok(0));
+    assert_eq!(exit_status(&Value::Int(3)), Ok(3));
+    assert_eq!(exit_status(&Value::Int(255)), Ok(255));
+}
+
+#[test]
+fn exit_statuses_out_of_range_are_errors() {
+    for n in [-1, 256, i64::MAX] {
+        assert_eq!(
+            exit_status(&Value::Int(n)),
+            Err(format!("main returned {}, but an exit status must be from 0 to 255", n))
+        );
+    }
+    assert_eq!(
+        exit_status(&Value::String("0".to_string())),
+        Err("main returned a string, but an exit status must be an int".to_string())
+    );
+    assert_eq!(
+        exit_status(&Value::Bool(true)),
+        Err("main returned a bool, but an exit status must be an int".to_string())
+    );
+}
+
+fn parse(args: &[&str]) -> Result<Args, String> {
+    Args::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn subcommands_are_dispatched_by_name() {
+    let cases = [
+        ("run", Command::Run),
+        ("check", Command::Check),
+        ("tokens", Command::Tokens),
+        ("ast", Command::Ast),
+        ("fmt", Command::Fmt),
+        ("compile", Command::Compile),
+        ("disasm", Command::Disasm),
+    ];
+    for (name, command) in cases {
+        let args = parse(&[name, "main.rey"]).expect("arguments parse");
+        assert_eq!(args.command, command);
+        assert_eq!(args.input, Some(Input::File("main.rey".to_string())));
+    }
+    assert_eq!(parse(&["test", "a", "b"]).expect("arguments parse").paths, ["a", "b"]);
+    assert_eq!(parse(&["help"]).expect("arguments parse").command, Command::Help);
+}
+
+#[test]
+fn a_file_alone_is_run_and_nothing_starts_the_prompt() {
+    let args = parse(&["main.rey", "x", "--", "--y"]).expect("arguments parse");
+    assert_eq!(args.command, Command::Run);
+    assert_eq!(args.program_args, ["x", "--y"]);
+    assert_eq!(parse(&[]).expect("arguments parse").command, Command::Repl);
+    assert_eq!(parse(&["--json"]).expect("arguments parse").command, Command::Repl);
+}
+
+#[test]
+fn options_apply_to_the_command() {
+    let args = parse(&["run", "--backend=vm", "-e", "println(1);"]).expect("arguments parse");
+    assert_eq!(args.backend, Backend::Vm);
+    assert_eq!(args.input, Some(Input::Inline("println(1);".to_string())));
+    let args = parse(&["fmt", "-w", "main.rey"]).expect("arguments parse");
+    assert!(args.write);
+}
+
+#[test]
+fn invalid_usage_is_an_error() {
+    assert_eq!(parse(&["check"]).err(), Some("no program given; pass a file, '-' or -e <code>".to_string()));
+    assert_eq!(parse(&["run", "--bogus", "a.rey"]).err(), Some("unknown option '--bogus'".to_string()));
+    assert_eq!(parse(&["check", "a.rey", "b.rey"]).err(), Some("only one program can be given".to_string()));
+    assert_eq!(parse(&["fmt", "-w", "-"]).err(), Some("--write needs a file to rewrite".to_string()));
+}
@@ -0,0 +1,10 @@
+pub mod diagnostic;
+pub mod render;
+pub mod source;
+
+pub use diagnostic::Diagnostic;
+pub use render::{Renderer, RenderMode};
+pub use source::{SourceFile, SourceMap};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,225 @@
+use std::fmt::Write;
+
+use super::diagnostic::{Diagnostic, Label, Severity};
+use super::source::{Position, SourceFile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    Plain,
+    // plain text with ANSI colour escapes, for terminals
+    Ansi,
+    // one JSON object per diagnostic, for editors and other tools
+    Json,
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+
+pub struct Renderer<'a> {
+    source: &'a SourceFile,
+    mode: RenderMode,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(source: &'a SourceFile, mode: RenderMode) -> Self {
+        Self { source, mode }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        match self.mode {
+            RenderMode::Json => self.renderJson(diagnostic),
+            RenderMode::Plain | RenderMode::Ansi => self.renderText(diagnostic),
+        }
+    }
+
+    fn renderText(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+        let severity_colour = match diagnostic.severity {
+            Severity::Error => RED,
+        };
+
+        let _ = writeln!(
+            out,
+            "{}{}{}: {}{}{}",
+            self.colour(severity_colour),
+            diagnostic.severity.as_str(),
+            self.colour(RESET),
+            self.colour(BOLD),
+            diagnostic.message,
+            self.colour(RESET),
+        );
+
+        // primary label first, then the rest in source order
+        let mut labels: Vec<&Label> = diagnostic.labels.iter().collect();
+        labels.sort_by_key(|label| (!label.primary, label.span.start));
+
+        let gutter_width = labels
+            .iter()
+            .map(|label| self.source.start(label.span).line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter_width);
+
+        if let Some(first) = labels.first() {
+            let start = self.source.start(first.span);
+            let _ = writeln!(
+                out,
+                "{}{}-->{} {}:{}:{}",
+                pad,
+                self.colour(BLUE),
+                self.colour(RESET),
+                self.source.name,
+                start.line,
+                start.column
+            );
+        }
+
+        // labels on the same line share one snippet, lines in label order
+        let mut lines: Vec<usize> = Vec::new();
+        for label in labels.iter().filter(|_| !self.source.is_detached()) {
+            let line = self.source.start(label.span).line;
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+
+        for line in lines {
+            let line_text = self.source.line(line);
+            let _ = writeln!(out, "{} {}|{}", pad, self.colour(BLUE), self.colour(RESET));
+            let _ = writeln!(
+                out,
+                "{}{:>width$} |{} {}",
+                self.colour(BLUE),
+                line,
+                self.colour(RESET),
+                line_text,
+                width = gutter_width
+            );
+
+            for label in labels.iter().filter(|label| self.source.start(label.span).line == line) {
+                let start = self.source.start(label.span);
+                let end = self.source.end(label.span);
+                let (marker, colour) = if label.primary {
+                    ('^', severity_colour)
+                } else {
+                    ('-', BLUE)
+                };
+                let underline = marker.to_string().repeat(Self::underlineWidth(start, end, line_text));
+                // keep tabs so the underline lines up with the source above it
+                let indent: String = line_text
+                    .chars()
+                    .take(start.column - 1)
+                    .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                    .collect();
+                let message = if label.message.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", label.message)
+                };
+                let _ = writeln!(
+                    out,
+                    "{} {}|{} {}{}{}{}{}",
+                    pad,
+                    self.colour(BLUE),
+                    self.colour(RESET),
+                    indent,
+                    self.colour(colour),
+                    underline,
+                    message,
+                    self.colour(RESET)
+                );
+            }
+        }
+
+        for note in &diagnostic.notes {
+            let _ = writeln!(
+                out,
+                "{} {}={} note: {}",
+                pad,
+                self.colour(BLUE),
+                self.colour(RESET),
+                note
+            );
+        }
+        out.push('\n');
+
+        out
+    }
+
+    // spans running past the end of their first line are underlined to the line end
+    fn underlineWidth(start: Position, end: Position, line_text: &str) -> usize {
+        let last_column = if end.line == start.line {
+            end.column
+        } else {
+            line_text.chars().count() + 1
+        };
+        last_column.saturating_sub(start.column).max(1)
+    }
+
+    fn renderJson(&self, diagnostic: &Diagnostic) -> String {
+        let labels: Vec<String> = diagnostic
+            .labels
+            .iter()
+            .map(|label| {
+                format!(
+                    "{{\"primary\":{},\"message\":{},\"start\":{},\"end\":{}}}",
+                    label.primary,
+                    jsonString(&label.message),
+                    self.jsonPosition(label.span.start),
+                    self.jsonPosition(label.span.end)
+                )
+            })
+            .collect();
+        let notes: Vec<String> = diagnostic.notes.iter().map(|note| jsonString(note)).collect();
+
+        format!(
+            "{{\"severity\":{},\"message\":{},\"file\":{},\"labels\":[{}],\"notes\":[{}]}}\n",
+            jsonString(diagnostic.severity.as_str()),
+            jsonString(&diagnostic.message),
+            jsonString(&self.source.name),
+            labels.join(","),
+            notes.join(",")
+        )
+    }
+
+    fn jsonPosition(&self, offset: usize) -> String {
+        let position = self.source.position(offset);
+        format!(
+            "{{\"offset\":{},\"line\":{},\"column\":{}}}",
+            offset.saturating_sub(self.source.base()),
+            position.line,
+            position.column
+        )
+    }
+
+    fn colour(&self, code: &'static str) -> &'static str {
+        if self.mode == RenderMode::Ansi {
+            code
+        } else {
+            ""
+        }
+    }
+}
+
+// quotes and escapes `text` as a JSON string literal
+pub fn jsonString(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
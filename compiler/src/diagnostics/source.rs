@@ -0,0 +1,151 @@
+use crate::lexer::span::Span;
+
+// a source text together with the byte offset of every line start,
+// used to turn span offsets into human line/column positions
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+    line_starts: Vec<usize>,
+    // only the line layout is known, e.g. for bytecode whose source has
+    // changed since; positions still resolve but there is no text to show
+    detached: bool,
+    // offset of the file's first byte in the spans pointing into it
+    base: usize,
+}
+
+// 1-based line and column, columns counted in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceFile {
+    pub fn new(name: &str, text: &str) -> Self {
+        let mut line_starts = vec![0];
+        for (offset, ch) in text.char_indices() {
+            if ch == '\n' {
+                line_starts.push(offset + 1);
+            }
+        }
+        Self {
+            name: name.to_string(),
+            text: text.to_string(),
+            line_starts,
+            detached: false,
+            base: 0,
+        }
+    }
+
+    pub fn detached(name: &str, line_starts: Vec<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            text: String::new(),
+            line_starts,
+            detached: true,
+            base: 0,
+        }
+    }
+
+    pub fn with_base(mut self, base: usize) -> Self {
+        self.base = base;
+        self
+    }
+
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached
+    }
+
+    pub fn line_starts(&self) -> &[usize] {
+        &self.line_starts
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.saturating_sub(self.base);
+        let offset = if self.detached { offset } else { self.clampToBoundary(offset) };
+        let line_index = match self.line_starts.binary_search(&offset) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+        let line_start = self.line_starts[line_index];
+        // without the text, columns can only be counted in bytes
+        let column = if self.detached {
+            offset - line_start + 1
+        } else {
+            self.text[line_start..offset].chars().count() + 1
+        };
+        Position {
+            line: line_index + 1,
+            column,
+        }
+    }
+
+    pub fn start(&self, span: Span) -> Position {
+        self.position(span.start)
+    }
+
+    pub fn end(&self, span: Span) -> Position {
+        self.position(span.end)
+    }
+
+    // text of a 1-based line without its line terminator
+    pub fn line(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .map(|next| next - 1)
+            .unwrap_or(self.text.len());
+        self.text[start..end].trim_end_matches('\r')
+    }
+
+    // spans may point one past the end (EOF) or, in theory, into the middle
+    // of a multi-byte character; both are moved to a valid boundary
+    fn clampToBoundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+// every file of a program; each file's spans start at its own base, so a
+// span alone tells which file it points into
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    // a base for the next file, one past the end of the last so that
+    // end-of-file spans still belong to the file they end
+    pub fn next_base(&self) -> usize {
+        match self.files.last() {
+            Some(file) => file.base + file.text.len() + 1,
+            None => 0,
+        }
+    }
+
+    pub fn add(&mut self, file: SourceFile) -> usize {
+        self.files.push(file);
+        self.files.len() - 1
+    }
+
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    // the file containing `offset`
+    pub fn find(&self, offset: usize) -> &SourceFile {
+        let index = self.files.partition_point(|file| file.base <= offset);
+        &self.files[index.saturating_sub(1)]
+    }
+}
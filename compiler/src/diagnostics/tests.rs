@@ -0,0 +1,100 @@
+use super::{Diagnostic, RenderMode, Renderer, SourceFile, SourceMap};
+use crate::lexer::span::Span;
+
+const SOURCE: &str = "var x = 1;\nx = \"one\" + 2;\n";
+
+// an error on the `"one" + 2` of SOURCE, with the declaration as context
+fn mismatch() -> Diagnostic {
+    Diagnostic::error("Mismatched types")
+        .with_primary(Span::new(15, 24), "this is 'string'")
+        .with_secondary(Span::new(4, 5), "declared here")
+        .with_note("convert with toString()")
+}
+
+// `text` without its `ESC[...m` colour escapes
+fn stripColours(text: &str) -> String {
+    let mut parts = text.split("\x1b[");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        out.push_str(part.split_once('m').map_or(part, |(_, rest)| rest));
+    }
+    out
+}
+
+#[test]
+fn plain_text_shows_each_line_with_its_labels() {
+    let file = SourceFile::new("main.rey", SOURCE);
+    let expected = "\
+error: Mismatched types
+ --> main.rey:2:5
+  |
+2 | x = \"one\" + 2;
+  |     ^^^^^^^^^ this is 'string'
+  |
+1 | var x = 1;
+  |     - declared here
+  = note: convert with toString()
+
+";
+    assert_eq!(Renderer::new(&file, RenderMode::Plain).render(&mismatch()), expected);
+}
+
+#[test]
+fn ansi_text_is_plain_text_with_colours() {
+    let file = SourceFile::new("main.rey", SOURCE);
+    let ansi = Renderer::new(&file, RenderMode::Ansi).render(&mismatch());
+    assert!(ansi.starts_with("\x1b[1;31merror\x1b[0m: \x1b[1mMismatched types\x1b[0m\n"), "got {:?}", ansi);
+    assert_eq!(stripColours(&ansi), Renderer::new(&file, RenderMode::Plain).render(&mismatch()));
+}
+
+#[test]
+fn json_gives_positions_and_escapes_text() {
+    let file = SourceFile::new("dir\\main.rey", SOURCE);
+    let expected = concat!(
+        r#"{"severity":"error","message":"Mismatched types","file":"dir\\main.rey","labels":["#,
+        r#"{"primary":true,"message":"this is 'string'","start":{"offset":15,"line":2,"column":5},"end":{"offset":24,"line":2,"column":14}},"#,
+        r#"{"primary":false,"message":"declared here","start":{"offset":4,"line":1,"column":5},"end":{"offset":5,"line":1,"column":6}}"#,
+        r#"],"notes":["convert with toString()"]}"#,
+        "\n"
+    );
+    assert_eq!(Renderer::new(&file, RenderMode::Json).render(&mismatch()), expected);
+}
+
+#[test]
+fn spans_over_several_lines_are_underlined_to_the_end_of_their_first() {
+    let file = SourceFile::new("main.rey", "func f() {\n    return;\n}\n");
+    let diagnostic = Diagnostic::error("Missing return").with_primary(Span::new(9, 23), "this block");
+    let expected = "\
+error: Missing return
+ --> main.rey:1:10
+  |
+1 | func f() {
+  |          ^ this block
+
+";
+    assert_eq!(Renderer::new(&file, RenderMode::Plain).render(&diagnostic), expected);
+}
+
+#[test]
+fn tabs_are_kept_under_the_source() {
+    let file = SourceFile::new("main.rey", "\tx = y;\n");
+    let diagnostic = Diagnostic::error("Undefined variable 'y'").with_primary(Span::new(5, 6), "");
+    let rendered = Renderer::new(&file, RenderMode::Plain).render(&diagnostic);
+    assert!(rendered.contains("1 | \tx = y;\n  | \t    ^\n"), "got {:?}", rendered);
+}
+
+#[test]
+fn source_maps_find_the_file_holding_an_offset() {
+    let mut sources = SourceMap::new();
+    let first = SourceFile::new("a.rey", "var a = 1;\n");
+    sources.add(first);
+    let base = sources.next_base();
+    sources.add(SourceFile::new("b.rey", "var b = 2;\nb = c;\n").with_base(base));
+
+    let diagnostic = Diagnostic::error("Undefined variable 'c'").with_primary(Span::new(base + 15, base + 16), "");
+    let file = sources.find(base + 15);
+    assert_eq!(file.name, "b.rey");
+    let rendered = Renderer::new(file, RenderMode::Json).render(&diagnostic);
+    assert!(rendered.contains(r#""start":{"offset":15,"line":2,"column":5}"#), "got {:?}", rendered);
+    assert_eq!(sources.find(3).name, "a.rey");
+}
@@ -0,0 +1,436 @@
+use crate::ast::{escapeString, floatText, stringText, Expr, Literal, Parameter, Stmt, Target, Type};
+use crate::lexer::span::Span;
+use crate::lexer::{Comment, TokenKind};
+
+const INDENT: &str = "    ";
+// precedence of prefix operators, and of calls, indexing and everything
+// that binds at least as tightly
+const UNARY: u8 = 7;
+const POSTFIX: u8 = 8;
+
+// prints a syntax tree back as canonically laid out source:
+// four-space indents, one statement per line and a blank line around
+// function, struct and impl declarations
+pub struct Formatter<'a> {
+    out: String,
+    depth: usize,
+    // source comments, written back before the statement that follows them
+    comments: Vec<Comment>,
+    nextComment: usize,
+    // the text the tree was parsed from; number and string literals keep
+    // their written form where it is known
+    source: Option<&'a str>,
+}
+
+impl<'a> Formatter<'a> {
+    pub fn new() -> Self {
+        Self {
+            out: String::new(),
+            depth: 0,
+            comments: Vec::new(),
+            nextComment: 0,
+            source: None,
+        }
+    }
+
+    pub fn with_comments(mut self, comments: Vec<Comment>) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    pub fn with_source(mut self, source: &'a str) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn format(mut self, statements: &[Stmt]) -> String {
+        self.writeStatements(statements);
+        self.writeComments(usize::MAX, false);
+        self.out
+    }
+
+    fn writeStatements(&mut self, statements: &[Stmt]) {
+        for (i, stmt) in statements.iter().enumerate() {
+            let start = stmt.span().start;
+            // a comment at the end of the previous line stays there, above
+            // the blank line that separates declarations
+            self.writeComments(start, true);
+            let declaration = Self::isDeclaration(stmt);
+            let afterDeclaration = i > 0 && Self::isDeclaration(&statements[i - 1]);
+            if i > 0 && (declaration || afterDeclaration) {
+                self.out.push('\n');
+            }
+            self.writeComments(start, false);
+            self.writeStmt(stmt);
+        }
+        // comments after the last statement of a block belong inside it
+        while self.comments.get(self.nextComment).is_some_and(|comment| comment.ends_block) {
+            self.writeComment();
+        }
+    }
+
+    // statements set apart by blank lines
+    fn isDeclaration(stmt: &Stmt) -> bool {
+        matches!(stmt, Stmt::FuncDecl { .. } | Stmt::Struct { .. } | Stmt::Impl { .. })
+    }
+
+    // writes the pending comments that start before `end`
+    fn writeComments(&mut self, end: usize, trailingOnly: bool) {
+        while let Some(comment) = self.comments.get(self.nextComment) {
+            if comment.span.start >= end || (trailingOnly && !comment.trailing) {
+                break;
+            }
+            self.writeComment();
+        }
+    }
+
+    fn writeComment(&mut self) {
+        let comment = self.comments[self.nextComment].clone();
+        self.nextComment += 1;
+        if comment.trailing && self.out.ends_with('\n') {
+            self.out.pop();
+            self.out.push(' ');
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+        } else {
+            // the later lines of a block comment are kept as written
+            self.indent();
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+        }
+    }
+
+    fn writeDoc(&mut self, doc: Option<&str>) {
+        for line in doc.into_iter().flat_map(|doc| doc.split('\n')) {
+            if line.is_empty() {
+                self.line("///");
+            } else {
+                self.line(&format!("/// {}", line));
+            }
+        }
+    }
+
+    fn writeStmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl {
+                doc,
+                name,
+                ty,
+                initializer,
+                ..
+            } => {
+                self.writeDoc(doc.as_deref());
+                let line = format!("var {}{} = {};", name, Self::annotation(ty.as_ref()), self.expr(initializer));
+                self.line(&line);
+            }
+            Stmt::FuncDecl {
+                doc,
+                name,
+                params,
+                return_ty,
+                body,
+                ..
+            } => {
+                self.writeDoc(doc.as_deref());
+                let header = format!(
+                    "func {}({}){} {{",
+                    name,
+                    Self::params(params),
+                    Self::annotation(return_ty.as_ref())
+                );
+                self.block(&header, body);
+                self.line("}");
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.block(&format!("if {} {{", self.expr(condition)), then_branch);
+                let mut else_branch = else_branch.as_deref();
+                // an `if` alone in the else branch is written as `else if`
+                while let Some([Stmt::If {
+                    condition,
+                    then_branch,
+                    else_branch: next,
+                    ..
+                }]) = else_branch
+                {
+                    self.block(&format!("}} else if {} {{", self.expr(condition)), then_branch);
+                    else_branch = next.as_deref();
+                }
+                if let Some(else_branch) = else_branch {
+                    self.block("} else {", else_branch);
+                }
+                self.line("}");
+            }
+            Stmt::While { condition, body, .. } => {
+                self.block(&format!("while {} {{", self.expr(condition)), body);
+                self.line("}");
+            }
+            Stmt::For {
+                variable,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                let header = format!(
+                    "for {} in range({}, {}) {{",
+                    variable,
+                    self.expr(start),
+                    self.expr(end)
+                );
+                self.block(&header, body);
+                self.line("}");
+            }
+            Stmt::ForEach {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                self.block(&format!("for {} in {} {{", variable, self.expr(iterable)), body);
+                self.line("}");
+            }
+            Stmt::Break { .. } => self.line("break;"),
+            Stmt::Continue { .. } => self.line("continue;"),
+            Stmt::Return { value, .. } => self.line(&format!("return {};", self.expr(value))),
+            Stmt::Import { path, names, .. } => match names {
+                Some(names) => {
+                    let names: Vec<&str> = names.iter().map(|name| name.name.as_str()).collect();
+                    self.line(&format!("import {} from {};", names.join(", "), stringText(path)));
+                }
+                None => self.line(&format!("import {};", stringText(path))),
+            },
+            Stmt::Struct { doc, name, fields, .. } => {
+                self.writeDoc(doc.as_deref());
+                self.line(&format!("struct {} {{", name));
+                self.depth += 1;
+                for field in fields {
+                    self.writeComments(field.span.start, false);
+                    self.writeDoc(field.doc.as_deref());
+                    self.line(&format!("{}{},", field.name, Self::annotation(field.ty.as_ref())));
+                }
+                self.depth -= 1;
+                self.line("}");
+            }
+            Stmt::Impl { name, methods, .. } => {
+                self.block(&format!("impl {} {{", name), methods);
+                self.line("}");
+            }
+            Stmt::ExprStmt(expr) => self.line(&format!("{};", self.expr(expr))),
+        }
+    }
+
+    // writes `header` and then `body` one level deeper
+    fn block(&mut self, header: &str, body: &[Stmt]) {
+        self.line(header);
+        self.depth += 1;
+        self.writeStatements(body);
+        self.depth -= 1;
+    }
+
+    // a lambda's body is the only thing that puts line breaks in an
+    // expression, and it is indented along with the statement holding it
+    fn line(&mut self, text: &str) {
+        for text in text.split('\n') {
+            if !text.is_empty() {
+                self.indent();
+            }
+            self.out.push_str(text);
+            self.out.push('\n');
+        }
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    // parentheses are not kept in the tree, so they are written back only
+    // where precedence needs them
+    fn expr(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::Literal { value, span } => match value {
+                // raw and `"""` strings, and escapes, stay as written
+                Literal::String(text) => match self.written(*span) {
+                    Some(written) => written.to_string(),
+                    None => stringText(text),
+                },
+                // `0xFF`, `1_000` and `1e9` stay as written
+                Literal::Int(_) | Literal::BigInt(_) | Literal::Float(_) => match self.written(*span) {
+                    Some(text) => text.to_string(),
+                    None => Self::number(value),
+                },
+                Literal::Bool(b) => b.to_string(),
+                Literal::Null => "null".to_string(),
+            },
+            Expr::Variable { name, .. } => name.clone(),
+            Expr::Binary { left, op, right, .. } => {
+                // operators group to the left, so a right operand of the
+                // same precedence keeps its parentheses
+                let precedence = Self::precedence(expr);
+                format!(
+                    "{} {} {}",
+                    self.operand(left, precedence),
+                    Self::operator(op),
+                    self.operand(right, precedence + 1)
+                )
+            }
+            Expr::Unary { op, right, .. } => {
+                let right = self.operand(right, UNARY);
+                // `- -x` must not turn into the `--` operator
+                if *op == TokenKind::Minus && right.starts_with('-') {
+                    format!("-({})", right)
+                } else {
+                    format!("{}{}", Self::operator(op), right)
+                }
+            }
+            Expr::Assign { target, op, value, .. } => {
+                let op = op.as_ref().map_or("", Self::operator);
+                format!("{} {}= {}", self.target(target), op, self.expr(value))
+            }
+            Expr::Update { target, op, prefix: true, .. } => format!("{}{}", Self::operator(op), self.target(target)),
+            Expr::Update { target, op, .. } => format!("{}{}", self.target(target), Self::operator(op)),
+            Expr::Call { callee, args, .. } => format!("{}({})", self.operand(callee, POSTFIX), self.list(args)),
+            Expr::Get { object, name, .. } => format!("{}.{}", self.operand(object, POSTFIX), name),
+            Expr::Array { elements, .. } => format!("[{}]", self.list(elements)),
+            Expr::Dict { entries, .. } => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", self.expr(key), self.expr(value)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Expr::Index { object, index, .. } => {
+                format!("{}[{}]", self.operand(object, POSTFIX), self.expr(index))
+            }
+            Expr::Lambda {
+                params,
+                return_ty,
+                body,
+                ..
+            } => match Self::arrowValue(expr) {
+                Some(value) => format!("({}) => {}", Self::params(params), self.expr(value)),
+                None => {
+                    let mut inner = Formatter::new();
+                    inner.source = self.source;
+                    inner.depth = 1;
+                    inner.writeStatements(body);
+                    let header = format!("func({}){}", Self::params(params), Self::annotation(return_ty.as_ref()));
+                    format!("{} {{\n{}}}", header, inner.out)
+                }
+            },
+            // written back whole, like other strings
+            Expr::Interpolation { parts, span } => match self.written(*span) {
+                Some(written) => written.to_string(),
+                None => {
+                    let parts: String = parts
+                        .iter()
+                        .map(|part| match part {
+                            Expr::Literal {
+                                value: Literal::String(text),
+                                ..
+                            } => escapeString(text),
+                            part => format!("{{{}}}", self.expr(part)),
+                        })
+                        .collect();
+                    format!("\"{}\"", parts)
+                }
+            },
+        }
+    }
+
+    fn number(value: &Literal) -> String {
+        match value {
+            Literal::Int(n) => n.to_string(),
+            Literal::BigInt(n) => n.to_string(),
+            Literal::Float(n) => floatText(*n),
+            other => unreachable!("{:?} is not a number", other),
+        }
+    }
+
+    // the source text at `span`
+    fn written(&self, span: Span) -> Option<&'a str> {
+        self.source?.get(span.start..span.end)
+    }
+
+    fn target(&self, target: &Target) -> String {
+        match target {
+            Target::Variable { name, .. } => name.clone(),
+            Target::Index { object, index, .. } => {
+                format!("{}[{}]", self.operand(object, POSTFIX), self.expr(index))
+            }
+            Target::Field { object, name, .. } => format!("{}.{}", self.operand(object, POSTFIX), name),
+        }
+    }
+
+    // the value of a lambda written as `(params) => value`, which every
+    // lambda whose body only returns a value is
+    fn arrowValue(expr: &Expr) -> Option<&Expr> {
+        match expr {
+            Expr::Lambda {
+                return_ty: None,
+                body,
+                ..
+            } => match body.as_slice() {
+                [Stmt::Return { value, .. }] => Some(value),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // `expr`, in parentheses when it binds less tightly than `minimum`
+    fn operand(&self, expr: &Expr, minimum: u8) -> String {
+        if Self::precedence(expr) < minimum {
+            format!("({})", self.expr(expr))
+        } else {
+            self.expr(expr)
+        }
+    }
+
+    fn precedence(expr: &Expr) -> u8 {
+        match expr {
+            Expr::Assign { .. } => 0,
+            // an arrow lambda's value extends as far as it can
+            Expr::Lambda { .. } if Self::arrowValue(expr).is_some() => 0,
+            Expr::Binary { op, .. } => match op {
+                TokenKind::OrOr => 1,
+                TokenKind::AndAnd => 2,
+                TokenKind::EqualEqual | TokenKind::BangEqual => 3,
+                TokenKind::Less | TokenKind::LessEqual | TokenKind::Greater | TokenKind::GreaterEqual => 4,
+                TokenKind::Plus | TokenKind::Minus => 5,
+                _ => 6,
+            },
+            Expr::Unary { .. } | Expr::Update { prefix: true, .. } => UNARY,
+            _ => POSTFIX,
+        }
+    }
+
+    fn list(&self, items: &[Expr]) -> String {
+        items.iter().map(|item| self.expr(item)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn operator(op: &TokenKind) -> &'static str {
+        op.lexeme().unwrap_or_default()
+    }
+
+    fn params(params: &[Parameter]) -> String {
+        params
+            .iter()
+            .map(|param| format!("{}{}", param.name, Self::annotation(param.ty.as_ref())))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn annotation(ty: Option<&Type>) -> String {
+        match ty {
+            Some(ty) => format!(": {}", ty.name),
+            None => String::new(),
+        }
+    }
+}
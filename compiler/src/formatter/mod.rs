@@ -0,0 +1,6 @@
+pub mod formatter;
+
+pub use formatter::Formatter;
+
+#[cfg(test)]
+mod tests;
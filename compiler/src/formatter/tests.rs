@@ -0,0 +1,37 @@
+use super::Formatter;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+// formats `source` the way `rey fmt` does
+fn format(source: &str) -> String {
+    let (tokens, lexErrors) = Lexer::new(source).tokenize();
+    assert!(lexErrors.is_empty(), "lexer errors: {:?}", lexErrors);
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "parser errors: {:?}", errors);
+    Formatter::new().with_source(source).format(&statements)
+}
+
+#[test]
+fn numbers_keep_their_written_form() {
+    let source = "var x = [0xFF_FF, 1_000_000, 0b1010, 0o17, 1e-9, 1e300, 2.50];\n";
+    assert_eq!(format(source), source);
+}
+
+#[test]
+fn numbers_without_source_are_written_canonically() {
+    let (tokens, _) = Lexer::new("var x = 0x10 + 1e3;").tokenize();
+    let (statements, _) = Parser::new(tokens).parse();
+    assert_eq!(Formatter::new().format(&statements), "var x = 16 + 1000.0;\n");
+}
+
+#[test]
+fn strings_keep_their_written_form() {
+    let source = "var raw = r\"x\\n\";\nvar text = \"\"\"\n    one\n    {raw} two\n    \"\"\";\nprintln(\"tab\\t\", text);\n";
+    assert_eq!(format(source), source);
+}
+
+#[test]
+fn field_doc_comments_are_kept() {
+    let source = "struct P {\n    /// the x\n    x: int,\n    y,\n}\n";
+    assert_eq!(format(source), source);
+}
@@ -1,11 +1,13 @@
 use super::value::Value;
+use crate::lexer::span::Span;
 
 #[derive(Debug, Clone)]
 pub enum ControlFlow {
     Normal(Value),
     Return(Value),
-    Break,
-    Continue,
+    // spans of the `break`/`continue` statements, for errors outside loops
+    Break(Span),
+    Continue(Span),
 }
 
 impl ControlFlow {
@@ -16,18 +18,4 @@ impl ControlFlow {
     pub fn return_value(value: Value) -> Self {
         ControlFlow::Return(value)
     }
-
-    pub fn unwrap_normal(self) -> Value {
-        match self {
-            ControlFlow::Normal(value) => value,
-            _ => panic!("Expected normal control flow"),
-        }
-    }
-
-    pub fn unwrap_return(self) -> Value {
-        match self {
-            ControlFlow::Return(value) => value,
-            _ => panic!("Expected return control flow"),
-        }
-    }
 }
\ No newline at end of file
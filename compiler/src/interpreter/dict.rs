@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::value::{exact_int, Value};
+use crate::ast::floatText;
+use crate::bigint::BigInt;
+
+// values that can be used as dictionary keys
+// a whole float is keyed as the int it equals, so `d[1]` and `d[1.0]` are
+// the same entry; other floats are keyed by their bit pattern
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DictKey {
+    String(String),
+    Int(i64),
+    BigInt(BigInt),
+    Float(u64),
+    Bool(bool),
+}
+
+impl DictKey {
+    pub fn from_value(value: &Value) -> Option<DictKey> {
+        match value {
+            Value::String(s) => Some(DictKey::String(s.clone())),
+            Value::Int(n) => Some(DictKey::Int(*n)),
+            Value::BigInt(n) => Some(DictKey::BigInt(n.as_ref().clone())),
+            Value::Float(n) => match (exact_int(*n), BigInt::from_f64(*n)) {
+                (Some(n), _) => Some(DictKey::Int(n)),
+                (None, Some(n)) => Some(DictKey::BigInt(n)),
+                (None, None) => Some(DictKey::Float(n.to_bits())),
+            },
+            Value::Bool(b) => Some(DictKey::Bool(*b)),
+            _ => None,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            DictKey::String(s) => Value::String(s.clone()),
+            DictKey::Int(n) => Value::Int(*n),
+            DictKey::BigInt(n) => Value::BigInt(Rc::new(n.clone())),
+            DictKey::Float(bits) => Value::Float(f64::from_bits(*bits)),
+            DictKey::Bool(b) => Value::Bool(*b),
+        }
+    }
+}
+
+// shown the way the key would be written in a literal
+impl fmt::Display for DictKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DictKey::String(s) => write!(f, "{:?}", s),
+            DictKey::Int(n) => write!(f, "{}", n),
+            DictKey::BigInt(n) => write!(f, "{}", n),
+            DictKey::Float(bits) => write!(f, "{}", floatText(f64::from_bits(*bits))),
+            DictKey::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+// map that remembers insertion order; keys, values and iteration all
+// follow the order keys were first inserted in
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dict {
+    entries: Vec<(DictKey, Value)>,
+    positions: HashMap<DictKey, usize>,
+}
+
+impl Dict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, key: &DictKey) -> Option<&Value> {
+        self.positions.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains(&self, key: &DictKey) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    // overwriting an existing key keeps its original position
+    pub fn insert(&mut self, key: DictKey, value: Value) {
+        match self.positions.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &DictKey) -> Option<Value> {
+        let index = self.positions.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+        for position in self.positions.values_mut() {
+            if *position > index {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(DictKey, Value)> {
+        self.entries.iter()
+    }
+}
@@ -1,59 +1,62 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use super::value::Value;
 
+// scopes are shared: a closure and the code that created it see the
+// same bindings, so writes through either are visible to both
+pub type SharedEnvironment = Rc<RefCell<Environment>>;
+
 pub struct Environment {
     values: HashMap<String, Value>,
-    parent: Option<Rc<Environment>>,
-}
-
-impl Clone for Environment {
-    fn clone(&self) -> Self {
-        Self {
-            values: self.values.clone(),
-            parent: self.parent.clone(),
-        }
-    }
+    parent: Option<SharedEnvironment>,
 }
 
 impl Environment {
-    pub fn new() -> Self {
-        Self {
+    pub fn new() -> SharedEnvironment {
+        Rc::new(RefCell::new(Self {
             values: HashMap::new(),
             parent: None,
-        }
+        }))
     }
-    pub fn with_parent(parent: Environment) -> Self {
-        Self {
+    pub fn with_parent(parent: SharedEnvironment) -> SharedEnvironment {
+        Rc::new(RefCell::new(Self {
             values: HashMap::new(),
-            parent: Some(Rc::new(parent)),
-        }
+            parent: Some(parent),
+        }))
     }
 
     pub fn define(&mut self, name: String, value: Value) {
         self.values.insert(name, value);
     }
 
-    pub fn get(&self, name: &str) -> Option<&Value> {
+    pub fn get(&self, name: &str) -> Option<Value> {
         if let Some(v) = self.values.get(name) {
-            Some(v)
+            Some(v.clone())
         } else if let Some(parent) = &self.parent {
-            parent.get(name)
+            parent.borrow().get(name)
         } else {
             None
         }
     }
 
+    // bindings declared directly in this scope, not its parents
+    pub fn bindings(&self) -> Vec<(String, Value)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
 
-    
-    pub fn assign(&mut self, name: &str, value: Value) -> Result<(), String> {
-        if self.values.contains_key(name) {
-            self.values.insert(name.to_string(), value);
-            Ok(())
+    // updates the nearest existing binding, false if `name` is not defined
+    pub fn assign(&mut self, name: &str, value: Value) -> bool {
+        if let Some(slot) = self.values.get_mut(name) {
+            *slot = value;
+            true
+        } else if let Some(parent) = &self.parent {
+            parent.borrow_mut().assign(name, value)
         } else {
-            Err(format!("Undefined variable '{}'", name))
+            false
         }
     }
-
-
-}
\ No newline at end of file
+}
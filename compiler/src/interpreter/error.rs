@@ -0,0 +1,147 @@
+use std::fmt;
+
+use crate::diagnostics::Diagnostic;
+use crate::lexer::span::Span;
+use crate::lexer::TokenKind;
+
+// errors raised while running a program, each pointing at the node that failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    UndefinedVariable {
+        name: String,
+        span: Span,
+    },
+
+    InvalidOperands {
+        op: TokenKind,
+        left: &'static str,
+        right: &'static str,
+        span: Span,
+    },
+
+    InvalidOperand {
+        op: TokenKind,
+        operand: &'static str,
+        span: Span,
+    },
+
+    DivisionByZero {
+        span: Span,
+    },
+
+    ArityMismatch {
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+
+    NotCallable {
+        found: &'static str,
+        span: Span,
+    },
+
+    IndexOutOfBounds {
+        index: i64,
+        length: usize,
+        span: Span,
+    },
+
+    MissingKey {
+        key: String,
+        span: Span,
+    },
+
+    // `main` can only receive the command-line arguments
+    MainParameters {
+        count: usize,
+        span: Span,
+    },
+
+    Custom {
+        message: String,
+        span: Span,
+    },
+}
+
+impl RuntimeError {
+    pub fn new(message: String, span: Span) -> Self {
+        Self::Custom { message, span }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let label = match self {
+            RuntimeError::UndefinedVariable { .. } => "not defined in this scope".to_string(),
+            RuntimeError::InvalidOperands { left, right, .. } => {
+                format!("operands are {} and {}", left, right)
+            }
+            RuntimeError::InvalidOperand { operand, .. } => format!("operand is {}", operand),
+            RuntimeError::DivisionByZero { .. } => "divisor is zero".to_string(),
+            RuntimeError::ArityMismatch { found, .. } => format!("called with {} arguments", found),
+            RuntimeError::NotCallable { found, .. } => format!("this is a {}", found),
+            RuntimeError::IndexOutOfBounds { length: 0, .. } => "the array is empty".to_string(),
+            RuntimeError::IndexOutOfBounds { length, .. } => {
+                format!("valid indices are 0 to {}", length - 1)
+            }
+            RuntimeError::MissingKey { .. } => "no such key".to_string(),
+            RuntimeError::MainParameters { count, .. } => format!("declared with {} parameters", count),
+            RuntimeError::Custom { .. } => String::new(),
+        };
+        let diagnostic = Diagnostic::error(self.to_string()).with_primary(self.span(), label);
+        match self {
+            RuntimeError::MissingKey { .. } => {
+                diagnostic.with_note("use `get` to read a key that may be absent")
+            }
+            _ => diagnostic,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            RuntimeError::UndefinedVariable { span, .. }
+            | RuntimeError::InvalidOperands { span, .. }
+            | RuntimeError::InvalidOperand { span, .. }
+            | RuntimeError::DivisionByZero { span }
+            | RuntimeError::ArityMismatch { span, .. }
+            | RuntimeError::NotCallable { span, .. }
+            | RuntimeError::IndexOutOfBounds { span, .. }
+            | RuntimeError::MissingKey { span, .. }
+            | RuntimeError::MainParameters { span, .. }
+            | RuntimeError::Custom { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::UndefinedVariable { name, .. } => {
+                write!(f, "Undefined variable '{}'", name)
+            }
+            RuntimeError::InvalidOperands {
+                op, left, right, ..
+            } => write!(f, "Cannot apply {} to {} and {}", op, left, right),
+            RuntimeError::InvalidOperand { op, operand, .. } => {
+                write!(f, "Cannot apply {} to {}", op, operand)
+            }
+            RuntimeError::DivisionByZero { .. } => write!(f, "Division by zero"),
+            RuntimeError::ArityMismatch {
+                expected, found, ..
+            } => write!(f, "Expected {} arguments but got {}", expected, found),
+            RuntimeError::NotCallable { found, .. } => {
+                write!(f, "Can only call functions, got {}", found)
+            }
+            RuntimeError::IndexOutOfBounds { index, length, .. } => write!(
+                f,
+                "Index {} is out of bounds for an array of length {}",
+                index, length
+            ),
+            RuntimeError::MissingKey { key, .. } => {
+                write!(f, "Key {} not found in dictionary", key)
+            }
+            RuntimeError::MainParameters { .. } => {
+                write!(f, "'main' takes at most one parameter, the command-line arguments")
+            }
+            RuntimeError::Custom { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
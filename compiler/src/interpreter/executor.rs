@@ -1,284 +1,441 @@
-use crate::ast::{Expr, Stmt};
+use crate::ast::{Expr, Stmt, Target};
 use crate::lexer::span::Span;
 use crate::lexer::TokenKind;
 use super::control_flow::ControlFlow;
-use super::environment::Environment;
-use super::function::Function;
+use super::dict::Dict;
+use super::environment::{Environment, SharedEnvironment};
+use super::error::RuntimeError;
+use super::function::{Function, MAX_CALL_DEPTH};
+use super::methods;
+use super::structs::StructType;
+use super::operators;
 use super::value::Value;
+use crate::modules::moduleName;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
 
-pub struct Executor;
+// an assignment target whose object and index have been evaluated
+enum Place<'a> {
+    Variable {
+        name: &'a str,
+        span: Span,
+    },
+    Index {
+        target: Value,
+        position: Value,
+        object_span: Span,
+        index_span: Span,
+    },
+    Field {
+        object: Value,
+        name: &'a str,
+        span: Span,
+    },
+}
+
+pub struct Executor {
+    // module values for the import paths of the file being run
+    imports: RefCell<HashMap<String, Value>>,
+    // number of function calls currently running
+    depth: Cell<usize>,
+}
 
 impl Executor {
     pub fn new() -> Self {
-        Self
+        Self {
+            imports: RefCell::new(HashMap::new()),
+            depth: Cell::new(0),
+        }
+    }
+
+    pub fn set_imports(&self, imports: HashMap<String, Value>) {
+        *self.imports.borrow_mut() = imports;
     }
 
-    pub fn execute(&self, stmt: &Stmt, env: &mut Environment) -> Result<ControlFlow, String> {
+    pub fn execute(&self, stmt: &Stmt, env: &SharedEnvironment) -> Result<ControlFlow, RuntimeError> {
         match stmt {
             Stmt::VarDecl { name, initializer, .. } => {
                 let value = self.evaluate_expr(initializer, env)?;
-                env.define(name.clone(), value);
+                env.borrow_mut().define(name.clone(), value);
                 Ok(ControlFlow::normal(Value::Null))
             }
             Stmt::ExprStmt(expr) => {
                 let value = self.evaluate_expr(expr, env)?;
                 Ok(ControlFlow::normal(value))
             }
-            Stmt::FuncDecl { name, params, body, .. } => {
+            Stmt::FuncDecl { name, params, body, span, .. } => {
                 let function = Function::new(
                     name.clone(),
                     params.clone(),
                     body.clone(),
-                    Span { start: 0, end: 0 }, // TODO: Get actual span
+                    *span,
+                    env.clone(),
                 );
-                env.define(name.clone(), Value::Function(function));
+                env.borrow_mut().define(name.clone(), Value::Function(Rc::new(function)));
                 Ok(ControlFlow::normal(Value::Null))
             }
-            Stmt::If { condition, then_branch, else_branch } => {
+            Stmt::If { condition, then_branch, else_branch, .. } => {
                 let condition_value = self.evaluate_expr(condition, env)?;
-                if self.isTruthy(&condition_value) {
-                    match self.execute_block_with_control_flow(then_branch, env)? {
-                        ControlFlow::Normal(_) => {}
-                        ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
-                        ControlFlow::Break | ControlFlow::Continue => {
-                            return Err("Break/continue not allowed in if statement".to_string());
-                        }
-                    }
+                // break/continue/return propagate to the enclosing loop or function
+                if condition_value.is_truthy() {
+                    self.execute_block_with_control_flow(then_branch, env)
                 } else if let Some(else_branch) = else_branch {
-                    match self.execute_block_with_control_flow(else_branch, env)? {
-                        ControlFlow::Normal(_) => {}
-                        ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
-                        ControlFlow::Break | ControlFlow::Continue => {
-                            return Err("Break/continue not allowed in if statement".to_string());
-                        }
-                    }
+                    self.execute_block_with_control_flow(else_branch, env)
+                } else {
+                    Ok(ControlFlow::normal(Value::Null))
                 }
-                Ok(ControlFlow::normal(Value::Null))
             }
-            Stmt::While { condition, body } => {
-                while self.isTruthy(&self.evaluate_expr(condition, env)?) {
+            Stmt::While { condition, body, .. } => {
+                while self.evaluate_expr(condition, env)?.is_truthy() {
                     match self.execute_block_with_control_flow(body, env)? {
-                        ControlFlow::Break => break,
-                        ControlFlow::Continue => continue,
+                        ControlFlow::Break(_) => break,
+                        ControlFlow::Continue(_) => continue,
                         ControlFlow::Return(value) => return Ok(ControlFlow::return_value(value)),
                         ControlFlow::Normal(_) => {} // Continue to next iteration
                     }
                 }
                 Ok(ControlFlow::normal(Value::Null))
             }
-            Stmt::For { variable, start, end, body } => {
-                // Evaluate start and end expressions
-                let start_val = self.evaluate_expr(start, env)?;
-                let end_val = self.evaluate_expr(end, env)?;
-
-                // Extract numeric values
-                let start_num = match start_val {
-                    Value::Number(n) => n as i64,
-                    _ => return Err("Range start must be a number".to_string()),
-                };
-                let end_num = match end_val {
-                    Value::Number(n) => n as i64,
-                    _ => return Err("Range end must be a number".to_string()),
-                };
+            Stmt::For { variable, start, end, body, .. } => {
+                // Evaluate the bounds, which must be ints
+                let start_num = methods::range_bound(self.evaluate_expr(start, env)?, "start", start.span())?;
+                let end_num = methods::range_bound(self.evaluate_expr(end, env)?, "end", end.span())?;
 
                 // Loop from start to end-1
                 for i in start_num..end_num {
                     // Set the loop variable
-                    env.define(variable.clone(), Value::Number(i as f64));
+                    env.borrow_mut().define(variable.clone(), Value::Int(i));
 
                     // Execute body
                     match self.execute_block_with_control_flow(body, env)? {
-                        ControlFlow::Break => break,
-                        ControlFlow::Continue => continue,
+                        ControlFlow::Break(_) => break,
+                        ControlFlow::Continue(_) => continue,
                         ControlFlow::Return(value) => return Ok(ControlFlow::return_value(value)),
                         ControlFlow::Normal(_) => {} // Continue to next iteration
                     }
                 }
                 Ok(ControlFlow::normal(Value::Null))
             }
-            Stmt::Break => {
-                Ok(ControlFlow::Break)
+            Stmt::ForEach { variable, iterable, body, .. } => {
+                // iterate over a snapshot so the body may modify the collection
+                let items = match self.evaluate_expr(iterable, env)? {
+                    Value::Array(elements) => elements.borrow().clone(),
+                    Value::Dict(entries) => entries.borrow().iter().map(|(key, _)| key.to_value()).collect(),
+                    other => {
+                        return Err(RuntimeError::new(
+                            format!("Cannot iterate over {}", other.type_name()),
+                            iterable.span(),
+                        ))
+                    }
+                };
+
+                for item in items {
+                    env.borrow_mut().define(variable.clone(), item);
+                    match self.execute_block_with_control_flow(body, env)? {
+                        ControlFlow::Break(_) => break,
+                        ControlFlow::Continue(_) => continue,
+                        ControlFlow::Return(value) => return Ok(ControlFlow::return_value(value)),
+                        ControlFlow::Normal(_) => {}
+                    }
+                }
+                Ok(ControlFlow::normal(Value::Null))
             }
-            Stmt::Continue => {
-                Ok(ControlFlow::Continue)
+            Stmt::Break { span } => {
+                Ok(ControlFlow::Break(*span))
             }
-            Stmt::Return(expr) => {
-                let value = self.evaluate_expr(expr, env)?;
+            Stmt::Continue { span } => {
+                Ok(ControlFlow::Continue(*span))
+            }
+            Stmt::Return { value, .. } => {
+                let value = self.evaluate_expr(value, env)?;
                 Ok(ControlFlow::return_value(value))
             }
+            Stmt::Struct { name, fields, .. } => {
+                let fields = fields.iter().map(|field| field.name.clone()).collect();
+                let ty = StructType::new(name.clone(), fields);
+                env.borrow_mut().define(name.clone(), Value::StructType(Rc::new(ty)));
+                Ok(ControlFlow::normal(Value::Null))
+            }
+            Stmt::Impl { name, methods: declared, span } => {
+                let target = Self::read_variable(name, *span, env)?;
+                for method in declared {
+                    if let Stmt::FuncDecl { name, params, body, span, .. } = method {
+                        let ty = methods::impl_target(&target, *span)?;
+                        let function = Function::new(name.clone(), params.clone(), body.clone(), *span, env.clone());
+                        ty.add_method(name.clone(), Value::Function(Rc::new(function)));
+                    }
+                }
+                Ok(ControlFlow::normal(Value::Null))
+            }
+            Stmt::Import { path, names, span } => {
+                let module = self.imports.borrow().get(path).cloned().ok_or_else(|| {
+                    RuntimeError::new(format!("The module '{}' was not loaded", path), *span)
+                })?;
+                match names {
+                    Some(names) => {
+                        for imported in names {
+                            let value = methods::get_property(&module, &imported.name, imported.span)?;
+                            env.borrow_mut().define(imported.name.clone(), value);
+                        }
+                    }
+                    None => {
+                        if let Some(name) = moduleName(path) {
+                            env.borrow_mut().define(name, module);
+                        }
+                    }
+                }
+                Ok(ControlFlow::normal(Value::Null))
+            }
         }
     }
 
-    pub fn evaluate_expr(&self, expr: &Expr, env: &mut Environment) -> Result<Value, String> {
+    pub fn evaluate_expr(&self, expr: &Expr, env: &SharedEnvironment) -> Result<Value, RuntimeError> {
         match expr {
-            Expr::Literal(lit) => Ok(Value::from(lit.clone())),
-            Expr::Variable(name) => env
-                .get(name)
-                .cloned()
-                .ok_or_else(|| format!("Undefined variable '{}'", name)),
-            Expr::Binary { left, op, right } => {
+            Expr::Literal { value, .. } => Ok(Value::from(value.clone())),
+            Expr::Variable { name, span } => Self::read_variable(name, *span, env),
+            Expr::Binary {
+                left,
+                op: op @ (TokenKind::AndAnd | TokenKind::OrOr),
+                right,
+                ..
+            } => {
+                let left_val = operators::logical_operand(op, &self.evaluate_expr(left, env)?, left.span())?;
+                if left_val == (*op == TokenKind::OrOr) {
+                    return Ok(Value::Bool(left_val));
+                }
+                let right_val = operators::logical_operand(op, &self.evaluate_expr(right, env)?, right.span())?;
+                Ok(Value::Bool(right_val))
+            }
+            Expr::Binary { left, op, right, span } => {
                 let left_val = self.evaluate_expr(left, env)?;
                 let right_val = self.evaluate_expr(right, env)?;
-                self.evaluate_binary(left_val, op, right_val)
+                operators::binary(left_val, op, right_val, *span)
             }
-            Expr::Unary { op, right } => {
+            Expr::Unary { op, right, span } => {
                 let right_val = self.evaluate_expr(right, env)?;
-                self.evaluate_unary(op, right_val)
+                operators::unary(op, right_val, *span)
             }
-            Expr::Assign { name, value } => {
-                let val = self.evaluate_expr(value, env)?;
-                env.assign(name, val.clone())?;
+            Expr::Assign { target, op, value, span } => {
+                let place = self.place(target, env)?;
+                let val = match op {
+                    Some(op) => {
+                        let current = self.read_place(&place, env)?;
+                        operators::binary(current, op, self.evaluate_expr(value, env)?, *span)?
+                    }
+                    None => self.evaluate_expr(value, env)?,
+                };
+                self.write_place(&place, val.clone(), env)?;
                 Ok(val)
             }
-            Expr::Call { callee, args } => {
-                // Check if it's a built-in function first
-                if let Expr::Variable(name) = callee.as_ref() {
+            Expr::Update { target, op, prefix, span } => {
+                let place = self.place(target, env)?;
+                let current = self.read_place(&place, env)?;
+                let step = if *op == TokenKind::PlusPlus { TokenKind::Plus } else { TokenKind::Minus };
+                let updated = operators::binary(current.clone(), &step, Value::Int(1), *span)?;
+                self.write_place(&place, updated.clone(), env)?;
+                Ok(if *prefix { updated } else { current })
+            }
+            Expr::Call { callee, args, span } => {
+                // `object.name(args)` calls a built-in method on the object
+                if let Expr::Get { object, name, .. } = callee.as_ref() {
+                    let receiver = self.evaluate_expr(object, env)?;
                     let mut evaluated_args = Vec::new();
                     for arg in args {
                         evaluated_args.push(self.evaluate_expr(arg, env)?);
                     }
-
-                    if let Some(result) = super::std::StdLib::call_builtin_function(name, &evaluated_args) {
-                        result
-                    } else {
-                        // Not a built-in, check if it's a user-defined function
-                        let function = self.evaluate_expr(callee, env)?;
-                        match function {
-                            Value::Function(func) => {
-                                if args.len() != func.arity() {
-                                    return Err(format!(
-                                        "Expected {} arguments but got {}",
-                                        func.arity(),
-                                        args.len()
-                                    ));
-                                }
-
-                                // Create new environment with function parameters
-                                // The function environment should have access to the global environment
-                                let mut function_env = Environment::with_parent(env.clone());
-
-                                // Bind parameters to arguments
-                                for (param, arg_value) in func.params.iter().zip(evaluated_args) {
-                                    function_env.define(param.name.clone(), arg_value);
-                                }
-
-                                // Execute function body
-                                self.execute_block(&func.body, &mut function_env)
+                    // a field holding a function is called like one; a method
+                    // gets the receiver as its first argument
+                    if let Value::Struct(instance) = &receiver {
+                        if let Some(function) = instance.get(name) {
+                            return self.call_value(function, evaluated_args, *span, callee.span());
+                        }
+                        if let Some(method) = instance.ty.method(name) {
+                            if let Value::Function(function) = &method {
+                                methods::check_method_arity(function.arity(), evaluated_args.len(), *span)?;
                             }
-                            _ => Err(format!("Can only call functions, got {:?}", function)),
+                            evaluated_args.insert(0, receiver.clone());
+                            return self.call_value(method, evaluated_args, *span, callee.span());
                         }
                     }
-                } else {
-                    // Dynamic function call (function as expression)
-                    let function = self.evaluate_expr(callee, env)?;
-                    match function {
-                        Value::Function(func) => {
-                            if args.len() != func.arity() {
-                                return Err(format!(
-                                    "Expected {} arguments but got {}",
-                                    func.arity(),
-                                    args.len()
-                                ));
-                            }
-
-                            // Evaluate arguments
-                            let mut evaluated_args = Vec::new();
-                            for arg in args {
-                                evaluated_args.push(self.evaluate_expr(arg, env)?);
-                            }
-
-                            // Create new environment with function parameters
-                            // The function environment should have access to the global environment
-                            let mut function_env = Environment::with_parent(env.clone());
+                    // `module.name(args)` calls the module's export instead
+                    if let Value::Module(_) = receiver {
+                        let function = methods::get_property(&receiver, name, callee.span())?;
+                        return self.call_value(function, evaluated_args, *span, callee.span());
+                    }
+                    return methods::call_method(&receiver, name, evaluated_args, *span);
+                }
 
-                            // Bind parameters to arguments
-                            for (param, arg_value) in func.params.iter().zip(evaluated_args) {
-                                function_env.define(param.name.clone(), arg_value);
-                            }
+                let mut evaluated_args = Vec::new();
+                for arg in args {
+                    evaluated_args.push(self.evaluate_expr(arg, env)?);
+                }
 
-                            // Execute function body
-                            self.execute_block(&func.body, &mut function_env)
-                        }
-                        _ => Err(format!("Can only call functions, got {:?}", function)),
+                // Check if it's a built-in function first
+                if let Expr::Variable { name, .. } = callee.as_ref() {
+                    if let Some(result) = super::std::StdLib::call_builtin_function(name, &evaluated_args) {
+                        return result.map_err(|message| RuntimeError::new(message, *span));
                     }
                 }
+
+                let function = self.evaluate_expr(callee, env)?;
+                self.call_value(function, evaluated_args, *span, callee.span())
             }
-            Expr::Get { .. } => {
-                Err("Property access not implemented yet".to_string())
+            Expr::Get { object, name, span } => {
+                let receiver = self.evaluate_expr(object, env)?;
+                methods::get_property(&receiver, name, *span)
+            }
+            Expr::Array { elements, .. } => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(self.evaluate_expr(element, env)?);
+                }
+                Ok(Value::array(values))
+            }
+            Expr::Lambda { params, body, span, .. } => {
+                let function = Function::new("<lambda>".to_string(), params.clone(), body.clone(), *span, env.clone());
+                Ok(Value::Function(Rc::new(function)))
+            }
+            Expr::Interpolation { parts, .. } => {
+                let mut text = String::new();
+                for part in parts {
+                    text.push_str(&self.evaluate_expr(part, env)?.to_string());
+                }
+                Ok(Value::String(text))
+            }
+            Expr::Dict { entries, .. } => {
+                let mut dict = Dict::new();
+                for (key, value) in entries {
+                    let k = self.evaluate_expr(key, env)?;
+                    let k = methods::dict_key(&k, key.span())?;
+                    dict.insert(k, self.evaluate_expr(value, env)?);
+                }
+                Ok(Value::dict(dict))
+            }
+            Expr::Index { object, index, .. } => {
+                let target = self.evaluate_expr(object, env)?;
+                let position = self.evaluate_expr(index, env)?;
+                methods::get_index(&target, &position, object.span(), index.span())
             }
         }
     }
 
-    fn isTruthy(&self, value: &Value) -> bool {
-        match value {
-            Value::Bool(false) => false,
-            Value::Null => false,
-            Value::Number(n) => *n != 0.0,
-            _ => true,
-        }
+    // evaluates the object and index of `target` once, so that compound
+    // assignment and `++` read and write the same element
+    fn place<'a>(&self, target: &'a Target, env: &SharedEnvironment) -> Result<Place<'a>, RuntimeError> {
+        Ok(match target {
+            Target::Variable { name, span } => Place::Variable { name, span: *span },
+            Target::Index { object, index, .. } => Place::Index {
+                target: self.evaluate_expr(object, env)?,
+                position: self.evaluate_expr(index, env)?,
+                object_span: object.span(),
+                index_span: index.span(),
+            },
+            Target::Field { object, name, span } => Place::Field {
+                object: self.evaluate_expr(object, env)?,
+                name,
+                span: *span,
+            },
+        })
     }
 
-    fn evaluate_binary(&self, left: Value, op: &TokenKind, right: Value) -> Result<Value, String> {
-        use TokenKind::*;
+    fn read_place(&self, place: &Place, env: &SharedEnvironment) -> Result<Value, RuntimeError> {
+        match place {
+            Place::Variable { name, span } => Self::read_variable(name, *span, env),
+            Place::Index { target, position, object_span, index_span } => {
+                methods::get_index(target, position, *object_span, *index_span)
+            }
+            Place::Field { object, name, span } => methods::get_property(object, name, *span),
+        }
+    }
 
-        match (left, op, right) {
-            (Value::Number(l), Plus, Value::Number(r)) => Ok(Value::Number(l + r)),
-            (Value::Number(l), Minus, Value::Number(r)) => Ok(Value::Number(l - r)),
-            (Value::Number(l), Star, Value::Number(r)) => Ok(Value::Number(l * r)),
-            (Value::Number(l), Slash, Value::Number(r)) => {
-                if r == 0.0 {
-                    Err("Division by zero".to_string())
-                } else {
-                    Ok(Value::Number(l / r))
+    fn write_place(&self, place: &Place, value: Value, env: &SharedEnvironment) -> Result<(), RuntimeError> {
+        match place {
+            Place::Variable { name, span } => {
+                if !env.borrow_mut().assign(name, value) {
+                    return Err(RuntimeError::UndefinedVariable {
+                        name: name.to_string(),
+                        span: *span,
+                    });
                 }
+                Ok(())
             }
-            (Value::Number(l), EqualEqual, Value::Number(r)) => Ok(Value::Bool(l == r)),
-            (Value::Number(l), BangEqual, Value::Number(r)) => Ok(Value::Bool(l != r)),
-            (Value::Number(l), Less, Value::Number(r)) => Ok(Value::Bool(l < r)),
-            (Value::Number(l), LessEqual, Value::Number(r)) => Ok(Value::Bool(l <= r)),
-            (Value::Number(l), Greater, Value::Number(r)) => Ok(Value::Bool(l > r)),
-            (Value::Number(l), GreaterEqual, Value::Number(r)) => Ok(Value::Bool(l >= r)),
-
-            (Value::String(l), Plus, Value::String(r)) => Ok(Value::String(l + &r)),
-            (Value::String(l), EqualEqual, Value::String(r)) => Ok(Value::Bool(l == r)),
-            (Value::String(l), BangEqual, Value::String(r)) => Ok(Value::Bool(l != r)),
+            Place::Index { target, position, object_span, index_span } => {
+                methods::set_index(target, position, value, *object_span, *index_span)
+            }
+            Place::Field { object, name, span } => methods::set_property(object, name, value, *span),
+        }
+    }
 
-            (Value::Bool(l), EqualEqual, Value::Bool(r)) => Ok(Value::Bool(l == r)),
-            (Value::Bool(l), BangEqual, Value::Bool(r)) => Ok(Value::Bool(l != r)),
-            (Value::Bool(l), And, Value::Bool(r)) => Ok(Value::Bool(l && r)),
-            (Value::Bool(l), Or, Value::Bool(r)) => Ok(Value::Bool(l || r)),
+    fn read_variable(name: &str, span: Span, env: &SharedEnvironment) -> Result<Value, RuntimeError> {
+        env.borrow().get(name).ok_or_else(|| RuntimeError::UndefinedVariable {
+            name: name.to_string(),
+            span,
+        })
+    }
 
-            _ => Err("Invalid binary operation".to_string()),
+    // `callee_span` is reported when `function` turns out not to be callable
+    fn call_value(&self, function: Value, args: Vec<Value>, span: Span, callee_span: Span) -> Result<Value, RuntimeError> {
+        match function {
+            // a built-in bound to another name
+            Value::Function(func) if func.body.is_empty() && super::std::StdLib::is_builtin(&func.name) => {
+                super::std::StdLib::call_builtin_function(&func.name, &args)
+                    .unwrap_or(Ok(Value::Null))
+                    .map_err(|message| RuntimeError::new(message, span))
+            }
+            Value::Function(func) => self.call_function(&func, args, span),
+            Value::StructType(ty) => methods::construct(&ty, args, span),
+            other => Err(RuntimeError::NotCallable {
+                found: other.type_name(),
+                span: callee_span,
+            }),
         }
     }
 
-    fn evaluate_unary(&self, op: &TokenKind, right: Value) -> Result<Value, String> {
-        use TokenKind::*;
+    // `span` is the call site, reported when the arguments don't fit
+    pub fn call_function(&self, func: &Function, args: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+        if args.len() != func.arity() {
+            return Err(RuntimeError::ArityMismatch {
+                expected: func.arity(),
+                found: args.len(),
+                span,
+            });
+        }
+        if self.depth.get() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::new("Stack overflow".to_string(), span));
+        }
 
-        match (op, right) {
-            (Minus, Value::Number(n)) => Ok(Value::Number(-n)),
-            (Bang, Value::Bool(b)) => Ok(Value::Bool(!b)),
-            _ => Err("Invalid unary operation".to_string()),
+        // each call gets a fresh scope whose parent is the scope the
+        // function was declared in, not the caller's
+        let function_env = Environment::with_parent(func.closure.clone());
+        for (param, arg_value) in func.params.iter().zip(args) {
+            function_env.borrow_mut().define(param.name.clone(), arg_value);
         }
+
+        self.depth.set(self.depth.get() + 1);
+        let result = self.execute_block(&func.body, &function_env);
+        self.depth.set(self.depth.get() - 1);
+        result
     }
 
-    pub fn execute_block(&self, statements: &[Stmt], env: &mut Environment) -> Result<Value, String> {
+    pub fn execute_block(&self, statements: &[Stmt], env: &SharedEnvironment) -> Result<Value, RuntimeError> {
         match self.execute_block_with_control_flow(statements, env)? {
             ControlFlow::Normal(value) | ControlFlow::Return(value) => Ok(value),
-            ControlFlow::Break | ControlFlow::Continue => Err("Break/continue outside of loop".to_string()),
+            ControlFlow::Break(span) => Err(RuntimeError::new("'break' outside of loop".to_string(), span)),
+            ControlFlow::Continue(span) => Err(RuntimeError::new("'continue' outside of loop".to_string(), span)),
         }
     }
 
-    pub fn execute_block_with_control_flow(&self, statements: &[Stmt], env: &mut Environment) -> Result<ControlFlow, String> {
+    pub fn execute_block_with_control_flow(&self, statements: &[Stmt], env: &SharedEnvironment) -> Result<ControlFlow, RuntimeError> {
         for stmt in statements {
             let control_flow = self.execute(stmt, env)?;
             match control_flow {
                 ControlFlow::Normal(_) => {} // Continue execution
-                ControlFlow::Break | ControlFlow::Continue | ControlFlow::Return(_) => {
+                ControlFlow::Break(_) | ControlFlow::Continue(_) | ControlFlow::Return(_) => {
                     return Ok(control_flow);
                 }
             }
         }
         Ok(ControlFlow::normal(Value::Null))
     }
-}
\ No newline at end of file
+}
@@ -1,25 +1,63 @@
+use std::fmt;
+
 use crate::ast::{Parameter, Stmt};
 use crate::lexer::span::Span;
+use super::environment::SharedEnvironment;
+
+// deepest call nesting either backend allows: deep enough for any sensible
+// recursion, shallow enough to fail with an error instead of exhausting memory
+pub const MAX_CALL_DEPTH: usize = 10_000;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone)]
 pub struct Function {
     pub name: String,
     pub params: Vec<Parameter>,
     pub body: Vec<Stmt>,
     pub span: Span,
+    // scope the function was declared in, kept alive for as long as the function is
+    pub closure: SharedEnvironment,
 }
 
 impl Function {
-    pub fn new(name: String, params: Vec<Parameter>, body: Vec<Stmt>, span: Span) -> Self {
+    pub fn new(
+        name: String,
+        params: Vec<Parameter>,
+        body: Vec<Stmt>,
+        span: Span,
+        closure: SharedEnvironment,
+    ) -> Self {
         Self {
             name,
             params,
             body,
             span,
+            closure,
         }
     }
 
     pub fn arity(&self) -> usize {
         self.params.len()
     }
-}
\ No newline at end of file
+}
+
+// the closure usually contains the function itself, so it is left out
+// of Debug to avoid infinite recursion
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .field("span", &self.span)
+            .finish_non_exhaustive()
+    }
+}
+
+// two function values are equal only if they come from the same declaration
+// evaluated in the same scope
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.span == other.span
+            && std::rc::Rc::ptr_eq(&self.closure, &other.closure)
+    }
+}
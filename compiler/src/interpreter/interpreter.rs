@@ -1,31 +1,112 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
 use crate::ast::Stmt;
-use super::environment::Environment;
+use crate::modules::ModuleGraph;
+use super::environment::{Environment, SharedEnvironment};
+use super::error::RuntimeError;
 use super::executor::Executor;
+use super::module::{Module, ModuleScope};
 use super::std::StdLib;
+use super::value::Value;
 
 pub struct Interpreter {
-    environment: Environment,
+    environment: SharedEnvironment,
     executor: Executor,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        let mut environment = Environment::new();
+        Self {
+            environment: Self::globalEnvironment(),
+            executor: Executor::new(),
+        }
+    }
 
-        // Initialize global environment with std lib functions
+    // a fresh top-level scope holding the std lib functions
+    fn globalEnvironment() -> SharedEnvironment {
+        let environment = Environment::new();
         let globals = StdLib::create_global_environment();
         for (name, value) in globals {
-            environment.define(name, value);
+            environment.borrow_mut().define(name, value);
         }
+        environment
+    }
 
-        Self {
-            environment,
-            executor: Executor::new(),
-        }
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
+        self.executor.execute_block(statements, &self.environment)?;
+        Ok(())
     }
 
-    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), String> {
-        self.executor.execute_block(statements, &mut self.environment)?;
+    // runs every module of a program in order, each in its own top-level
+    // scope, and the root module in the interpreter's own
+    pub fn interpret_modules(&mut self, graph: &ModuleGraph) -> Result<(), RuntimeError> {
+        let root = self.interpretImports(graph)?;
+        self.interpret(root)?;
+        self.executor.set_imports(HashMap::new());
         Ok(())
     }
+
+    // runs every module but the root, leaving the values of the root's
+    // imports in place; hands back the root's statements
+    fn interpretImports<'g>(&mut self, graph: &'g ModuleGraph) -> Result<&'g [Stmt], RuntimeError> {
+        let mut values: Vec<Value> = Vec::new();
+        for (index, module) in graph.modules.iter().enumerate() {
+            let imports = module
+                .imports
+                .iter()
+                .map(|(path, &target)| (path.clone(), values[target].clone()))
+                .collect();
+            self.executor.set_imports(imports);
+
+            if index == graph.modules.len() - 1 {
+                return Ok(&module.statements);
+            }
+            let environment = Self::globalEnvironment();
+            self.executor.execute_block(&module.statements, &environment)?;
+            values.push(Value::Module(Rc::new(Module::new(
+                module.name.clone(),
+                module.exports.clone(),
+                ModuleScope::Environment(environment),
+            ))));
+        }
+        Ok(&[])
+    }
+
+    // calls the program's `main`, if it declared one, with the command-line
+    // arguments when it takes a parameter; the result is what main returned
+    pub fn call_main(&mut self, args: &[String]) -> Result<Value, RuntimeError> {
+        let main = match self.environment.borrow().get("main") {
+            Some(Value::Function(main)) if !StdLib::is_builtin(&main.name) => main,
+            _ => return Ok(Value::Null),
+        };
+        let args = match main.arity() {
+            0 => Vec::new(),
+            1 => vec![Value::array(args.iter().map(|arg| Value::String(arg.clone())).collect())],
+            count => return Err(RuntimeError::MainParameters { count, span: main.span }),
+        };
+        self.executor.call_function(&main, args, main.span)
+    }
+
+    // like interpret_modules, but hands back the value of a trailing
+    // expression statement of the root so the REPL can echo it
+    pub fn interpret_line(&mut self, graph: &ModuleGraph) -> Result<Option<Value>, RuntimeError> {
+        let statements = self.interpretImports(graph)?;
+        let result = match statements.split_last() {
+            Some((Stmt::ExprStmt(expr), rest)) => self
+                .interpret(rest)
+                .and_then(|()| self.executor.evaluate_expr(expr, &self.environment))
+                .map(Some),
+            _ => self.interpret(statements).map(|()| None),
+        };
+        self.executor.set_imports(HashMap::new());
+        result
+    }
+
+    // global bindings sorted by name
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let mut bindings = self.environment.borrow().bindings();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
 }
\ No newline at end of file
@@ -0,0 +1,505 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use super::dict::{Dict, DictKey};
+use super::error::RuntimeError;
+use super::module::{Module, ModuleScope};
+use super::operators::{as_big, as_float, compare_numbers};
+use super::structs::{Instance, StructType};
+use super::value::Value;
+use crate::ast::floatText;
+use crate::bigint::BigInt;
+use crate::lexer::span::Span;
+
+// built-in methods, called as `receiver.name(args)`; `span` is the call site
+// each value type has its own table, every value has `toString`, and
+// strings, arrays and dicts all have `length` and its short form `len`
+pub fn call_method(receiver: &Value, name: &str, args: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+    if name == "toString" {
+        check_arity(&args, 0, 0, span)?;
+        return Ok(Value::String(receiver.to_string()));
+    }
+    if let ("length" | "len", Some(length)) = (name, length(receiver)) {
+        check_arity(&args, 0, 0, span)?;
+        return Ok(count(length));
+    }
+    match receiver {
+        Value::String(s) => string_method(s, name, args, span),
+        Value::Int(_) | Value::BigInt(_) | Value::Float(_) => number_method(receiver, name, args, span),
+        Value::Array(elements) => array_method(elements, name, args, span),
+        Value::Dict(entries) => dict_method(entries, name, args, span),
+        // methods from `impl` blocks are called by the backends themselves
+        Value::Struct(instance) => Err(RuntimeError::new(
+            format!("{} has no method '{}'", instance.ty.name, name),
+            span,
+        )),
+        other => Err(RuntimeError::new(
+            format!("{} has no method '{}'", other.type_name(), name),
+            span,
+        )),
+    }
+}
+
+// properties read as `receiver.name`, without a call
+pub fn get_property(receiver: &Value, name: &str, span: Span) -> Result<Value, RuntimeError> {
+    if let ("length", Some(length)) = (name, length(receiver)) {
+        return Ok(count(length));
+    }
+    match (receiver, name) {
+        (Value::Struct(instance), _) => instance.get(name).ok_or_else(|| no_field(instance, name, span)),
+        (Value::Module(module), _) => {
+            export_index(module, name, span)?;
+            let value = match &module.scope {
+                ModuleScope::Environment(env) => env.borrow().get(name),
+                ModuleScope::Globals(_) => unreachable!("the VM reads exports from its own globals"),
+            };
+            value.ok_or_else(|| RuntimeError::UndefinedVariable {
+                name: name.to_string(),
+                span,
+            })
+        }
+        (other, _) => Err(RuntimeError::new(
+            format!("{} has no property '{}'", other.type_name(), name),
+            span,
+        )),
+    }
+}
+
+// `receiver.name = value`; only struct fields can be assigned
+pub fn set_property(receiver: &Value, name: &str, value: Value, span: Span) -> Result<(), RuntimeError> {
+    match receiver {
+        Value::Struct(instance) => {
+            if instance.set(name, value) {
+                Ok(())
+            } else {
+                Err(no_field(instance, name, span))
+            }
+        }
+        Value::Module(module) => Err(RuntimeError::new(
+            format!("Cannot assign to '{}', an export of module '{}'", name, module.name),
+            span,
+        )),
+        other => Err(RuntimeError::new(
+            format!("Cannot assign to property '{}' of {}", name, other.type_name()),
+            span,
+        )),
+    }
+}
+
+// the type an `impl` block adds methods to
+pub fn impl_target(value: &Value, span: Span) -> Result<&Rc<StructType>, RuntimeError> {
+    match value {
+        Value::StructType(ty) => Ok(ty),
+        other => Err(RuntimeError::new(
+            format!("'impl' needs a struct type, got {}", other.type_name()),
+            span,
+        )),
+    }
+}
+
+// a method is declared with its receiver as the first parameter, which
+// the call does not count as an argument
+pub fn check_method_arity(arity: usize, args: usize, span: Span) -> Result<(), RuntimeError> {
+    if arity != args + 1 {
+        return Err(RuntimeError::ArityMismatch {
+            expected: arity - 1,
+            found: args,
+            span,
+        });
+    }
+    Ok(())
+}
+
+fn no_field(instance: &Instance, name: &str, span: Span) -> RuntimeError {
+    RuntimeError::new(format!("{} has no field '{}'", instance.ty.name, name), span)
+}
+
+// calling a struct type builds an instance from the fields in declaration order
+pub fn construct(ty: &Rc<StructType>, args: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+    if args.len() != ty.fields.len() {
+        return Err(RuntimeError::ArityMismatch {
+            expected: ty.fields.len(),
+            found: args.len(),
+            span,
+        });
+    }
+    Ok(Value::Struct(Rc::new(Instance::new(ty.clone(), args))))
+}
+
+// position of `name` among the module's exports
+pub fn export_index(module: &Module, name: &str, span: Span) -> Result<usize, RuntimeError> {
+    module.export(name).ok_or_else(|| {
+        RuntimeError::new(format!("Module '{}' has no export '{}'", module.name, name), span)
+    })
+}
+
+// checks that `index` is an int inside `0..length`
+// (`0..=length` when `allow_end`, for positions between elements)
+pub fn element_index(index: &Value, length: usize, allow_end: bool, span: Span) -> Result<usize, RuntimeError> {
+    let n = match index {
+        Value::Int(n) => *n,
+        Value::BigInt(n) => {
+            return Err(RuntimeError::new(
+                format!("Index {} is out of bounds for an array of length {}", n, length),
+                span,
+            ))
+        }
+        other => {
+            return Err(RuntimeError::new(
+                format!("Array index must be an int, got {}", other.type_name()),
+                span,
+            ))
+        }
+    };
+    let limit = if allow_end { length + 1 } else { length };
+    if n < 0 || n >= limit as i64 {
+        return Err(RuntimeError::IndexOutOfBounds {
+            index: n,
+            length,
+            span,
+        });
+    }
+    Ok(n as usize)
+}
+
+// a `range` bound, which has to fit in 64 bits; `which` is "start" or "end"
+pub fn range_bound(value: Value, which: &str, span: Span) -> Result<i64, RuntimeError> {
+    match value {
+        Value::Int(n) => Ok(n),
+        Value::BigInt(n) => Err(RuntimeError::new(format!("Range {} {} is too large", which, n), span)),
+        other => Err(RuntimeError::new(
+            format!("Range {} must be an int, got {}", which, other.type_name()),
+            span,
+        )),
+    }
+}
+
+// a number of characters, elements or entries, as an int
+fn count(n: usize) -> Value {
+    Value::Int(n as i64)
+}
+
+// the number of characters, elements or entries in a collection
+fn length(value: &Value) -> Option<usize> {
+    match value {
+        Value::String(s) => Some(s.chars().count()),
+        Value::Array(elements) => Some(elements.borrow().len()),
+        Value::Dict(entries) => Some(entries.borrow().len()),
+        _ => None,
+    }
+}
+
+// errors unless `target` is an array or dictionary
+pub fn check_indexable(target: &Value, span: Span) -> Result<(), RuntimeError> {
+    match target {
+        Value::Array(_) | Value::Dict(_) => Ok(()),
+        other => Err(RuntimeError::new(
+            format!("Cannot index into {}", other.type_name()),
+            span,
+        )),
+    }
+}
+
+// `target[position]`; `object_span` is reported when the target cannot be indexed
+pub fn get_index(target: &Value, position: &Value, object_span: Span, index_span: Span) -> Result<Value, RuntimeError> {
+    check_indexable(target, object_span)?;
+    match target {
+        Value::Array(elements) => {
+            let elements = elements.borrow();
+            let position = element_index(position, elements.len(), false, index_span)?;
+            Ok(elements[position].clone())
+        }
+        // reading a missing key is an error; `get` returns null instead
+        Value::Dict(entries) => {
+            let key = dict_key(position, index_span)?;
+            entries.borrow().get(&key).cloned().ok_or(RuntimeError::MissingKey {
+                key: key.to_string(),
+                span: index_span,
+            })
+        }
+        _ => unreachable!("checked above"),
+    }
+}
+
+// `target[position] = value`; arrays only replace existing elements,
+// dictionaries insert missing keys
+pub fn set_index(target: &Value, position: &Value, value: Value, object_span: Span, index_span: Span) -> Result<(), RuntimeError> {
+    check_indexable(target, object_span)?;
+    match target {
+        Value::Array(elements) => {
+            let mut elements = elements.borrow_mut();
+            let position = element_index(position, elements.len(), false, index_span)?;
+            elements[position] = value;
+        }
+        Value::Dict(entries) => {
+            let key = dict_key(position, index_span)?;
+            entries.borrow_mut().insert(key, value);
+        }
+        _ => unreachable!("checked above"),
+    }
+    Ok(())
+}
+
+pub fn dict_key(key: &Value, span: Span) -> Result<DictKey, RuntimeError> {
+    DictKey::from_value(key).ok_or_else(|| {
+        RuntimeError::new(
+            format!("Dictionary keys must be strings, numbers or bools, got {}", key.type_name()),
+            span,
+        )
+    })
+}
+
+fn check_arity(args: &[Value], min: usize, max: usize, span: Span) -> Result<(), RuntimeError> {
+    if args.len() < min || args.len() > max {
+        return Err(RuntimeError::ArityMismatch {
+            expected: if args.len() < min { min } else { max },
+            found: args.len(),
+            span,
+        });
+    }
+    Ok(())
+}
+
+fn string_arg<'a>(args: &'a [Value], position: usize, method: &str, span: Span) -> Result<&'a str, RuntimeError> {
+    match &args[position] {
+        Value::String(s) => Ok(s),
+        other => Err(RuntimeError::new(
+            format!("'{}' expects a string argument, got {}", method, other.type_name()),
+            span,
+        )),
+    }
+}
+
+fn number_arg<'a>(args: &'a [Value], position: usize, method: &str, span: Span) -> Result<&'a Value, RuntimeError> {
+    match &args[position] {
+        number @ (Value::Int(_) | Value::BigInt(_) | Value::Float(_)) => Ok(number),
+        other => Err(RuntimeError::new(
+            format!("'{}' expects a number argument, got {}", method, other.type_name()),
+            span,
+        )),
+    }
+}
+
+// lengths and positions count characters, not bytes
+fn string_method(s: &str, name: &str, args: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+    let (min, max) = match name {
+        "upper" | "lower" | "trim" | "toInt" | "toFloat" => (0, 0),
+        "contains" | "startsWith" | "endsWith" | "indexOf" | "split" => (1, 1),
+        "replace" => (2, 2),
+        _ => {
+            return Err(RuntimeError::new(
+                format!("string has no method '{}'", name),
+                span,
+            ))
+        }
+    };
+    check_arity(&args, min, max, span)?;
+
+    match name {
+        "upper" => Ok(Value::String(s.to_uppercase())),
+        "lower" => Ok(Value::String(s.to_lowercase())),
+        "trim" => Ok(Value::String(s.trim().to_string())),
+        "contains" => Ok(Value::Bool(s.contains(string_arg(&args, 0, name, span)?))),
+        "startsWith" => Ok(Value::Bool(s.starts_with(string_arg(&args, 0, name, span)?))),
+        "endsWith" => Ok(Value::Bool(s.ends_with(string_arg(&args, 0, name, span)?))),
+        // -1 when the text does not occur
+        "indexOf" => {
+            let needle = string_arg(&args, 0, name, span)?;
+            let index = s.find(needle).map(|byte| s[..byte].chars().count() as i64);
+            Ok(Value::Int(index.unwrap_or(-1)))
+        }
+        "split" => {
+            let separator = string_arg(&args, 0, name, span)?;
+            if separator.is_empty() {
+                return Ok(Value::array(s.chars().map(|ch| Value::String(ch.to_string())).collect()));
+            }
+            Ok(Value::array(s.split(separator).map(|part| Value::String(part.to_string())).collect()))
+        }
+        "replace" => {
+            let from = string_arg(&args, 0, name, span)?;
+            let to = string_arg(&args, 1, name, span)?;
+            Ok(Value::String(s.replace(from, to)))
+        }
+        // surrounding whitespace is ignored; anything else that isn't a
+        // number is an error
+        "toInt" => BigInt::parse(s.trim()).map(Value::integer).ok_or_else(|| {
+            RuntimeError::new(format!("Cannot convert {:?} to an int", s), span)
+        }),
+        "toFloat" => s.trim().parse().map(Value::Float).map_err(|_| {
+            RuntimeError::new(format!("Cannot convert {:?} to a float", s), span)
+        }),
+        _ => unreachable!("arity table and dispatch disagree on '{}'", name),
+    }
+}
+
+// int results stay ints where they can; a float anywhere makes the
+// result a float, as with the arithmetic operators
+fn number_method(n: &Value, name: &str, args: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+    let (min, max) = match name {
+        "abs" | "floor" | "ceil" | "round" | "sqrt" | "toInt" | "toFloat" => (0, 0),
+        "pow" | "min" | "max" => (1, 1),
+        _ => {
+            return Err(RuntimeError::new(
+                format!("{} has no method '{}'", n.type_name(), name),
+                span,
+            ))
+        }
+    };
+    check_arity(&args, min, max, span)?;
+
+    let float = as_float(n).expect("number_method is only called on numbers");
+    match (n, name) {
+        (Value::Int(i), "abs") => Ok(match i.checked_abs() {
+            Some(abs) => Value::Int(abs),
+            None => Value::integer(BigInt::from(*i).abs()),
+        }),
+        (Value::BigInt(i), "abs") => Ok(Value::integer(i.abs())),
+        (Value::Int(_) | Value::BigInt(_), "floor" | "ceil" | "round" | "toInt") => Ok(n.clone()),
+        (Value::Float(f), "abs") => Ok(Value::Float(f.abs())),
+        (Value::Float(f), "floor") => Ok(Value::Float(f.floor())),
+        (Value::Float(f), "ceil") => Ok(Value::Float(f.ceil())),
+        (Value::Float(f), "round") => Ok(Value::Float(f.round())),
+        // drops the fractional part
+        (Value::Float(f), "toInt") => BigInt::from_f64(f.trunc()).map(Value::integer).ok_or_else(|| {
+            RuntimeError::new(format!("Cannot convert {} to an int", floatText(*f)), span)
+        }),
+        (_, "toFloat") => Ok(Value::Float(float)),
+        (_, "sqrt") => Ok(Value::Float(float.sqrt())),
+        // a negative exponent gives a fraction, so only a float can hold it
+        (Value::Int(_) | Value::BigInt(_), "pow") => match number_arg(&args, 0, name, span)? {
+            Value::Int(exponent) if *exponent >= 0 => {
+                let exponent = u32::try_from(*exponent).map_err(|_| {
+                    RuntimeError::new(format!("Exponent {} is too large", exponent), span)
+                })?;
+                Ok(int_pow(n, exponent))
+            }
+            Value::BigInt(exponent) if !exponent.is_negative() => {
+                Err(RuntimeError::new(format!("Exponent {} is too large", exponent), span))
+            }
+            exponent => Ok(Value::Float(float.powf(as_float(exponent).unwrap()))),
+        },
+        (_, "pow") => {
+            let exponent = number_arg(&args, 0, name, span)?;
+            Ok(Value::Float(float.powf(as_float(exponent).unwrap())))
+        }
+        // returns whichever operand is smaller or larger, keeping its type
+        (_, "min" | "max") => {
+            let other = number_arg(&args, 0, name, span)?;
+            let wanted = if name == "min" { Ordering::Less } else { Ordering::Greater };
+            let pick_other = compare_numbers(other, n) == Some(wanted);
+            Ok(if pick_other { other.clone() } else { n.clone() })
+        }
+        _ => unreachable!("arity table and dispatch disagree on '{}'", name),
+    }
+}
+
+fn int_pow(base: &Value, exponent: u32) -> Value {
+    if let Value::Int(base) = base {
+        if let Some(result) = base.checked_pow(exponent) {
+            return Value::Int(result);
+        }
+    }
+    let base = as_big(base).expect("int_pow is only called on ints");
+    Value::integer(base.pow(exponent))
+}
+
+fn array_method(
+    elements: &Rc<RefCell<Vec<Value>>>,
+    name: &str,
+    args: Vec<Value>,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    let (min, max) = match name {
+        "pop" | "reverse" => (0, 0),
+        "push" | "remove" | "contains" => (1, 1),
+        "insert" => (2, 2),
+        "slice" => (1, 2),
+        _ => {
+            return Err(RuntimeError::new(
+                format!("array has no method '{}'", name),
+                span,
+            ))
+        }
+    };
+    check_arity(&args, min, max, span)?;
+
+    let length = elements.borrow().len();
+    match name {
+        "push" => {
+            elements.borrow_mut().push(args[0].clone());
+            Ok(Value::Null)
+        }
+        "pop" => elements
+            .borrow_mut()
+            .pop()
+            .ok_or_else(|| RuntimeError::new("Cannot pop from an empty array".to_string(), span)),
+        "slice" => {
+            let start = element_index(&args[0], length, true, span)?;
+            let end = match args.get(1) {
+                Some(end) => element_index(end, length, true, span)?,
+                None => length,
+            };
+            if start > end {
+                return Err(RuntimeError::new(
+                    format!("Slice start {} is after its end {}", start, end),
+                    span,
+                ));
+            }
+            Ok(Value::array(elements.borrow()[start..end].to_vec()))
+        }
+        "insert" => {
+            let index = element_index(&args[0], length, true, span)?;
+            elements.borrow_mut().insert(index, args[1].clone());
+            Ok(Value::Null)
+        }
+        "remove" => {
+            let index = element_index(&args[0], length, false, span)?;
+            Ok(elements.borrow_mut().remove(index))
+        }
+        "contains" => Ok(Value::Bool(elements.borrow().contains(&args[0]))),
+        "reverse" => {
+            elements.borrow_mut().reverse();
+            Ok(Value::Null)
+        }
+        _ => unreachable!("arity table and dispatch disagree on '{}'", name),
+    }
+}
+
+fn dict_method(
+    entries: &Rc<RefCell<Dict>>,
+    name: &str,
+    args: Vec<Value>,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    let (min, max) = match name {
+        "keys" | "values" => (0, 0),
+        "has" | "remove" => (1, 1),
+        "get" => (1, 2),
+        _ => {
+            return Err(RuntimeError::new(
+                format!("dict has no method '{}'", name),
+                span,
+            ))
+        }
+    };
+    check_arity(&args, min, max, span)?;
+
+    match name {
+        "keys" => Ok(Value::array(entries.borrow().iter().map(|(key, _)| key.to_value()).collect())),
+        "values" => Ok(Value::array(entries.borrow().iter().map(|(_, value)| value.clone()).collect())),
+        "has" => Ok(Value::Bool(entries.borrow().contains(&dict_key(&args[0], span)?))),
+        // missing keys give the default, or null without one
+        "get" => {
+            let key = dict_key(&args[0], span)?;
+            let default = args.get(1).cloned().unwrap_or(Value::Null);
+            Ok(entries.borrow().get(&key).cloned().unwrap_or(default))
+        }
+        // returns the removed value, or null if the key was absent
+        "remove" => {
+            let key = dict_key(&args[0], span)?;
+            Ok(entries.borrow_mut().remove(&key).unwrap_or(Value::Null))
+        }
+        _ => unreachable!("arity table and dispatch disagree on '{}'", name),
+    }
+}
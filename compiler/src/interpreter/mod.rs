@@ -1,17 +1,18 @@
 pub mod control_flow;
+pub mod dict;
 pub mod environment;
-pub mod evaluator;
+pub mod error;
 pub mod executor;
 pub mod function;
 pub mod interpreter;
+pub mod methods;
+pub mod module;
+pub mod operators;
 pub mod std;
+pub mod structs;
 pub mod value;
 
-pub use control_flow::ControlFlow;
-pub use environment::Environment;
-pub use evaluator::Evaluator;
-pub use executor::Executor;
-pub use function::Function;
 pub use interpreter::Interpreter;
-pub use std::StdLib;
-pub use value::Value;
\ No newline at end of file
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,46 @@
+use std::fmt;
+
+use super::environment::SharedEnvironment;
+
+// where a module's top-level bindings live, so reading an export always
+// sees its current value
+pub enum ModuleScope {
+    // the scope the tree walker ran the module in
+    Environment(SharedEnvironment),
+    // the VM global holding each export, in the same order as `exports`
+    Globals(Vec<u16>),
+}
+
+// the value `import "path"` binds
+pub struct Module {
+    pub name: String,
+    pub exports: Vec<String>,
+    pub scope: ModuleScope,
+}
+
+impl Module {
+    pub fn new(name: String, exports: Vec<String>, scope: ModuleScope) -> Self {
+        Self { name, exports, scope }
+    }
+
+    // position of `name` in `exports`
+    pub fn export(&self, name: &str) -> Option<usize> {
+        self.exports.iter().position(|export| export == name)
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Module")
+            .field("name", &self.name)
+            .field("exports", &self.exports)
+            .finish_non_exhaustive()
+    }
+}
+
+// each file is loaded once, so a module is only ever equal to itself
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
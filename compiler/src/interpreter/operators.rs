@@ -0,0 +1,200 @@
+use std::cmp::Ordering;
+
+use super::error::RuntimeError;
+use super::value::Value;
+use crate::bigint::BigInt;
+use crate::lexer::span::Span;
+use crate::lexer::TokenKind;
+
+// operator semantics shared by the tree walker and the bytecode VM
+
+pub fn binary(left: Value, op: &TokenKind, right: Value, span: Span) -> Result<Value, RuntimeError> {
+    use TokenKind::*;
+
+    match (left, op, right) {
+        (left, Plus | Minus | Star | Slash | Percent, right) if is_int(&left) && is_int(&right) => {
+            int_arithmetic(&left, op, &right, span)
+        }
+        (left, Less | LessEqual | Greater | GreaterEqual, right) if is_number(&left) && is_number(&right) => {
+            // NaN is unordered, so every comparison with it is false
+            let ordering = compare_numbers(&left, &right);
+            let result = match op {
+                Less => ordering == Some(Ordering::Less),
+                LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                Greater => ordering == Some(Ordering::Greater),
+                _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            };
+            Ok(Value::Bool(result))
+        }
+
+        // any other pair of numbers has a float in it, and the int is promoted
+        (left, op, right) if is_number(&left) && is_number(&right) => {
+            let (l, r) = (as_float(&left).unwrap(), as_float(&right).unwrap());
+            match op {
+                Plus | Minus | Star | Slash | Percent => float_arithmetic(l, op, r, span),
+                // equality is exact, even where promoting would round the int
+                EqualEqual => Ok(Value::Bool(left == right)),
+                BangEqual => Ok(Value::Bool(left != right)),
+                _ => Err(invalid_operands(&left, op, &right, span)),
+            }
+        }
+
+        (Value::String(l), Plus, Value::String(r)) => Ok(Value::String(l + &r)),
+        (Value::String(l), EqualEqual, Value::String(r)) => Ok(Value::Bool(l == r)),
+        (Value::String(l), BangEqual, Value::String(r)) => Ok(Value::Bool(l != r)),
+
+        (Value::Bool(l), EqualEqual, Value::Bool(r)) => Ok(Value::Bool(l == r)),
+        (Value::Bool(l), BangEqual, Value::Bool(r)) => Ok(Value::Bool(l != r)),
+
+        // arrays, dicts and structs compare by their contents
+        (left, EqualEqual, right) if is_compound_pair(&left, &right) => Ok(Value::Bool(left == right)),
+        (left, BangEqual, right) if is_compound_pair(&left, &right) => Ok(Value::Bool(left != right)),
+
+        (left, op, right) => Err(invalid_operands(&left, op, &right, span)),
+    }
+}
+
+// an operand of `&&` or `||`, which only take bools; the right operand is
+// never evaluated when the left one decides the result
+pub fn logical_operand(op: &TokenKind, value: &Value, span: Span) -> Result<bool, RuntimeError> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        other => Err(RuntimeError::InvalidOperand {
+            op: op.clone(),
+            operand: other.type_name(),
+            span,
+        }),
+    }
+}
+
+pub fn unary(op: &TokenKind, right: Value, span: Span) -> Result<Value, RuntimeError> {
+    use TokenKind::*;
+
+    match (op, right) {
+        (Minus, Value::Int(n)) => Ok(match n.checked_neg() {
+            Some(n) => Value::Int(n),
+            None => Value::integer(-&BigInt::from(n)),
+        }),
+        (Minus, Value::BigInt(n)) => Ok(Value::integer(-n.as_ref())),
+        (Minus, Value::Float(n)) => Ok(Value::Float(-n)),
+        (Bang, Value::Bool(b)) => Ok(Value::Bool(!b)),
+        // bitwise not, as if ints were two's complement of any width
+        (Tilde, Value::Int(n)) => Ok(Value::Int(!n)),
+        (Tilde, Value::BigInt(n)) => Ok(Value::integer(-&(n.as_ref() + &BigInt::from(1)))),
+        (op, right) => Err(RuntimeError::InvalidOperand {
+            op: op.clone(),
+            operand: right.type_name(),
+            span,
+        }),
+    }
+}
+
+fn is_int(value: &Value) -> bool {
+    matches!(value, Value::Int(_) | Value::BigInt(_))
+}
+
+fn is_number(value: &Value) -> bool {
+    matches!(value, Value::Int(_) | Value::BigInt(_) | Value::Float(_))
+}
+
+// two arrays, two dicts or two structs
+fn is_compound_pair(left: &Value, right: &Value) -> bool {
+    matches!(
+        (left, right),
+        (Value::Array(_), Value::Array(_)) | (Value::Dict(_), Value::Dict(_)) | (Value::Struct(_), Value::Struct(_))
+    )
+}
+
+// a number as a float, for mixing it with another float
+pub fn as_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(n) => Some(*n as f64),
+        Value::BigInt(n) => Some(n.to_f64()),
+        Value::Float(n) => Some(*n),
+        _ => None,
+    }
+}
+
+// an int of either size as a BigInt
+pub fn as_big(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Int(n) => Some(BigInt::from(*n)),
+        Value::BigInt(n) => Some(n.as_ref().clone()),
+        _ => None,
+    }
+}
+
+// ints compare exactly whatever their size; with a float, both are compared
+// as floats; None when either isn't a number or one is NaN
+pub fn compare_numbers(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Int(l), Value::Int(r)) => Some(l.cmp(r)),
+        _ => match (as_big(left), as_big(right)) {
+            (Some(l), Some(r)) => Some(l.cmp(&r)),
+            _ => as_float(left)?.partial_cmp(&as_float(right)?),
+        },
+    }
+}
+
+// ints never overflow: a result that doesn't fit in 64 bits becomes a
+// BigInt, and goes back to a plain int once it fits again
+fn int_arithmetic(left: &Value, op: &TokenKind, right: &Value, span: Span) -> Result<Value, RuntimeError> {
+    use TokenKind::*;
+
+    if let (Value::Int(l), Value::Int(r)) = (left, right) {
+        let small = match op {
+            Plus => l.checked_add(*r),
+            Minus => l.checked_sub(*r),
+            Star => l.checked_mul(*r),
+            // rounds toward zero
+            Slash => l.checked_div(*r),
+            // takes the sign of the dividend, so `a == a / b * b + a % b`
+            Percent => l.checked_rem(*r),
+            _ => unreachable!("{} is not arithmetic", op),
+        };
+        if let Some(result) = small {
+            return Ok(Value::Int(result));
+        }
+    }
+
+    let (l, r) = (as_big(left).unwrap(), as_big(right).unwrap());
+    let result = match op {
+        Plus => &l + &r,
+        Minus => &l - &r,
+        Star => &l * &r,
+        Slash | Percent => {
+            let (quotient, remainder) = l.div_rem(&r).ok_or(RuntimeError::DivisionByZero { span })?;
+            if *op == Slash {
+                quotient
+            } else {
+                remainder
+            }
+        }
+        _ => unreachable!("{} is not arithmetic", op),
+    };
+    Ok(Value::integer(result))
+}
+
+fn float_arithmetic(l: f64, op: &TokenKind, r: f64, span: Span) -> Result<Value, RuntimeError> {
+    use TokenKind::*;
+
+    let result = match op {
+        Plus => l + r,
+        Minus => l - r,
+        Star => l * r,
+        Slash | Percent if r == 0.0 => return Err(RuntimeError::DivisionByZero { span }),
+        Slash => l / r,
+        Percent => l % r,
+        _ => unreachable!("{} is not arithmetic", op),
+    };
+    Ok(Value::Float(result))
+}
+
+fn invalid_operands(left: &Value, op: &TokenKind, right: &Value, span: Span) -> RuntimeError {
+    RuntimeError::InvalidOperands {
+        op: op.clone(),
+        left: left.type_name(),
+        right: right.type_name(),
+        span,
+    }
+}
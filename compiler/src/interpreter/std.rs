@@ -1,6 +1,8 @@
 use super::value::Value;
+use super::environment::Environment;
 use super::function::Function;
 use crate::lexer::span::Span;
+use std::rc::Rc;
 
 pub struct StdLib;
 
@@ -14,12 +16,18 @@ impl StdLib {
             vec![], // No parameters - accepts any number of arguments
             vec![], // Empty body - handled specially
             Span { start: 0, end: 0 },
+            Environment::new(),
         );
-        globals.insert("println".to_string(), Value::Function(println_func));
+        globals.insert("println".to_string(), Value::Function(Rc::new(println_func)));
 
         globals
     }
 
+    // names call_builtin_function handles; calls to them never look up a binding
+    pub fn is_builtin(name: &str) -> bool {
+        matches!(name, "println")
+    }
+
     pub fn call_builtin_function(name: &str, args: &[Value]) -> Option<Result<Value, String>> {
         match name {
             "println" => {
@@ -27,13 +35,7 @@ impl StdLib {
                     if i > 0 {
                         print!(" ");
                     }
-                    match arg {
-                        Value::String(s) => print!("{}", s),
-                        Value::Number(n) => print!("{}", n),
-                        Value::Bool(b) => print!("{}", b),
-                        Value::Null => print!("null"),
-                        Value::Function(_) => print!("<function>"),
-                    }
+                    print!("{}", arg);
                 }
                 println!();
                 Some(Ok(Value::Null))
@@ -0,0 +1,89 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::value::Value;
+
+// the value a `struct` declaration binds: its fields in declaration order
+// and the methods `impl` blocks have added since; calling it constructs
+// an instance
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<String>,
+    methods: RefCell<HashMap<String, Value>>,
+}
+
+impl StructType {
+    pub fn new(name: String, fields: Vec<String>) -> Self {
+        Self {
+            name,
+            fields,
+            methods: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // position of `name` among the fields
+    pub fn field(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field == name)
+    }
+
+    pub fn method(&self, name: &str) -> Option<Value> {
+        self.methods.borrow().get(name).cloned()
+    }
+
+    pub fn add_method(&self, name: String, function: Value) {
+        self.methods.borrow_mut().insert(name, function);
+    }
+}
+
+// methods usually close over the scope holding the type, so they are
+// left out of Debug
+impl fmt::Debug for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StructType")
+            .field("name", &self.name)
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+// each evaluation of a declaration makes a distinct type
+impl PartialEq for StructType {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// an instance of a struct; like arrays, copies of the value share fields
+#[derive(Debug)]
+pub struct Instance {
+    pub ty: Rc<StructType>,
+    pub fields: RefCell<Vec<Value>>,
+}
+
+impl Instance {
+    // `values` are the fields in declaration order
+    pub fn new(ty: Rc<StructType>, values: Vec<Value>) -> Self {
+        Self {
+            ty,
+            fields: RefCell::new(values),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        let index = self.ty.field(name)?;
+        Some(self.fields.borrow()[index].clone())
+    }
+
+    // false when the type has no field `name`
+    pub fn set(&self, name: &str, value: Value) -> bool {
+        match self.ty.field(name) {
+            Some(index) => {
+                self.fields.borrow_mut()[index] = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
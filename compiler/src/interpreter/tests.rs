@@ -0,0 +1,359 @@
+use std::rc::Rc;
+
+use super::dict::{Dict, DictKey};
+use super::structs::{Instance, StructType};
+use super::value::Value;
+use super::Interpreter;
+use crate::diagnostics::SourceFile;
+use crate::modules::Loader;
+use crate::vm::{Compiler, Vm};
+
+// what `main` returns, or the runtime error, the same on both backends
+fn run(source: &str) -> Result<String, String> {
+    runWith(source, &[])
+}
+
+fn runWith(source: &str, args: &[String]) -> Result<String, String> {
+    let (graph, diagnostics) = Loader::new().load(&SourceFile::new("test.rey", source), false);
+    assert!(diagnostics.is_empty(), "load errors: {:?}", diagnostics);
+    let mut interpreter = Interpreter::new();
+    let walked = interpreter
+        .interpret_modules(&graph)
+        .and_then(|()| interpreter.call_main(args))
+        .map(|value| value.to_string())
+        .map_err(|err| err.to_string());
+    let program = Compiler::new().compile(&graph).expect("program compiles");
+    let compiled = Vm::new().run(&program, args).map(|value| value.to_string()).map_err(|err| err.to_string());
+    assert_eq!(walked, compiled, "the backends disagree");
+    walked
+}
+
+// an instance of a one-field struct `P { x }` whose field holds the instance
+fn selfReferencing(ty: &Rc<StructType>) -> Value {
+    let instance = Value::Struct(Rc::new(Instance::new(ty.clone(), vec![Value::Null])));
+    if let Value::Struct(inner) = &instance {
+        inner.set("x", instance.clone());
+    }
+    instance
+}
+
+#[test]
+fn array_containing_itself_prints_a_placeholder() {
+    let array = Value::array(vec![Value::Int(1)]);
+    if let Value::Array(elements) = &array {
+        elements.borrow_mut().push(array.clone());
+    }
+    assert_eq!(array.to_string(), "[1, [...]]");
+}
+
+#[test]
+fn dict_containing_itself_prints_a_placeholder() {
+    let dict = Value::dict(Dict::new());
+    if let Value::Dict(entries) = &dict {
+        entries.borrow_mut().insert(DictKey::String("self".to_string()), dict.clone());
+    }
+    assert_eq!(dict.to_string(), r#"{"self": {...}}"#);
+}
+
+#[test]
+fn repeated_but_acyclic_values_print_in_full() {
+    let inner = Value::array(vec![Value::Int(1)]);
+    let outer = Value::array(vec![inner.clone(), inner]);
+    assert_eq!(outer.to_string(), "[[1], [1]]");
+}
+
+#[test]
+fn struct_containing_itself_prints_a_placeholder() {
+    let ty = Rc::new(StructType::new("P".to_string(), vec!["x".to_string()]));
+    assert_eq!(selfReferencing(&ty).to_string(), "P { x: P {...} }");
+}
+
+#[test]
+fn values_containing_themselves_compare_without_overflowing() {
+    let ty = Rc::new(StructType::new("P".to_string(), vec!["x".to_string()]));
+    let (first, second) = (selfReferencing(&ty), selfReferencing(&ty));
+    assert!(first == first);
+    assert!(first == second);
+
+    let array = Value::array(vec![Value::Int(1)]);
+    if let Value::Array(elements) = &array {
+        elements.borrow_mut().push(array.clone());
+    }
+    assert!(array == array);
+    assert!(array != Value::array(vec![Value::Int(1), Value::Int(2)]));
+}
+
+#[test]
+fn unbounded_recursion_is_a_runtime_error() {
+    // the same stack the CLI runs programs on
+    let worker = std::thread::Builder::new().stack_size(crate::STACK_SIZE).spawn(|| {
+        let source = "func f(n) { return f(n + 1); }\nf(0);";
+        let (tokens, _) = crate::lexer::Lexer::new(source).tokenize();
+        let (statements, _) = crate::parser::Parser::new(tokens).parse();
+        Interpreter::new().interpret(&statements).map_err(|err| err.to_string())
+    });
+    let result = worker.expect("test thread starts").join().expect("test thread finishes");
+    assert_eq!(result, Err("Stack overflow".to_string()));
+}
+
+#[test]
+fn deeply_nested_values_print_and_compare_in_linear_time() {
+    // the same stack the CLI runs programs on
+    let worker = std::thread::Builder::new().stack_size(crate::STACK_SIZE).spawn(|| {
+        let nest = || (0..200_000).fold(Value::array(Vec::new()), |inner, _| Value::array(vec![inner]));
+        let (first, second) = (nest(), nest());
+        let equal = first == second;
+        let text = first.to_string();
+        (equal, text.len(), text.starts_with("[[[") && text.ends_with("]]]"))
+    });
+    let result = worker.expect("test thread starts").join().expect("test thread finishes");
+    assert_eq!(result, (true, 400_002, true));
+}
+
+#[test]
+fn every_collection_has_length_and_len() {
+    let span = crate::lexer::span::Span::new(0, 0);
+    let mut dict = Dict::new();
+    dict.insert(DictKey::String("a".to_string()), Value::Int(1));
+    let collections = [
+        Value::String("héllo".to_string()),
+        Value::array(vec![Value::Int(1); 5]),
+        Value::dict(dict),
+    ];
+    for (collection, expected) in collections.iter().zip([5, 5, 1]) {
+        for name in ["length", "len"] {
+            let length = super::methods::call_method(collection, name, Vec::new(), span).map_err(|err| err.to_string());
+            assert_eq!(length, Ok(Value::Int(expected)), "{}.{}()", collection.type_name(), name);
+        }
+        let property = super::methods::get_property(collection, "length", span).map_err(|err| err.to_string());
+        assert_eq!(property, Ok(Value::Int(expected)));
+    }
+}
+
+#[test]
+fn closures_share_the_variables_they_capture() {
+    let source = r#"
+        func pair() {
+            var count = 0;
+            var inc = func() { count += 1; };
+            var get = func() { return count; };
+            return [inc, get];
+        }
+        func main() {
+            var first = pair();
+            var second = pair();
+            first[0]();
+            first[0]();
+            second[0]();
+            return [first[1](), second[1]()];
+        }
+    "#;
+    assert_eq!(run(source), Ok("[2, 1]".to_string()));
+}
+
+#[test]
+fn closures_see_later_assignments() {
+    let source = r#"
+        func main() {
+            var x = 1;
+            var get = () => x;
+            x = 2;
+            return get();
+        }
+    "#;
+    assert_eq!(run(source), Ok("2".to_string()));
+}
+
+#[test]
+fn closures_outlive_the_call_that_made_them() {
+    let source = r#"
+        func counter() {
+            var n = 0;
+            return func() { n += 1; return n; };
+        }
+        func main() {
+            var next = counter();
+            next();
+            next();
+            return next();
+        }
+    "#;
+    assert_eq!(run(source), Ok("3".to_string()));
+}
+
+#[test]
+fn runtime_errors_point_at_the_code_that_failed() {
+    let cases = [
+        ("println(missing);", "Undefined variable 'missing'", "missing"),
+        ("return 1 / 0;", "Division by zero", "1 / 0"),
+        ("return [1][3];", "Index 3 is out of bounds for an array of length 1", "3"),
+        ("return {\"k\": 1}[\"z\"];", "Key \"z\" not found in dictionary", "\"z\""),
+        ("return \"a\" - 1;", "Cannot apply '-' to string and int", "\"a\" - 1"),
+    ];
+    for (body, message, text) in cases {
+        let source = format!("func main() {{\n    {}\n}}\n", body);
+        let (tokens, _) = crate::lexer::Lexer::new(&source).tokenize();
+        let (statements, _) = crate::parser::Parser::new(tokens).parse();
+        let mut interpreter = Interpreter::new();
+        let err = interpreter
+            .interpret(&statements)
+            .and_then(|_| interpreter.call_main(&[]))
+            .expect_err("main fails");
+        assert_eq!(err.to_string(), message);
+        assert_eq!(&source[err.span().start..err.span().end], text, "{}", body);
+    }
+}
+
+#[test]
+fn dicts_keep_insertion_order() {
+    let source = r#"
+        func main() {
+            var d = {"b": 1, "a": 2};
+            d["c"] = 3;
+            d["b"] = 4;
+            d.remove("a");
+            d["a"] = 5;
+            var keys = [];
+            for key in d { keys.push(key); }
+            return [d, d.keys(), d.values(), keys];
+        }
+    "#;
+    assert_eq!(
+        run(source),
+        Ok(r#"[{"b": 4, "c": 3, "a": 5}, ["b", "c", "a"], [4, 3, 5], ["b", "c", "a"]]"#.to_string())
+    );
+}
+
+#[test]
+fn dict_lookups_by_key() {
+    let source = r#"
+        func main() {
+            var d = {1: "int", "1": "string", true: "bool"};
+            return [d[1.0], d["1"], d[true], d.get("x", 0), d.has(1), d.length];
+        }
+    "#;
+    assert_eq!(run(source), Ok(r#"["int", "string", "bool", 0, true, 3]"#.to_string()));
+    assert_eq!(
+        run(r#"func main() { return {"k": 1}["z"]; }"#),
+        Err(r#"Key "z" not found in dictionary"#.to_string())
+    );
+}
+
+#[test]
+fn main_runs_after_the_top_level_with_the_arguments() {
+    let source = r#"
+        var order = ["top"];
+        func main(args) {
+            order.push("main");
+            return [order, args];
+        }
+        order.push("end");
+    "#;
+    let args = ["a".to_string(), "b".to_string()];
+    assert_eq!(runWith(source, &args), Ok(r#"[["top", "end", "main"], ["a", "b"]]"#.to_string()));
+    assert_eq!(run("func main() { return 7; }"), Ok("7".to_string()));
+    assert_eq!(run("var x = 1;"), Ok("null".to_string()));
+}
+
+#[test]
+fn main_takes_at_most_one_parameter() {
+    assert_eq!(
+        run("func main(a, b) { return 0; }"),
+        Err("'main' takes at most one parameter, the command-line arguments".to_string())
+    );
+}
+
+#[test]
+fn ints_and_floats_stay_apart() {
+    let source = r#"
+        func main() {
+            return [7 / 2, -7 / 2, -7 % 3, 7 / 2.0, 2.0, 1 == 1.0, 3.toFloat(), 2.9.toInt(), 9223372036854775807 + 1];
+        }
+    "#;
+    assert_eq!(run(source), Ok("[3, -3, -1, 3.5, 2.0, true, 3.0, 2, 9223372036854775808]".to_string()));
+    assert_eq!(run("func main() { return 1 / 0; }"), Err("Division by zero".to_string()));
+    assert_eq!(run("func main() { return 1.0 % 0; }"), Err("Division by zero".to_string()));
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let source = r#"
+        var calls = 0;
+        func touch(result) { calls += 1; return result; }
+        func main() {
+            var items = [1];
+            var safe = 5 < items.length() && items[5] > 0;
+            var either = true || touch(false);
+            var both = false && touch(true);
+            var evaluated = touch(true) || touch(true);
+            return [safe, either, both, evaluated, calls];
+        }
+    "#;
+    assert_eq!(run(source), Ok("[false, true, false, true, 1]".to_string()));
+    assert_eq!(
+        run("func main() { return 1 && true; }"),
+        Err("Cannot apply '&&' to int".to_string())
+    );
+}
+
+#[test]
+fn operators_follow_their_precedence() {
+    let source = "func main() { return [1 + 2 * 3, 10 - 4 - 3, ~5, -2 * -3, !(1 < 2) || 2 >= 2, 7 % 4 == 3 && 1 != 2]; }";
+    assert_eq!(run(source), Ok("[7, 3, -6, 6, true, true]".to_string()));
+}
+
+#[test]
+fn assignment_targets_are_evaluated_once() {
+    let source = r#"
+        var calls = 0;
+        func next() { calls += 1; return 0; }
+        struct Box { value }
+        func main() {
+            var items = [10, 20];
+            items[next()] += 5;
+            items[next()]++;
+            var boxes = [Box(1)];
+            boxes[next()].value *= 3;
+            return [items, boxes[0].value, calls];
+        }
+    "#;
+    assert_eq!(run(source), Ok("[[16, 20], 3, 3]".to_string()));
+}
+
+#[test]
+fn updates_give_the_value_before_or_after() {
+    let source = "func main() { var x = 1; var a = x++; var b = ++x; var c = x--; var d = (x = 9); return [a, b, c, x, d]; }";
+    assert_eq!(run(source), Ok("[1, 3, 3, 9, 9]".to_string()));
+    assert_eq!(
+        run("func main() { var s = \"abc\"; s.length = 1; }"),
+        Err("Cannot assign to property 'length' of string".to_string())
+    );
+}
+
+#[test]
+fn lambdas_are_values() {
+    let source = r#"
+        func map(items, f) {
+            var out = [];
+            for item in items { out.push(f(item)); }
+            return out;
+        }
+        func compose(f, g) { return (x) => f(g(x)); }
+        func main() {
+            var double = (x) => x * 2;
+            var isOdd = func(x: int): bool { return x % 2 == 1; };
+            var inc = func(x) { return x + 1; };
+            return [map([1, 2, 3], double), isOdd(3), compose(inc, double)(5), map([1], func(x) { return -x; })];
+        }
+    "#;
+    assert_eq!(run(source), Ok("[[2, 4, 6], true, 11, [-1]]".to_string()));
+}
+
+#[test]
+fn lambdas_print_and_check_their_arity() {
+    assert_eq!(run("func main() { return [(x) => x, func() {}]; }"), Ok("[<function>, <function>]".to_string()));
+    assert_eq!(
+        run("func main() { var f = (x) => x; return f(1, 2); }"),
+        Err("Expected 1 arguments but got 2".to_string())
+    );
+}
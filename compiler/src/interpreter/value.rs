@@ -1,13 +1,34 @@
-use crate::ast::Literal;
+use crate::ast::{floatText, Literal};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
 
+use super::dict::Dict;
 use super::function::Function;
+use super::module::Module;
+use super::structs::{Instance, StructType};
+use crate::bigint::BigInt;
+use crate::vm::function::Closure;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Value {
     String(String),
-    Number(f64),
+    Int(i64),
+    // an int outside the 64-bit range; ints that fit are always `Int`
+    BigInt(Rc<BigInt>),
+    Float(f64),
     Bool(bool),
-    Function(Function),
+    Function(Rc<Function>),
+    // function compiled for the bytecode VM
+    Closure(Rc<Closure>),
+    // arrays are shared: copies of the value alias the same elements
+    Array(Rc<RefCell<Vec<Value>>>),
+    Dict(Rc<RefCell<Dict>>),
+    Module(Rc<Module>),
+    // a declared struct, which constructs instances when called
+    StructType(Rc<StructType>),
+    Struct(Rc<Instance>),
     Null,
 }
 
@@ -15,8 +36,218 @@ impl From<Literal> for Value {
     fn from(lit: Literal) -> Self {
         match lit {
             Literal::String(s) => Value::String(s),
-            Literal::Number(n) => Value::Number(n),
+            Literal::Int(n) => Value::Int(n),
+            Literal::BigInt(n) => Value::integer(n),
+            Literal::Float(n) => Value::Float(n),
             Literal::Bool(b) => Value::Bool(b),
             Literal::Null => Value::Null, }
     }
-}
\ No newline at end of file
+}
+
+impl Value {
+    // name of the value's runtime type, as used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Int(_) | Value::BigInt(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Function(_) | Value::Closure(_) => "function",
+            Value::Array(_) => "array",
+            Value::Dict(_) => "dict",
+            Value::Module(_) => "module",
+            Value::StructType(_) => "struct type",
+            Value::Struct(_) => "struct",
+            Value::Null => "null",
+        }
+    }
+
+    // false, null and zero are falsy; everything else is truthy
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Bool(false) => false,
+            Value::Null => false,
+            Value::Int(n) => *n != 0,
+            Value::Float(n) => *n != 0.0,
+            _ => true,
+        }
+    }
+
+    // an int of any size, kept as `Int` when it fits
+    pub fn integer(n: BigInt) -> Value {
+        match n.to_i64() {
+            Some(n) => Value::Int(n),
+            None => Value::BigInt(Rc::new(n)),
+        }
+    }
+
+    pub fn array(elements: Vec<Value>) -> Value {
+        Value::Array(Rc::new(RefCell::new(elements)))
+    }
+
+    pub fn dict(entries: Dict) -> Value {
+        Value::Dict(Rc::new(RefCell::new(entries)))
+    }
+
+    // strings nested in collections are quoted so `["1", 1]` stays distinguishable
+    fn fmtNested(&self, f: &mut fmt::Formatter<'_>, seen: &mut HashSet<*const ()>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{:?}", s),
+            other => other.show(f, seen),
+        }
+    }
+
+    // `seen` holds the collections being printed around this value, so
+    // one that contains itself shows as `[...]` or `{...}` there
+    fn show(&self, f: &mut fmt::Formatter<'_>, seen: &mut HashSet<*const ()>) -> fmt::Result {
+        match self {
+            Value::Array(elements) => {
+                let id = Rc::as_ptr(elements) as *const ();
+                if !seen.insert(id) {
+                    return write!(f, "[...]");
+                }
+                write!(f, "[")?;
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.fmtNested(f, seen)?;
+                }
+                seen.remove(&id);
+                write!(f, "]")
+            }
+            Value::Dict(entries) => {
+                let id = Rc::as_ptr(entries) as *const ();
+                if !seen.insert(id) {
+                    return write!(f, "{{...}}");
+                }
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", key)?;
+                    value.fmtNested(f, seen)?;
+                }
+                seen.remove(&id);
+                write!(f, "}}")
+            }
+            // `Point { x: 1, y: 2 }`
+            Value::Struct(instance) => {
+                let id = Rc::as_ptr(instance) as *const ();
+                if !seen.insert(id) {
+                    return write!(f, "{} {{...}}", instance.ty.name);
+                }
+                write!(f, "{} {{", instance.ty.name)?;
+                for (i, (name, value)) in instance.ty.fields.iter().zip(instance.fields.borrow().iter()).enumerate() {
+                    write!(f, "{}{}: ", if i > 0 { ", " } else { " " }, name)?;
+                    value.fmtNested(f, seen)?;
+                }
+                seen.remove(&id);
+                if instance.ty.fields.is_empty() {
+                    write!(f, "}}")
+                } else {
+                    write!(f, " }}")
+                }
+            }
+            Value::String(s) => write!(f, "{}", s),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::BigInt(n) => write!(f, "{}", n),
+            Value::Float(n) => write!(f, "{}", floatText(*n)),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Function(_) | Value::Closure(_) => write!(f, "<function>"),
+            Value::Module(module) => write!(f, "<module {}>", module.name),
+            Value::StructType(ty) => write!(f, "<struct {}>", ty.name),
+            Value::Null => write!(f, "null"),
+        }
+    }
+}
+
+// the text println shows
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.show(f, &mut HashSet::new())
+    }
+}
+
+// an int and a float are equal when they denote the same number, so `1`
+// and `1.0` match in `==`, `contains` and as dictionary keys
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.equals(other, &mut HashSet::new())
+    }
+}
+
+impl Value {
+    // `seen` holds the pairs of collections being compared around these
+    // values; a pair met again is taken as equal, leaving the decision to
+    // the rest of the comparison, so values that contain themselves compare
+    fn equals(&self, other: &Self, seen: &mut HashSet<(*const (), *const ())>) -> bool {
+        match (self, other) {
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Int(l), Value::Int(r)) => l == r,
+            (Value::Float(l), Value::Float(r)) => l == r,
+            (Value::Int(i), Value::Float(f)) | (Value::Float(f), Value::Int(i)) => exact_int(*f) == Some(*i),
+            // a BigInt never equals an `Int`, since it would have fit in one
+            (Value::BigInt(l), Value::BigInt(r)) => l == r,
+            (Value::BigInt(b), Value::Float(f)) | (Value::Float(f), Value::BigInt(b)) => {
+                BigInt::from_f64(*f).as_ref() == Some(b.as_ref())
+            }
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::Function(l), Value::Function(r)) => l == r,
+            (Value::Closure(l), Value::Closure(r)) => l == r,
+            (Value::Array(l), Value::Array(r)) => {
+                Self::compare(Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const (), seen, |seen| {
+                    let (l, r) = (l.borrow(), r.borrow());
+                    l.len() == r.len() && l.iter().zip(r.iter()).all(|(l, r)| l.equals(r, seen))
+                })
+            }
+            // entries are matched by key, whatever order they were inserted in
+            (Value::Dict(l), Value::Dict(r)) => {
+                Self::compare(Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const (), seen, |seen| {
+                    let (l, r) = (l.borrow(), r.borrow());
+                    l.len() == r.len()
+                        && l.iter().all(|(key, value)| r.get(key).is_some_and(|other| value.equals(other, seen)))
+                })
+            }
+            (Value::Module(l), Value::Module(r)) => l == r,
+            (Value::StructType(l), Value::StructType(r)) => l == r,
+            // the same struct type with equal fields
+            (Value::Struct(l), Value::Struct(r)) => {
+                Rc::ptr_eq(&l.ty, &r.ty)
+                    && Self::compare(Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const (), seen, |seen| {
+                        let (l, r) = (l.fields.borrow(), r.fields.borrow());
+                        l.iter().zip(r.iter()).all(|(l, r)| l.equals(r, seen))
+                    })
+            }
+            (Value::Null, Value::Null) => true,
+            _ => false,
+        }
+    }
+
+    // a collection always equals itself; otherwise `contents` decides,
+    // unless the pair is already being compared further out
+    fn compare(
+        l: *const (),
+        r: *const (),
+        seen: &mut HashSet<(*const (), *const ())>,
+        contents: impl FnOnce(&mut HashSet<(*const (), *const ())>) -> bool,
+    ) -> bool {
+        if l == r || !seen.insert((l, r)) {
+            return true;
+        }
+        let equal = contents(seen);
+        seen.remove(&(l, r));
+        equal
+    }
+}
+
+// the int a float is exactly equal to, if there is one
+pub fn exact_int(n: f64) -> Option<i64> {
+    // 2^63 is the first float past i64::MAX
+    if n.fract() == 0.0 && n >= i64::MIN as f64 && n < 9223372036854775808.0 {
+        Some(n as i64)
+    } else {
+        None
+    }
+}
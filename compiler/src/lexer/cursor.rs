@@ -2,13 +2,16 @@
 pub struct Cursor<'a> {
     source: &'a str,
     position: usize,
+    // added to every reported position
+    offset: usize,
 }
 
 impl<'a> Cursor<'a> {
-    pub fn new(source: &'a str) -> Self {
+    pub fn with_offset(source: &'a str, offset: usize) -> Self {
         Self {
             source,
             position: 0,
+            offset,
         }
     }
 
@@ -23,7 +26,7 @@ impl<'a> Cursor<'a> {
     }
 
     pub fn position(&self) -> usize {
-        self.position
+        self.offset + self.position
     }
 
     pub fn peek_ahead(&self, n: usize) -> Option<char> {
@@ -1,16 +1,75 @@
 #![allow(non_snake_case)]
 
 use super::span::Span;
-
-#[derive(Debug, Clone)]
-pub struct LexError {
-    pub message: String,
-    pub span: Span,
-}
+use crate::diagnostics::Diagnostic;
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum LexerError {
     UnexpectedCharacter { found: char, span: Span },
 
     UnterminatedString { span: Span },
+
+    UnterminatedComment { span: Span },
+
+    // a `{` in a string whose expression runs to the end of the input
+    UnterminatedInterpolation { span: Span },
+
+    // `text` is the literal as written; `reason` says what is wrong with it
+    InvalidNumber { text: String, reason: String, span: Span },
+
+    // `sequence` is the escape as written, backslash included
+    InvalidEscape { sequence: String, span: Span },
+}
+
+impl LexerError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            LexerError::UnexpectedCharacter { found, span } => {
+                Diagnostic::error(self.to_string())
+                    .with_primary(*span, format!("'{}' is not valid here", found))
+            }
+            LexerError::UnterminatedString { span } => Diagnostic::error(self.to_string())
+                .with_primary(Span::new(span.start, span.start + 1), "string starts here")
+                .with_note("add a closing '\"' to end the string"),
+            LexerError::UnterminatedComment { span } => Diagnostic::error(self.to_string())
+                .with_primary(Span::new(span.start, span.start + 2), "comment starts here")
+                .with_note("add a closing '*/'; comments nest, so each '/*' inside needs one too"),
+            LexerError::UnterminatedInterpolation { span } => Diagnostic::error(self.to_string())
+                .with_primary(Span::new(span.start, span.start + 1), "expression starts here")
+                .with_note("add a closing '}'; write '\\{' for a literal brace"),
+            LexerError::InvalidNumber { reason, span, .. } => {
+                Diagnostic::error(self.to_string()).with_primary(*span, reason.clone())
+            }
+            LexerError::InvalidEscape { sequence, span } => {
+                let note = if sequence.starts_with("\\u") {
+                    "write a unicode escape as '\\u{1F600}', with 1 to 6 hex digits naming a character"
+                } else {
+                    "the escapes are \\n, \\t, \\r, \\0, \\\\, \\\", \\{, \\} and \\u{...}; a raw string r\"...\" keeps backslashes as written"
+                };
+                Diagnostic::error(self.to_string())
+                    .with_primary(*span, "not a valid escape")
+                    .with_note(note)
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexerError::UnexpectedCharacter { found, .. } => {
+                write!(f, "Unexpected character '{}'", found)
+            }
+            LexerError::UnterminatedString { .. } => write!(f, "Unterminated string literal"),
+            LexerError::UnterminatedComment { .. } => write!(f, "Unterminated block comment"),
+            LexerError::UnterminatedInterpolation { .. } => {
+                write!(f, "Unterminated expression in string")
+            }
+            LexerError::InvalidNumber { text, .. } => write!(f, "Invalid number '{}'", text),
+            LexerError::InvalidEscape { sequence, .. } => {
+                write!(f, "Invalid escape sequence '{}'", sequence)
+            }
+        }
+    }
 }
@@ -1,34 +1,154 @@
-use crate::lexer::token;
-
 use super::{
     cursor::Cursor,
     error::LexerError,
     span::Span,
-    token::{Token, TokenKind},
+    token::{Comment, StringSegment, Token, TokenKind},
 };
+use crate::bigint::BigInt;
 
 pub struct Lexer<'a> {
     cursor: Cursor<'a>,
+    comments: Vec<Comment>,
+    // comments before this index already have a token after them
+    commentsSeen: usize,
+    // a line break came after the last token
+    newline: bool,
 }
 
 impl<'a> Lexer<'a> {
     //new lexer
     pub fn new(input: &'a str) -> Self {
+        Self::with_offset(input, 0)
+    }
+
+    // spans start at `offset`, so that every file of a program can be
+    // given its own range of positions
+    pub fn with_offset(input: &'a str, offset: usize) -> Self {
         Self {
-            cursor: Cursor::new(input),
+            cursor: Cursor::with_offset(input, offset),
+            comments: Vec::new(),
+            commentsSeen: 0,
+            newline: true,
+        }
+    }
+
+    // the `//` and `/* */` comments lexed so far, in source order
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    // lexes the whole input, continuing past bad characters so that every
+    // lexer error is reported; the tokens always end with Eof
+    pub fn tokenize(&mut self) -> (Vec<Token>, Vec<LexerError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        loop {
+            match self.nextToken() {
+                Ok(token) => {
+                    let done = token.kind == TokenKind::Eof;
+                    tokens.push(token);
+                    if done {
+                        break;
+                    }
+                }
+                Err(err) => errors.push(err),
+            }
         }
+        (tokens, errors)
     }
 
     //next token
     pub fn nextToken(&mut self) -> Result<Token, LexerError> {
-        // 1.skip whitespace
-        while let Some(ch) = self.cursor.peek() {
-            if ch.is_whitespace() {
-                self.cursor.advance();
+        // 1.skip whitespace and comments
+        let token = match self.skipTrivia()? {
+            Some(doc) => doc,
+            None => self.lexToken()?,
+        };
+        if token.kind == TokenKind::RightBrace {
+            for comment in &mut self.comments[self.commentsSeen..] {
+                comment.ends_block = true;
+            }
+        }
+        self.commentsSeen = self.comments.len();
+        self.newline = false;
+        Ok(token)
+    }
+
+    // skips whitespace and records `//` and `/* */` comments; a `///` doc
+    // comment is returned as a token so the parser can attach it
+    fn skipTrivia(&mut self) -> Result<Option<Token>, LexerError> {
+        loop {
+            match self.cursor.peek() {
+                Some('\n') => {
+                    self.cursor.advance();
+                    self.newline = true;
+                }
+                Some(ch) if ch.is_whitespace() => {
+                    self.cursor.advance();
+                }
+                Some('/') if self.cursor.peek_ahead(1) == Some('/') => {
+                    let start = self.cursor.position();
+                    let mut text = String::new();
+                    while let Some(ch) = self.cursor.peek().filter(|&ch| ch != '\n') {
+                        self.cursor.advance();
+                        text.push(ch);
+                    }
+                    let text = text.trim_end().to_string();
+                    // exactly three slashes; `////` dividers are plain comments
+                    if text.starts_with("///") && !text.starts_with("////") {
+                        let doc = &text[3..];
+                        return Ok(Some(Token {
+                            kind: TokenKind::DocComment(doc.strip_prefix(' ').unwrap_or(doc).to_string()),
+                            span: Span::new(start, self.cursor.position()),
+                        }));
+                    }
+                    self.record(text, start);
+                }
+                Some('/') if self.cursor.peek_ahead(1) == Some('*') => {
+                    let start = self.cursor.position();
+                    let text = self.blockComment(start)?;
+                    self.record(text, start);
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    // `/* */` comments nest, so commenting out code that has comments works
+    fn blockComment(&mut self, start: usize) -> Result<String, LexerError> {
+        let mut text = String::from("/*");
+        self.cursor.advance();
+        self.cursor.advance();
+        let mut depth = 1;
+        while depth > 0 {
+            let ch = self.cursor.advance().ok_or(LexerError::UnterminatedComment {
+                span: Span::new(start, self.cursor.position()),
+            })?;
+            text.push(ch);
+            let next = self.cursor.peek();
+            if ch == '/' && next == Some('*') {
+                depth += 1;
+            } else if ch == '*' && next == Some('/') {
+                depth -= 1;
             } else {
-                break;
+                continue;
             }
+            self.cursor.advance();
+            text.push(next.unwrap());
         }
+        Ok(text)
+    }
+
+    fn record(&mut self, text: String, start: usize) {
+        self.comments.push(Comment {
+            text,
+            span: Span::new(start, self.cursor.position()),
+            trailing: !self.newline,
+            ends_block: false,
+        });
+    }
+
+    fn lexToken(&mut self) -> Result<Token, LexerError> {
         let start = self.cursor.position();
 
         // 2.end of sc input
@@ -42,7 +162,8 @@ impl<'a> Lexer<'a> {
             }
         };
         match ch {
-            '"' => self.lexString(start),
+            '"' => self.lexString(start, false),
+            'r' if self.matchNext('"') => self.lexString(start, true),
 
             c if c.is_alphabetic() || c == '_' => Ok(self.lexIdentifier(start, c)),
 
@@ -50,19 +171,72 @@ impl<'a> Lexer<'a> {
             ')' => Ok(self.simpleToken(TokenKind::RightParen, start)),
             '{' => Ok(self.simpleToken(TokenKind::LeftBrace, start)),
             '}' => Ok(self.simpleToken(TokenKind::RightBrace, start)),
+            '[' => Ok(self.simpleToken(TokenKind::LeftBracket, start)),
+            ']' => Ok(self.simpleToken(TokenKind::RightBracket, start)),
             ';' => Ok(self.simpleToken(TokenKind::Semicolon, start)),
-            '+' => Ok(self.simpleToken(TokenKind::Plus, start)),
-            '-' => Ok(self.simpleToken(TokenKind::Minus, start)),
-            '*' => Ok(self.simpleToken(TokenKind::Star, start)),
-            '/' => Ok(self.simpleToken(TokenKind::Slash, start)),
-            ':' => Ok(self.simpleToken(TokenKind::Colon, start)),
+            ':' => {
+                let kind = if self.matchNext('=') {
+                    TokenKind::ColonEqual
+                } else {
+                    TokenKind::Colon
+                };
+                Ok(self.simpleToken(kind, start))
+            }
             '.' => Ok(self.simpleToken(TokenKind::Dot, start)),
             ',' => Ok(self.simpleToken(TokenKind::Comma, start)),
-            '%' => Ok(self.simpleToken(TokenKind::Percent, start)),
+            '~' => Ok(self.simpleToken(TokenKind::Tilde, start)),
+            '+' => {
+                let kind = if self.matchNext('+') {
+                    TokenKind::PlusPlus
+                } else if self.matchNext('=') {
+                    TokenKind::PlusEqual
+                } else {
+                    TokenKind::Plus
+                };
+                Ok(self.simpleToken(kind, start))
+            }
+            '-' => {
+                let kind = if self.matchNext('-') {
+                    TokenKind::MinusMinus
+                } else if self.matchNext('=') {
+                    TokenKind::MinusEqual
+                } else {
+                    TokenKind::Minus
+                };
+                Ok(self.simpleToken(kind, start))
+            }
+            '*' => {
+                let kind = if self.matchNext('=') {
+                    TokenKind::StarEqual
+                } else {
+                    TokenKind::Star
+                };
+                Ok(self.simpleToken(kind, start))
+            }
+            '/' => {
+                let kind = if self.matchNext('=') {
+                    TokenKind::SlashEqual
+                } else {
+                    TokenKind::Slash
+                };
+                Ok(self.simpleToken(kind, start))
+            }
+            '%' => {
+                let kind = if self.matchNext('=') {
+                    TokenKind::PercentEqual
+                } else {
+                    TokenKind::Percent
+                };
+                Ok(self.simpleToken(kind, start))
+            }
+            '&' if self.matchNext('&') => Ok(self.simpleToken(TokenKind::AndAnd, start)),
+            '|' if self.matchNext('|') => Ok(self.simpleToken(TokenKind::OrOr, start)),
 
             '=' => {
                 let kind = if self.matchNext('=') {
                     TokenKind::EqualEqual
+                } else if self.matchNext('>') {
+                    TokenKind::FatArrow
                 } else {
                     TokenKind::Equal
                 };
@@ -86,32 +260,14 @@ impl<'a> Lexer<'a> {
             }
             '!' => {
                 let kind = if self.matchNext('=') {
-                    TokenKind::NotEqual
+                    TokenKind::BangEqual
                 } else {
-                    TokenKind::Not
+                    TokenKind::Bang
                 };
                 Ok(self.simpleToken(kind, start))
             }
 
-            int if int.is_digit(10) => {
-                let mut number = String::new();
-                number.push(int);
-
-                while let Some(ch) = self.cursor.peek() {
-                    if ch.is_digit(10) || ch == '.' {
-                        self.cursor.advance();
-                        number.push(ch);
-                    } else {
-                        break;
-                    }
-                }
-
-                let value: f64 = number.parse().unwrap();
-                Ok(Token {
-                    kind: TokenKind::NumberLiteral(value),
-                    span: Span::new(start, self.cursor.position()),
-                })
-            }
+            digit if digit.is_ascii_digit() => self.lexNumber(start, digit),
 
             _ => Err(LexerError::UnexpectedCharacter {
                 found: ch,
@@ -119,6 +275,144 @@ impl<'a> Lexer<'a> {
             }),
         }
     }
+    // ints are decimal, or hex, binary or octal after `0x`, `0b` or `0o`;
+    // floats are decimal with a fraction, an exponent or both; any of them
+    // may group digits with `_`
+    fn lexNumber(&mut self, start: usize, first: char) -> Result<Token, LexerError> {
+        let mut text = first.to_string();
+        let radix = match (first, self.cursor.peek()) {
+            ('0', Some('x')) => 16,
+            ('0', Some('b')) => 2,
+            ('0', Some('o')) => 8,
+            _ => 10,
+        };
+        if radix != 10 {
+            text.push(self.cursor.advance().unwrap());
+            return self.lexRadixNumber(start, text, radix);
+        }
+
+        self.lexDigits(&mut text);
+        // a '.' is only part of the number when a digit follows it, so
+        // `2.pow(3)` is a method call
+        let fraction = self.cursor.peek() == Some('.')
+            && self.cursor.peek_ahead(1).is_some_and(|ch| ch.is_ascii_digit());
+        if fraction {
+            text.push(self.cursor.advance().unwrap());
+            self.lexDigits(&mut text);
+        }
+        let exponent = matches!(self.cursor.peek(), Some('e' | 'E'));
+        if exponent {
+            text.push(self.cursor.advance().unwrap());
+            if let Some(sign @ ('+' | '-')) = self.cursor.peek() {
+                self.cursor.advance();
+                text.push(sign);
+            }
+            if !self.cursor.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+                return Err(self.invalidNumber(start, text, "expected the exponent's digits after 'e'"));
+            }
+            self.lexDigits(&mut text);
+        }
+        if self.cursor.peek() == Some('.') && self.cursor.peek_ahead(1).is_some_and(|ch| ch.is_ascii_digit()) {
+            return Err(self.invalidNumber(start, text, "a number has at most one '.'"));
+        }
+        if let Some(ch) = self.cursor.peek().filter(|&ch| ch.is_alphanumeric() || ch == '_') {
+            let reason = format!("'{}' can't be part of a number", ch);
+            return Err(self.invalidNumber(start, text, &reason));
+        }
+        if let Some(reason) = Self::misplacedUnderscore(&text, 10) {
+            return Err(self.invalidNumber(start, text, reason));
+        }
+
+        let digits = text.replace('_', "");
+        let span = Span::new(start, self.cursor.position());
+        let kind = if fraction || exponent {
+            let value: f64 = digits.parse().unwrap();
+            if value.is_infinite() {
+                return Err(self.invalidNumber(start, text, "too large for a float"));
+            }
+            TokenKind::FloatLiteral(value)
+        } else {
+            // ints have any size
+            match digits.parse() {
+                Ok(value) => TokenKind::IntLiteral(value),
+                Err(_) => TokenKind::BigIntLiteral(BigInt::parse(&digits).unwrap()),
+            }
+        };
+        Ok(Token { kind, span })
+    }
+
+    // the digits after a `0x`, `0b` or `0o` prefix, already in `text`
+    fn lexRadixNumber(&mut self, start: usize, mut text: String, radix: u32) -> Result<Token, LexerError> {
+        while let Some(ch) = self.cursor.peek().filter(|&ch| ch.is_alphanumeric() || ch == '_') {
+            self.cursor.advance();
+            text.push(ch);
+        }
+        let digits = &text[2..];
+        let base = match radix {
+            16 => "hex",
+            8 => "octal",
+            _ => "binary",
+        };
+        if let Some(bad) = digits.chars().find(|&ch| ch != '_' && !ch.is_digit(radix)) {
+            let reason = format!("'{}' is not a {} digit", bad, base);
+            return Err(self.invalidNumber(start, text, &reason));
+        }
+        if digits.is_empty() {
+            let reason = format!("expected {} digits after '{}'", base, &text[..2]);
+            return Err(self.invalidNumber(start, text, &reason));
+        }
+        if self.cursor.peek() == Some('.') && self.cursor.peek_ahead(1).is_some_and(|ch| ch.is_ascii_digit()) {
+            let reason = format!("{} numbers can't have a fraction", base);
+            return Err(self.invalidNumber(start, text, &reason));
+        }
+        if let Some(reason) = Self::misplacedUnderscore(digits, radix) {
+            return Err(self.invalidNumber(start, text, reason));
+        }
+
+        let digits = digits.replace('_', "");
+        let value = BigInt::parse_radix(&digits, radix).unwrap();
+        let kind = match value.to_i64() {
+            Some(value) => TokenKind::IntLiteral(value),
+            None => TokenKind::BigIntLiteral(value),
+        };
+        Ok(Token {
+            kind,
+            span: Span::new(start, self.cursor.position()),
+        })
+    }
+
+    fn lexDigits(&mut self, text: &mut String) {
+        while let Some(ch) = self.cursor.peek().filter(|&ch| ch.is_ascii_digit() || ch == '_') {
+            self.cursor.advance();
+            text.push(ch);
+        }
+    }
+
+    // `_` may only separate two digits
+    fn misplacedUnderscore(text: &str, radix: u32) -> Option<&'static str> {
+        let chars: Vec<char> = text.chars().collect();
+        let isDigit = |i: usize| chars.get(i).is_some_and(|ch| ch.is_digit(radix));
+        let misplaced = (0..chars.len()).any(|i| chars[i] == '_' && (i == 0 || !isDigit(i - 1) || !isDigit(i + 1)));
+        misplaced.then_some("'_' can only go between two digits")
+    }
+
+    // skips the rest of a malformed number, so it is reported as a whole
+    fn invalidNumber(&mut self, start: usize, mut text: String, reason: &str) -> LexerError {
+        while let Some(ch) = self.cursor.peek() {
+            let fraction = ch == '.' && self.cursor.peek_ahead(1).is_some_and(|next| next.is_ascii_digit());
+            if !(ch.is_alphanumeric() || ch == '_' || fraction) {
+                break;
+            }
+            self.cursor.advance();
+            text.push(ch);
+        }
+        LexerError::InvalidNumber {
+            text,
+            reason: reason.to_string(),
+            span: Span::new(start, self.cursor.position()),
+        }
+    }
+
     fn matchNext(&mut self, expected: char) -> bool {
         match self.cursor.peek() {
             Some(ch) if ch == expected => {
@@ -128,23 +422,224 @@ impl<'a> Lexer<'a> {
             _ => false,
         }
     }
-    fn lexString(&mut self, start: usize) -> Result<Token, LexerError> {
-        let mut value = String::new();
+    // `"..."` with escapes and `{expression}`s, `"""..."""` across lines,
+    // and with a leading `r` either form takes its text as written
+    fn lexString(&mut self, start: usize, raw: bool) -> Result<Token, LexerError> {
+        let multiline = self.cursor.peek() == Some('"') && self.cursor.peek_ahead(1) == Some('"');
+        if multiline {
+            self.cursor.advance();
+            self.cursor.advance();
+        }
+        let mut lines = vec![StringLine::default()];
+        let mut indented = true;
+        // the first error is reported once the whole string is read, so
+        // lexing resumes after the closing quote
+        let mut error = None;
 
-        while let Some(ch) = self.cursor.advance() {
-            if ch == '"' {
-                return Ok(Token {
-                    kind: TokenKind::StringLiteral(value),
-                    span: Span::new(start, self.cursor.position()),
-                });
+        loop {
+            let position = self.cursor.position();
+            let ch = match self.cursor.advance() {
+                Some(ch) => ch,
+                None => {
+                    return Err(match error {
+                        Some(err @ LexerError::UnterminatedInterpolation { .. }) => err,
+                        _ => LexerError::UnterminatedString {
+                            span: Span::new(start, self.cursor.position()),
+                        },
+                    })
+                }
+            };
+            match ch {
+                '"' if !multiline => break,
+                '"' if self.cursor.peek() == Some('"') && self.cursor.peek_ahead(1) == Some('"') => {
+                    self.cursor.advance();
+                    self.cursor.advance();
+                    break;
+                }
+                '\r' if multiline && self.cursor.peek() == Some('\n') => continue,
+                '\n' if multiline => {
+                    lines.push(StringLine::default());
+                    indented = true;
+                    continue;
+                }
+                '\\' if !raw => match self.lexEscape(position) {
+                    Ok(escaped) => lines.last_mut().unwrap().push(escaped),
+                    Err(err) => {
+                        error.get_or_insert(err);
+                    }
+                },
+                '{' if !raw => match self.lexInterpolation(position) {
+                    Ok(tokens) => lines.last_mut().unwrap().segments.push(StringSegment::Code(tokens)),
+                    Err(err) => {
+                        error.get_or_insert(err);
+                    }
+                },
+                ' ' | '\t' if indented => {
+                    let line = lines.last_mut().unwrap();
+                    line.push(ch);
+                    line.indent += 1;
+                    continue;
+                }
+                _ => lines.last_mut().unwrap().push(ch),
             }
-            value.push(ch);
+            indented = false;
         }
 
-        Err(LexerError::UnterminatedString {
+        if let Some(err) = error {
+            return Err(err);
+        }
+        let segments = if multiline {
+            Self::stripIndentation(lines)
+        } else {
+            lines.pop().unwrap().segments
+        };
+        // without any `{expression}` the string is a plain literal
+        let kind = match segments.as_slice() {
+            [] => TokenKind::StringLiteral(String::new()),
+            [StringSegment::Text(text)] => TokenKind::StringLiteral(text.clone()),
+            _ => TokenKind::InterpolatedString(segments),
+        };
+        Ok(Token {
+            kind,
             span: Span::new(start, self.cursor.position()),
         })
     }
+
+    // lexes the expression after a `{` at `open` through its closing `}`;
+    // the returned tokens end with an Eof of their own
+    fn lexInterpolation(&mut self, open: usize) -> Result<Vec<Token>, LexerError> {
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = match self.nextToken() {
+                Ok(token) => token,
+                // in `"{"` the closing quote opens a string inside the
+                // expression instead, so the brace is what was left open
+                Err(LexerError::UnterminatedString { .. }) => {
+                    return Err(LexerError::UnterminatedInterpolation {
+                        span: Span::new(open, self.cursor.position()),
+                    })
+                }
+                Err(err) => return Err(err),
+            };
+            match token.kind {
+                TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightBrace if depth == 0 => {
+                    let end = Span::new(token.span.end, token.span.end);
+                    tokens.push(token);
+                    tokens.push(Token {
+                        kind: TokenKind::Eof,
+                        span: end,
+                    });
+                    return Ok(tokens);
+                }
+                TokenKind::RightBrace => depth -= 1,
+                TokenKind::Eof => {
+                    return Err(LexerError::UnterminatedInterpolation {
+                        span: Span::new(open, token.span.start),
+                    })
+                }
+                _ => {}
+            }
+            tokens.push(token);
+        }
+    }
+
+    // reads the escape after a backslash at `start`
+    fn lexEscape(&mut self, start: usize) -> Result<char, LexerError> {
+        let invalid = |lexer: &Self, sequence: String| LexerError::InvalidEscape {
+            sequence,
+            span: Span::new(start, lexer.cursor.position()),
+        };
+        let ch = match self.cursor.peek() {
+            // the missing closing quote is the better error
+            None => return Ok('\\'),
+            Some(ch) => ch,
+        };
+        self.cursor.advance();
+        let escaped = match ch {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '{' => '{',
+            '}' => '}',
+            'u' => {
+                let mut sequence = String::from("\\u");
+                if !self.matchNext('{') {
+                    return Err(invalid(self, sequence));
+                }
+                sequence.push('{');
+                let mut digits = String::new();
+                while let Some(digit) = self.cursor.peek().filter(char::is_ascii_hexdigit) {
+                    self.cursor.advance();
+                    digits.push(digit);
+                }
+                sequence.push_str(&digits);
+                if !self.matchNext('}') {
+                    return Err(invalid(self, sequence));
+                }
+                sequence.push('}');
+                let code = match digits.len() {
+                    1..=6 => u32::from_str_radix(&digits, 16).ok(),
+                    _ => None,
+                };
+                return code.and_then(char::from_u32).ok_or_else(|| invalid(self, sequence));
+            }
+            other => return Err(invalid(self, format!("\\{}", other))),
+        };
+        Ok(escaped)
+    }
+
+    // the text of a `"""` string starts on the line after the opening quotes
+    // and ends on the line before the closing ones, each line losing the
+    // indentation all of them share; a closing line of only whitespace
+    // counts towards that indentation, so it sets the left margin
+    fn stripIndentation(mut lines: Vec<StringLine>) -> Vec<StringSegment> {
+        let first = if lines.len() > 1 && lines[0].isBlank() {
+            lines.remove(0);
+            None
+        } else {
+            Some(lines.remove(0))
+        };
+        let closing = match lines.last() {
+            Some(line) if line.isBlank() => lines.pop(),
+            _ => None,
+        };
+        let margin = lines
+            .iter()
+            .filter(|line| !line.isBlank())
+            .chain(closing.as_ref())
+            .map(|line| line.indent)
+            .min()
+            .unwrap_or(0);
+
+        let mut segments = first.map(|line| line.segments).unwrap_or_default();
+        let mut lines = lines.into_iter();
+        if segments.is_empty() {
+            if let Some(line) = lines.next() {
+                segments = line.dedent(margin);
+            }
+        }
+        for line in lines {
+            let mut rest = line.dedent(margin).into_iter();
+            match (segments.last_mut(), rest.next()) {
+                (Some(StringSegment::Text(text)), Some(StringSegment::Text(next))) => {
+                    text.push('\n');
+                    text.push_str(&next);
+                }
+                (_, next) => {
+                    segments.push(StringSegment::Text("\n".to_string()));
+                    segments.extend(next);
+                }
+            }
+            segments.extend(rest);
+        }
+        segments
+    }
+
     fn lexIdentifier(&mut self, start: usize, first: char) -> Token {
         let mut ident = String::new();
         ident.push(first);
@@ -165,8 +660,14 @@ impl<'a> Lexer<'a> {
             "if" => TokenKind::If,
             "else" => TokenKind::Else,
             "while" => TokenKind::While,
+            "break" => TokenKind::Break,
+            "continue" => TokenKind::Continue,
             "for" => TokenKind::For,
             "in" => TokenKind::In,
+            "import" => TokenKind::Import,
+            "from" => TokenKind::From,
+            "struct" => TokenKind::Struct,
+            "impl" => TokenKind::Impl,
             "true" => TokenKind::True,
             "false" => TokenKind::False,
             "null" => TokenKind::Null,
@@ -179,10 +680,44 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // a token of one or two characters, ending where the cursor is
     fn simpleToken(&self, kind: TokenKind, start: usize) -> Token {
         Token {
             kind,
-            span: Span::new(start, start + 1),
+            span: Span::new(start, self.cursor.position()),
+        }
+    }
+}
+
+// a line of a string being lexed, and how many whitespace characters it
+// starts with; escapes never count as indentation
+#[derive(Default)]
+struct StringLine {
+    segments: Vec<StringSegment>,
+    indent: usize,
+}
+
+impl StringLine {
+    fn push(&mut self, ch: char) {
+        match self.segments.last_mut() {
+            Some(StringSegment::Text(text)) => text.push(ch),
+            _ => self.segments.push(StringSegment::Text(ch.to_string())),
+        }
+    }
+
+    fn isBlank(&self) -> bool {
+        match self.segments.as_slice() {
+            [] => true,
+            [StringSegment::Text(text)] => text.chars().count() == self.indent,
+            _ => false,
+        }
+    }
+
+    // indentation is literal whitespace, so it is all in the first segment
+    fn dedent(mut self, margin: usize) -> Vec<StringSegment> {
+        if let Some(StringSegment::Text(text)) = self.segments.first_mut() {
+            *text = text.chars().skip(margin.min(self.indent)).collect();
         }
+        self.segments
     }
 }
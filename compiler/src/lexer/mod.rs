@@ -5,4 +5,7 @@ pub mod span;
 pub mod token;
 
 pub use lexer::Lexer;
-pub use token::{Token, TokenKind};
+pub use token::{Comment, StringSegment, Token, TokenKind};
+
+#[cfg(test)]
+mod tests;
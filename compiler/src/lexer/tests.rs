@@ -0,0 +1,178 @@
+use super::error::LexerError;
+use super::{Lexer, StringSegment, TokenKind};
+
+// the kinds of every token but the trailing Eof
+fn kinds(source: &str) -> Result<Vec<TokenKind>, Vec<LexerError>> {
+    let (mut tokens, errors) = Lexer::new(source).tokenize();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    tokens.pop();
+    Ok(tokens.into_iter().map(|token| token.kind).collect())
+}
+
+// the value of a source holding a single plain string literal
+fn string(source: &str) -> String {
+    match kinds(source).expect("source lexes").as_slice() {
+        [TokenKind::StringLiteral(text)] => text.clone(),
+        other => panic!("expected one string literal, got {:?}", other),
+    }
+}
+
+#[test]
+fn interpolations_hold_the_tokens_of_their_expressions() {
+    let segments = match kinds(r#""a {x + 1}b""#).expect("source lexes").as_slice() {
+        [TokenKind::InterpolatedString(segments)] => segments.clone(),
+        other => panic!("expected one interpolated string, got {:?}", other),
+    };
+    assert_eq!(segments.len(), 3);
+    assert_eq!(segments[0], StringSegment::Text("a ".to_string()));
+    match &segments[1] {
+        StringSegment::Code(tokens) => {
+            let code: Vec<&TokenKind> = tokens.iter().map(|token| &token.kind).collect();
+            assert!(matches!(
+                code.as_slice(),
+                [TokenKind::Identifier(_), TokenKind::Plus, TokenKind::IntLiteral(1), TokenKind::RightBrace, TokenKind::Eof]
+            ), "got {:?}", code);
+        }
+        other => panic!("expected code, got {:?}", other),
+    }
+    assert_eq!(segments[2], StringSegment::Text("b".to_string()));
+}
+
+#[test]
+fn strings_nest_inside_interpolations() {
+    assert!(matches!(kinds(r#""{"}"}""#).expect("source lexes").as_slice(), [TokenKind::InterpolatedString(_)]));
+}
+
+#[test]
+fn escaped_braces_are_text() {
+    assert_eq!(string(r#""\{x\}""#), "{x}");
+}
+
+#[test]
+fn an_unclosed_interpolation_is_reported_as_one() {
+    for source in [r#"println("{");"#, r#"println("{x");"#, r#""{"#] {
+        let errors = kinds(source).expect_err("the interpolation is rejected");
+        assert!(
+            matches!(errors.as_slice(), [LexerError::UnterminatedInterpolation { .. }]),
+            "{}: got {:?}",
+            source,
+            errors
+        );
+    }
+}
+
+#[test]
+fn block_comments_nest() {
+    assert_eq!(
+        kinds("a /* x /* y */ z */ b").expect("source lexes"),
+        [TokenKind::Identifier("a".to_string()), TokenKind::Identifier("b".to_string())]
+    );
+}
+
+#[test]
+fn an_unclosed_block_comment_points_at_its_start() {
+    let errors = kinds("a /* x /* y */").expect_err("the comment is rejected");
+    match errors.as_slice() {
+        [LexerError::UnterminatedComment { span }] => assert_eq!(span.start, 2),
+        other => panic!("expected one unterminated comment, got {:?}", other),
+    }
+}
+
+#[test]
+fn only_three_slashes_start_a_doc_comment() {
+    assert_eq!(
+        kinds("/// doc\n//// not\n// plain\nx").expect("source lexes"),
+        [TokenKind::DocComment("doc".to_string()), TokenKind::Identifier("x".to_string())]
+    );
+}
+
+#[test]
+fn comments_are_kept_beside_the_tokens() {
+    let mut lexer = Lexer::new("a // end\n/* own */\nb");
+    let (_, errors) = lexer.tokenize();
+    assert!(errors.is_empty(), "got {:?}", errors);
+    let comments: Vec<(&str, bool)> = lexer.comments().iter().map(|comment| (comment.text.as_str(), comment.trailing)).collect();
+    assert_eq!(comments, [("// end", true), ("/* own */", false)]);
+}
+
+#[test]
+fn escapes_stand_for_their_characters() {
+    assert_eq!(string(r#""a\nb\tc\rd\0e\\f\"g""#), "a\nb\tc\rd\0e\\f\"g");
+    assert_eq!(string(r#""\u{41}\u{1F600}""#), "A\u{1F600}");
+}
+
+#[test]
+fn invalid_escapes_are_errors() {
+    for (source, sequence) in [(r#""\q""#, r"\q"), (r#""\u{}""#, r"\u{}"), (r#""\u{110000}""#, r"\u{110000}"), (r#""\u41""#, r"\u")] {
+        let errors = kinds(source).expect_err("the escape is rejected");
+        match errors.as_slice() {
+            [LexerError::InvalidEscape { sequence: found, .. }] => assert_eq!(found, sequence),
+            other => panic!("{}: expected one invalid escape, got {:?}", source, other),
+        }
+    }
+}
+
+#[test]
+fn raw_strings_keep_backslashes_and_braces() {
+    assert_eq!(string(r#"r"C:\dir\{x}""#), r"C:\dir\{x}");
+    assert_eq!(string("r\"\"\"\n    \\d+ {x}\n    \"\"\""), r"\d+ {x}");
+}
+
+#[test]
+fn multi_line_strings_drop_their_shared_indentation() {
+    assert_eq!(string("\"\"\"\n        usage:\n          --vm\n        \"\"\""), "usage:\n  --vm");
+    assert_eq!(string("\"\"\"one \"quoted\" line\"\"\""), "one \"quoted\" line");
+    // the closing quotes' line counts towards the shared indentation
+    assert_eq!(string("\"\"\"\n    a\n  \"\"\""), "  a");
+}
+
+#[test]
+fn an_unclosed_string_points_at_its_start() {
+    let errors = kinds("var s = \"abc").expect_err("the string is rejected");
+    match errors.as_slice() {
+        [LexerError::UnterminatedString { span }] => assert_eq!(span.start, 8),
+        other => panic!("expected one unterminated string, got {:?}", other),
+    }
+}
+
+#[test]
+fn number_literals_in_every_base() {
+    assert_eq!(
+        kinds("0xFF_FF 0b1010 0o17 1_000_000 3.25 1e-9 2E3").expect("source lexes"),
+        [
+            TokenKind::IntLiteral(65535),
+            TokenKind::IntLiteral(10),
+            TokenKind::IntLiteral(15),
+            TokenKind::IntLiteral(1_000_000),
+            TokenKind::FloatLiteral(3.25),
+            TokenKind::FloatLiteral(1e-9),
+            TokenKind::FloatLiteral(2000.0),
+        ]
+    );
+    assert!(matches!(kinds("99999999999999999999").expect("source lexes").as_slice(), [TokenKind::BigIntLiteral(_)]));
+}
+
+#[test]
+fn malformed_numbers_say_what_is_wrong() {
+    let cases = [
+        ("1.2.3", "a number has at most one '.'"),
+        ("0b102", "'2' is not a binary digit"),
+        ("1_", "'_' can only go between two digits"),
+        ("1__0", "'_' can only go between two digits"),
+        ("0x", "expected hex digits after '0x'"),
+        ("1e", "expected the exponent's digits after 'e'"),
+        ("1e400", "too large for a float"),
+    ];
+    for (source, expected) in cases {
+        let errors = kinds(source).expect_err("the number is rejected");
+        match errors.as_slice() {
+            [LexerError::InvalidNumber { text, reason, span }] => {
+                assert_eq!((text.as_str(), reason.as_str()), (source, expected));
+                assert_eq!((span.start, span.end), (0, source.len()));
+            }
+            other => panic!("{}: expected one invalid number, got {:?}", source, other),
+        }
+    }
+}
@@ -1,10 +1,35 @@
+use crate::ast::floatText;
+use crate::bigint::BigInt;
 use crate::lexer::span::Span;
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     pub span: Span,
 }
 
+// a `//` or `/* */` comment; comments are not tokens, but are kept beside
+// them for tools that print source back out, like the formatter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    // as written, delimiters included
+    pub text: String,
+    pub span: Span,
+    // on the same line as the token before it
+    pub trailing: bool,
+    // the token after it is a `}`, so it ends a block
+    pub ends_block: bool,
+}
+
+// a piece of an interpolated string: text, or the tokens of a `{...}`
+// expression, from after the `{` through the `}` and a closing Eof
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringSegment {
+    Text(String),
+    Code(Vec<Token>),
+}
+
 //this enum will grow over time
 // for v0, keeping it simple
 //contains all tokens supported for v0
@@ -15,6 +40,8 @@ pub enum TokenKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -24,7 +51,6 @@ pub enum TokenKind {
     Star,
     Percent,
     Colon,
-    Not,
     AndAnd,
     OrOr,
     BangEqual,
@@ -50,6 +76,10 @@ pub enum TokenKind {
     Continue,
     For,
     In,
+    Import,
+    From,
+    Struct,
+    Impl,
     True,
     False,
     Null,
@@ -57,20 +87,112 @@ pub enum TokenKind {
     //literals
     Identifier(String),
     StringLiteral(String),
-    NumberLiteral(f64),
-    BooleanLiteral(bool),
+    // a string with at least one `{expression}` in it
+    InterpolatedString(Vec<StringSegment>),
+    IntLiteral(i64),
+    // an int literal too large for 64 bits
+    BigIntLiteral(BigInt),
+    FloatLiteral(f64),
+    // `/// text`, documenting the declaration after it; holds the text
+    // without the slashes
+    DocComment(String),
 
     //operators
     Equal,
     EqualEqual,
+    // `=>`, between a lambda's parameters and its body
+    FatArrow,
     Greater,
     GreaterEqual,
-    NotEqual,
     Less,
     LessEqual,
-    And,
-    Or,
 
     //special
     Eof,
 }
+
+impl TokenKind {
+    // source text of tokens that are always spelled the same way
+    pub fn lexeme(&self) -> Option<&'static str> {
+        let text = match self {
+            TokenKind::LeftParen => "(",
+            TokenKind::RightParen => ")",
+            TokenKind::LeftBrace => "{",
+            TokenKind::RightBrace => "}",
+            TokenKind::LeftBracket => "[",
+            TokenKind::RightBracket => "]",
+            TokenKind::Comma => ",",
+            TokenKind::Dot => ".",
+            TokenKind::Minus => "-",
+            TokenKind::Plus => "+",
+            TokenKind::Semicolon => ";",
+            TokenKind::Slash => "/",
+            TokenKind::Star => "*",
+            TokenKind::Percent => "%",
+            TokenKind::Colon => ":",
+            TokenKind::Bang => "!",
+            TokenKind::AndAnd => "&&",
+            TokenKind::OrOr => "||",
+            TokenKind::BangEqual => "!=",
+            TokenKind::Tilde => "~",
+            TokenKind::PlusPlus => "++",
+            TokenKind::MinusMinus => "--",
+            TokenKind::PlusEqual => "+=",
+            TokenKind::MinusEqual => "-=",
+            TokenKind::StarEqual => "*=",
+            TokenKind::SlashEqual => "/=",
+            TokenKind::PercentEqual => "%=",
+            TokenKind::ColonEqual => ":=",
+            TokenKind::Var => "var",
+            TokenKind::Func => "func",
+            TokenKind::Return => "return",
+            TokenKind::If => "if",
+            TokenKind::Else => "else",
+            TokenKind::While => "while",
+            TokenKind::Break => "break",
+            TokenKind::Continue => "continue",
+            TokenKind::For => "for",
+            TokenKind::In => "in",
+            TokenKind::Import => "import",
+            TokenKind::From => "from",
+            TokenKind::Struct => "struct",
+            TokenKind::Impl => "impl",
+            TokenKind::True => "true",
+            TokenKind::False => "false",
+            TokenKind::Null => "null",
+            TokenKind::Identifier(_)
+            | TokenKind::StringLiteral(_)
+            | TokenKind::InterpolatedString(_)
+            | TokenKind::IntLiteral(_)
+            | TokenKind::BigIntLiteral(_)
+            | TokenKind::FloatLiteral(_)
+            | TokenKind::DocComment(_)
+            | TokenKind::Eof => return None,
+            TokenKind::Equal => "=",
+            TokenKind::EqualEqual => "==",
+            TokenKind::FatArrow => "=>",
+            TokenKind::Greater => ">",
+            TokenKind::GreaterEqual => ">=",
+            TokenKind::Less => "<",
+            TokenKind::LessEqual => "<=",
+        };
+        Some(text)
+    }
+}
+
+// how a token is referred to in diagnostics
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Identifier(name) => write!(f, "identifier '{}'", name),
+            TokenKind::StringLiteral(value) => write!(f, "string \"{}\"", value),
+            TokenKind::InterpolatedString(_) => write!(f, "interpolated string"),
+            TokenKind::IntLiteral(value) => write!(f, "integer {}", value),
+            TokenKind::BigIntLiteral(value) => write!(f, "integer {}", value),
+            TokenKind::FloatLiteral(value) => write!(f, "float {}", floatText(*value)),
+            TokenKind::DocComment(_) => write!(f, "doc comment"),
+            TokenKind::Eof => write!(f, "end of file"),
+            other => write!(f, "'{}'", other.lexeme().unwrap_or_default()),
+        }
+    }
+}
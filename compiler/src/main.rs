@@ -1,65 +1,40 @@
 #![allow(non_snake_case)]
+#![allow(clippy::module_inception)]
 
 mod ast;
+mod bigint;
+mod checker;
+mod cli;
+mod diagnostics;
+mod formatter;
 mod interpreter;
 mod lexer;
+mod modules;
 mod parser;
+mod repl;
+mod vm;
 
-use interpreter::Interpreter;
-use lexer::{Lexer, TokenKind};
-use parser::Parser;
+use cli::Args;
 use std::env;
-use std::fs;
+use std::process;
+use std::thread;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let filename = if args.len() > 1 {
-        args[1].clone()
-    } else {
-        "".to_string()
-    };
-    if filename.is_empty() {
-        println!("No filename provided");
-        return;
-    }
-
-    let source = fs::read_to_string(&filename)
-        .expect(&format!("Failed to read {} file", filename));
-
-    let mut lexer = Lexer::new(&source);
-    let mut tokens = Vec::new();
+// the tree walker recurses natively for every call, so it needs far more
+// stack than the default to reach the call depth limit before overflowing
+const STACK_SIZE: usize = 1024 * 1024 * 1024;
 
-    loop {
-        match lexer.nextToken() {
-            Ok(token) => {
-                println!("{:?}", token);
-                tokens.push(token.clone());
-                if token.kind == TokenKind::Eof {
-                    break;
-                }
-            }
-            Err(err) => {
-                println!("Lexer error: {:?}", err);
-                break;
-            }
-        }
-    }
-    println!("Parsing Started.");
-    let mut parser = Parser::new(tokens);
-    match parser.parse() {
-        Ok(ast) => {
-            let mut interpreter = Interpreter::new();
-            match interpreter.interpret(&ast) {
-                Ok(()) => {
-                    println!("Program executed successfully!");
-                }
-                Err(err) => {
-                    println!("Runtime error: {}", err);
-                }
+fn main() {
+    let worker = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| match Args::parse(env::args().skip(1)) {
+            Ok(args) => cli::run(args),
+            Err(message) => {
+                eprintln!("error: {}\n\n{}", message, cli::USAGE);
+                cli::USAGE_ERROR
             }
-        }
-        Err(err) => {
-            println!("Parser error: {:?}", err);
-        }
-    }
+        })
+        .expect("failed to start the main thread");
+    // a panic on the worker is re-raised so it exits the way it always has
+    let code = worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+    process::exit(code);
 }
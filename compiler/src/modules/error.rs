@@ -0,0 +1,69 @@
+use std::fmt;
+
+use crate::diagnostics::Diagnostic;
+use crate::lexer::span::Span;
+
+// problems resolving the imports of a program; every span points at the
+// path or name in the importing file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModuleError {
+    NotFound {
+        path: String,
+        reason: String,
+        span: Span,
+    },
+
+    // `chain` lists the files from the first one in the cycle back to itself
+    Cycle {
+        chain: Vec<String>,
+        span: Span,
+    },
+
+    // the file name can't be used as the name of the module value
+    BadName {
+        path: String,
+        name: String,
+        span: Span,
+    },
+
+    MissingExport {
+        path: String,
+        name: String,
+        span: Span,
+    },
+}
+
+impl ModuleError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string());
+        match self {
+            ModuleError::NotFound { reason, span, .. } => diagnostic.with_primary(*span, reason.clone()),
+            ModuleError::Cycle { span, .. } => diagnostic
+                .with_primary(*span, "this import closes the cycle")
+                .with_note("a module runs before the files importing it, so two modules cannot import each other"),
+            ModuleError::BadName { path, span, .. } => diagnostic
+                .with_primary(*span, "not usable as a name")
+                .with_note(format!("import the names you need instead: import a, b from \"{}\";", path)),
+            ModuleError::MissingExport { span, .. } => {
+                diagnostic.with_primary(*span, "not a top-level variable or function there")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleError::NotFound { path, .. } => write!(f, "Cannot import '{}'", path),
+            ModuleError::Cycle { chain, .. } => write!(f, "Import cycle: {}", chain.join(" -> ")),
+            ModuleError::BadName { path, name, .. } => write!(
+                f,
+                "Cannot import '{}' as a module: '{}' is not a valid name",
+                path, name
+            ),
+            ModuleError::MissingExport { path, name, .. } => {
+                write!(f, "'{}' has no export named '{}'", path, name)
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use super::error::ModuleError;
+use crate::ast::{ImportedName, Stmt};
+use crate::checker::TypeChecker;
+use crate::diagnostics::{Diagnostic, SourceFile, SourceMap};
+use crate::lexer::span::Span;
+use crate::lexer::{Lexer, TokenKind};
+use crate::parser::Parser;
+
+// one file of a program, parsed and checked
+pub struct SourceModule {
+    // what `import "path"` binds the module to, taken from the file name
+    pub name: String,
+    pub statements: Vec<Stmt>,
+    // the module each import path written in this file refers to
+    pub imports: HashMap<String, usize>,
+    // top-level variables and functions, in declaration order
+    pub exports: Vec<String>,
+}
+
+// every file a program is made of; a module comes before the modules
+// importing it, so running them in order runs each dependency first,
+// and the file the program started from is last
+pub struct ModuleGraph {
+    pub sources: SourceMap,
+    pub modules: Vec<SourceModule>,
+}
+
+// reads a program and everything it imports, each file once
+pub struct Loader {
+    sources: SourceMap,
+    modules: Vec<SourceModule>,
+    // canonical path of each file already loaded
+    loaded: HashMap<PathBuf, usize>,
+    // files whose imports are being loaded, outermost first
+    loading: Vec<(Option<PathBuf>, String)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Self {
+            sources: SourceMap::new(),
+            modules: Vec::new(),
+            loaded: HashMap::new(),
+            loading: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    // imports are resolved relative to the importing file; for a program
+    // not read from a file, relative to the working directory
+    // the graph is only complete when there are no diagnostics
+    pub fn load(mut self, root: &SourceFile, from_file: bool) -> (ModuleGraph, Vec<Diagnostic>) {
+        let path = if from_file { fs::canonicalize(&root.name).ok() } else { None };
+        self.loadFile(&root.name, &root.text, path);
+        self.finish()
+    }
+
+    // a loader adding to files already loaded, as a REPL session does
+    pub fn with_sources(sources: SourceMap) -> Self {
+        Self { sources, ..Self::new() }
+    }
+
+    // like load, for a root that was already parsed and checked, as a REPL
+    // entry is; its source must already be the loader's last file
+    pub fn load_parsed(mut self, name: &str, statements: Vec<Stmt>, from_file: bool) -> (ModuleGraph, Vec<Diagnostic>) {
+        let path = if from_file { fs::canonicalize(name).ok() } else { None };
+        self.addModule(name, statements, path);
+        self.finish()
+    }
+
+    fn finish(self) -> (ModuleGraph, Vec<Diagnostic>) {
+        let graph = ModuleGraph {
+            sources: self.sources,
+            modules: self.modules,
+        };
+        (graph, self.diagnostics)
+    }
+
+    fn loadFile(&mut self, name: &str, text: &str, path: Option<PathBuf>) -> usize {
+        let base = self.sources.next_base();
+        self.sources.add(SourceFile::new(name, text).with_base(base));
+        let statements = self.parse(text, base).unwrap_or_default();
+        self.addModule(name, statements, path)
+    }
+
+    // loads the files `statements` import and records them as a module
+    fn addModule(&mut self, name: &str, statements: Vec<Stmt>, path: Option<PathBuf>) -> usize {
+        self.loading.push((path.clone(), name.to_string()));
+        let dir = Path::new(name).parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut imports = HashMap::new();
+        for stmt in &statements {
+            if let Stmt::Import { path, names, span } = stmt {
+                let target = match self.resolve(&dir, path, *span) {
+                    Some(target) => target,
+                    None => continue,
+                };
+                imports.insert(path.clone(), target);
+                self.checkImport(target, path, names.as_deref(), *span);
+            }
+        }
+        self.loading.pop();
+
+        let mut exports: Vec<String> = Vec::new();
+        for stmt in &statements {
+            if let Stmt::VarDecl { name, .. } | Stmt::FuncDecl { name, .. } | Stmt::Struct { name, .. } = stmt {
+                if !exports.contains(name) {
+                    exports.push(name.clone());
+                }
+            }
+        }
+        let stem = Path::new(name).file_stem().and_then(|stem| stem.to_str()).unwrap_or(name);
+        self.modules.push(SourceModule {
+            name: stem.to_string(),
+            statements,
+            imports,
+            exports,
+        });
+        let index = self.modules.len() - 1;
+        if let Some(path) = path {
+            self.loaded.insert(path, index);
+        }
+        index
+    }
+
+    // lexes, parses and type-checks one file, stopping at the first stage
+    // that fails
+    fn parse(&mut self, text: &str, base: usize) -> Option<Vec<Stmt>> {
+        let (tokens, errors) = Lexer::with_offset(text, base).tokenize();
+        if !errors.is_empty() {
+            self.diagnostics.extend(errors.iter().map(|err| err.to_diagnostic()));
+            return None;
+        }
+        let (statements, errors) = Parser::new(tokens).parse();
+        if !errors.is_empty() {
+            self.diagnostics.extend(errors.iter().map(|err| err.to_diagnostic()));
+            return None;
+        }
+        if let Err(errors) = TypeChecker::new().check(&statements) {
+            self.diagnostics.extend(errors.iter().map(|err| err.to_diagnostic()));
+        }
+        Some(statements)
+    }
+
+    // the module an import refers to, loading it the first time
+    fn resolve(&mut self, dir: &Path, path: &str, span: Span) -> Option<usize> {
+        let file: PathBuf = dir.join(path).components().collect();
+        let notFound = |reason: String| ModuleError::NotFound {
+            path: path.to_string(),
+            reason,
+            span,
+        };
+        let canonical = match fs::canonicalize(&file) {
+            Ok(canonical) => canonical,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                self.report(notFound(format!("there is no file '{}'", file.display())));
+                return None;
+            }
+            Err(err) => {
+                self.report(notFound(err.to_string()));
+                return None;
+            }
+        };
+
+        if let Some(start) = self.loading.iter().position(|(loading, _)| loading.as_ref() == Some(&canonical)) {
+            let mut chain: Vec<String> = self.loading[start..].iter().map(|(_, name)| name.clone()).collect();
+            chain.push(self.loading[start].1.clone());
+            self.report(ModuleError::Cycle { chain, span });
+            return None;
+        }
+        if let Some(&index) = self.loaded.get(&canonical) {
+            return Some(index);
+        }
+
+        let text = match fs::read_to_string(&canonical) {
+            Ok(text) => text,
+            Err(err) => {
+                self.report(notFound(format!("cannot read '{}': {}", file.display(), err)));
+                return None;
+            }
+        };
+        Some(self.loadFile(&file.to_string_lossy(), &text, Some(canonical)))
+    }
+
+    // a whole-module import needs a usable name; selective ones need the
+    // names to exist
+    fn checkImport(&mut self, target: usize, path: &str, names: Option<&[ImportedName]>, span: Span) {
+        let names = match names {
+            Some(names) => names,
+            None => {
+                if moduleName(path).is_none() {
+                    let name = self.modules[target].name.clone();
+                    self.report(ModuleError::BadName {
+                        path: path.to_string(),
+                        name,
+                        span,
+                    });
+                }
+                return;
+            }
+        };
+        for imported in names {
+            if !self.modules[target].exports.contains(&imported.name) {
+                self.report(ModuleError::MissingExport {
+                    path: path.to_string(),
+                    name: imported.name.clone(),
+                    span: imported.span,
+                });
+            }
+        }
+    }
+
+    fn report(&mut self, err: ModuleError) {
+        self.diagnostics.push(err.to_diagnostic());
+    }
+}
+
+// the name `import "path"` binds: the file name without its extension,
+// if that is a valid identifier
+pub fn moduleName(path: &str) -> Option<String> {
+    let stem = Path::new(path).file_stem()?.to_str()?;
+    let (tokens, errors) = Lexer::new(stem).tokenize();
+    match tokens.as_slice() {
+        [first, _] if errors.is_empty() && matches!(first.kind, TokenKind::Identifier(_)) => Some(stem.to_string()),
+        _ => None,
+    }
+}
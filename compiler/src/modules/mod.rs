@@ -0,0 +1,7 @@
+pub mod error;
+pub mod loader;
+
+pub use loader::{moduleName, Loader, ModuleGraph};
+
+#[cfg(test)]
+mod tests;
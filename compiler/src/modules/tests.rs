@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use super::{Loader, ModuleGraph};
+use crate::diagnostics::SourceFile;
+use crate::interpreter::Interpreter;
+use crate::vm::{Compiler, Vm};
+
+// writes `files` into a fresh directory and loads its main.rey; the
+// error messages when it does not load
+fn load(test: &str, files: &[(&str, &str)]) -> Result<ModuleGraph, Vec<String>> {
+    let dir = std::env::temp_dir().join(format!("rey-modules-{}-{}", test, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (name, text) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().expect("files are in the directory")).expect("directory is writable");
+        fs::write(&path, text).expect("file is writable");
+    }
+    let main: PathBuf = dir.join("main.rey");
+    let text = fs::read_to_string(&main).expect("main.rey was written");
+    let (graph, diagnostics) = Loader::new().load(&SourceFile::new(&main.to_string_lossy(), &text), true);
+    let _ = fs::remove_dir_all(&dir);
+    if diagnostics.is_empty() {
+        Ok(graph)
+    } else {
+        Err(diagnostics.into_iter().map(|diagnostic| diagnostic.message).collect())
+    }
+}
+
+// what main returns, the same on both backends
+fn run(graph: &ModuleGraph) -> String {
+    let mut interpreter = Interpreter::new();
+    let walked = interpreter
+        .interpret_modules(graph)
+        .and_then(|()| interpreter.call_main(&[]))
+        .map(|value| value.to_string())
+        .map_err(|err| err.to_string());
+    let program = Compiler::new().compile(graph).expect("program compiles");
+    let compiled = Vm::new().run(&program, &[]).map(|value| value.to_string()).map_err(|err| err.to_string());
+    assert_eq!(walked, compiled, "the backends disagree");
+    walked.expect("main runs")
+}
+
+#[test]
+fn modules_are_namespaces() {
+    let graph = load(
+        "namespaces",
+        &[
+            ("main.rey", "import \"lib/util.rey\";\nvar scale = 10;\nfunc main() { return [util.scaled(2), scale]; }"),
+            ("lib/util.rey", "var scale = 3;\nfunc scaled(n) { return n * scale; }"),
+        ],
+    )
+    .expect("program loads");
+    assert_eq!(run(&graph), "[6, 10]");
+}
+
+#[test]
+fn selected_names_are_imported_and_modules_load_once() {
+    let graph = load(
+        "selected",
+        &[
+            ("main.rey", "import next from \"count.rey\";\nimport \"other.rey\";\nfunc main() { next(); return [next(), other.bump()]; }"),
+            ("other.rey", "import next from \"count.rey\";\nfunc bump() { return next(); }"),
+            ("count.rey", "var n = 0;\nfunc next() { n += 1; return n; }"),
+        ],
+    )
+    .expect("program loads");
+    assert_eq!(graph.modules.len(), 3);
+    assert_eq!(run(&graph), "[2, 3]");
+}
+
+#[test]
+fn import_cycles_are_errors() {
+    let errors = load(
+        "cycle",
+        &[("main.rey", "import \"a.rey\";"), ("a.rey", "import \"b.rey\";"), ("b.rey", "import \"a.rey\";")],
+    )
+    .err()
+    .expect("the cycle is rejected");
+    assert_eq!(errors.len(), 1);
+    let chain = errors[0].strip_prefix("Import cycle: ").expect("a cycle error");
+    let files: Vec<&str> = chain.split(" -> ").filter_map(|path| Path::new(path).file_name()?.to_str()).collect();
+    assert_eq!(files, ["a.rey", "b.rey", "a.rey"]);
+}
+
+#[test]
+fn missing_files_and_exports_are_errors() {
+    let errors = load(
+        "missing",
+        &[("main.rey", "import \"nowhere.rey\";\nimport gone from \"lib.rey\";"), ("lib.rey", "var here = 1;")],
+    )
+    .err()
+    .expect("the imports are rejected");
+    assert_eq!(errors, ["Cannot import 'nowhere.rey'", "'lib.rey' has no export named 'gone'"]);
+}
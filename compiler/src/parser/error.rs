@@ -1,4 +1,6 @@
+use crate::diagnostics::Diagnostic;
 use crate::lexer::span::Span;
+use std::fmt;
 use crate::lexer::TokenKind;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -18,9 +20,67 @@ pub enum ParserError {
         message: String,
         span: Span,
     },
+
+    MisplacedDocComment {
+        span: Span,
+    },
 }
 impl ParserError {
     pub fn new(message: String, span: Span) -> Self {
         Self::Custom { message, span }
     }
-}
\ No newline at end of file
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let label = match self {
+            ParserError::UnexpectedToken { expected, .. } => {
+                format!("expected {}", expectedList(expected))
+            }
+            ParserError::UnexpectedEOF { expected, .. } => {
+                format!("expected {}", expectedList(expected))
+            }
+            ParserError::Custom { .. } => String::new(),
+            ParserError::MisplacedDocComment { .. } => "not followed by a declaration".to_string(),
+        };
+        let diagnostic = Diagnostic::error(self.to_string()).with_primary(self.span(), label);
+        match self {
+            ParserError::MisplacedDocComment { .. } => {
+                diagnostic.with_note("use '//' for comments that don't describe a declaration")
+            }
+            _ => diagnostic,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ParserError::UnexpectedToken { span, .. }
+            | ParserError::UnexpectedEOF { span, .. }
+            | ParserError::Custom { span, .. }
+            | ParserError::MisplacedDocComment { span } => *span,
+        }
+    }
+}
+
+fn expectedList(expected: &[TokenKind]) -> String {
+    expected
+        .iter()
+        .map(|kind| kind.to_string())
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParserError::UnexpectedToken { expected, found, .. } => {
+                write!(f, "Expected {}, found {}", expectedList(expected), found)
+            }
+            ParserError::UnexpectedEOF { expected, .. } => {
+                write!(f, "Unexpected end of file, expected {}", expectedList(expected))
+            }
+            ParserError::Custom { message, .. } => write!(f, "{}", message),
+            ParserError::MisplacedDocComment { .. } => {
+                write!(f, "Doc comments must come before a 'var', 'func' or 'struct' declaration")
+            }
+        }
+    }
+}
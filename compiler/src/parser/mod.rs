@@ -2,4 +2,6 @@ pub mod error;
 pub mod parser;
 
 pub use parser::Parser;
-pub use crate::ast::{Expr, Literal, Stmt, Type};
+
+#[cfg(test)]
+mod tests;
@@ -1,26 +1,104 @@
 #![allow(non_snake_case)]
 
-use crate::ast::{Expr, Literal, Parameter, Stmt, Type};
-use crate::lexer::{Token, TokenKind};
+use crate::ast::{Expr, Field, ImportedName, Literal, Parameter, Stmt, Target, Type};
+use crate::lexer::span::Span;
+use crate::lexer::{StringSegment, Token, TokenKind};
 use crate::parser::error::ParserError;
 
 //impl for recursive descent parser
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<ParserError>,
 } 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+        }
     }
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, ParserError> {
+
+    // parses the whole program, recovering after each syntax error so that
+    // every error is reported; the returned statements are the ones that parsed
+    pub fn parse(&mut self) -> (Vec<Stmt>, Vec<ParserError>) {
         let mut statements = Vec::new();
         while !self.isAtEnd() {
-            if let Some(stmt) = self.parseStatement()? {
+            if let Some(stmt) = self.parseStatementOrRecover() {
                 statements.push(stmt);
             }
         }
-        Ok(statements)
+        (statements, std::mem::take(&mut self.errors))
+    }
+
+    fn parseStatementOrRecover(&mut self) -> Option<Stmt> {
+        let start = self.current;
+        match self.parseStatement() {
+            Ok(stmt) => stmt,
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize(start);
+                None
+            }
+        }
+    }
+
+    // panic mode: skip tokens until the next statement boundary, jumping
+    // over whole `{ ... }` groups so a broken header doesn't leak its body,
+    // including the rest of any group the statement already opened
+    fn synchronize(&mut self, start: usize) {
+        if self.current == start {
+            self.advance();
+        }
+        let mut depth = 0;
+        for token in &self.tokens[start..self.current] {
+            match token.kind {
+                TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightBrace if depth > 0 => depth -= 1,
+                _ => {}
+            }
+        }
+        let opened = depth > 0;
+        while !self.isAtEnd() {
+            if depth == 0 && self.current > start && self.previous().kind == TokenKind::Semicolon {
+                return;
+            }
+            match self.peek().kind {
+                TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightBrace if depth > 0 => {
+                    depth -= 1;
+                    self.advance();
+                    if depth == 0 {
+                        // a group left open by the statement, such as a dict
+                        // literal, may be followed by the rest of it
+                        if opened {
+                            self.matchToken(&TokenKind::Semicolon);
+                        }
+                        return;
+                    }
+                    continue;
+                }
+                TokenKind::RightBrace
+                | TokenKind::Var
+                | TokenKind::Func
+                | TokenKind::If
+                | TokenKind::While
+                | TokenKind::For
+                | TokenKind::Return
+                | TokenKind::Break
+                | TokenKind::Continue
+                | TokenKind::Import
+                | TokenKind::Struct
+                | TokenKind::Impl
+                    if depth == 0 =>
+                {
+                    return
+                }
+                _ => {}
+            }
+            self.advance();
+        }
     }
 
     //parsing statements
@@ -28,10 +106,16 @@ impl Parser {
         if self.isAtEnd() {
             return Ok(None);
         }
+        let doc = self.parseDocComment()?;
         if self.matchToken(&TokenKind::Var) {
-            Ok(Some(self.parseVarDeclaration()?))
-        } else if self.matchToken(&TokenKind::Func) {
-            Ok(Some(self.parseFuncDeclaration()?))
+            Ok(Some(self.parseVarDeclaration(doc)?))
+        } else if self.isFuncDeclaration() {
+            self.advance();
+            Ok(Some(self.parseFuncDeclaration(doc)?))
+        } else if self.matchToken(&TokenKind::Struct) {
+            Ok(Some(self.parseStructDeclaration(doc)?))
+        } else if self.matchToken(&TokenKind::Impl) {
+            Ok(Some(self.parseImplBlock()?))
         } else if self.matchToken(&TokenKind::If) {
             Ok(Some(self.parseIfStatement()?))
         } else if self.matchToken(&TokenKind::While) {
@@ -44,11 +128,44 @@ impl Parser {
             Ok(Some(self.parseContinueStatement()?))
         } else if self.matchToken(&TokenKind::Return) {
             Ok(Some(self.parseReturnStatement()?))
+        } else if self.matchToken(&TokenKind::Import) {
+            Ok(Some(self.parseImportStatement()?))
+        } else if self.isShortDeclaration() {
+            Ok(Some(self.parseShortDeclaration(doc)?))
         } else {
             Ok(Some(self.parseExpressionStatement()?))
         }
     }
-    fn parseVarDeclaration(&mut self) -> Result<Stmt, ParserError> {
+    // consecutive `///` lines document the declaration that follows them
+    fn parseDocComment(&mut self) -> Result<Option<String>, ParserError> {
+        let (doc, span) = match self.docLines() {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+        let declaration = self.check(&TokenKind::Var) || self.check(&TokenKind::Struct) || self.isFuncDeclaration();
+        if !declaration && !self.isShortDeclaration() {
+            return Err(ParserError::MisplacedDocComment { span });
+        }
+        Ok(Some(doc))
+    }
+
+    // the doc comment lines at the current token, joined, and where they are
+    fn docLines(&mut self) -> Option<(String, Span)> {
+        let mut lines = Vec::new();
+        let mut span = self.peek().span;
+        while let TokenKind::DocComment(text) = &self.peek().kind {
+            lines.push(text.clone());
+            span = span.merge(self.advance().span);
+        }
+        if lines.is_empty() {
+            None
+        } else {
+            Some((lines.join("\n"), span))
+        }
+    }
+
+    fn parseVarDeclaration(&mut self, doc: Option<String>) -> Result<Stmt, ParserError> {
+        let start = self.previous().span;
         let name = match &self.peek().kind {
             TokenKind::Identifier(name) => name.clone(),
             _ => return Err(self.error("Expected variable name.")),
@@ -57,25 +174,152 @@ impl Parser {
 
         let ty = self.parseTypeAnnotation()?;
 
-        self.consume(&TokenKind::Equal, "Expected '=' after variable name.")?;
+        self.consume(&TokenKind::Equal)?;
+        let initializer = self.parseExpression()?;
+        self.consume(&TokenKind::Semicolon)?;
+
+        Ok(Stmt::VarDecl {
+            doc,
+            name,
+            ty,
+            initializer,
+            span: self.spanFrom(start),
+        })
+    }
+
+    // `name := value;` declares an untyped variable, like `var name = value;`
+    fn isShortDeclaration(&self) -> bool {
+        matches!(self.peek().kind, TokenKind::Identifier(_)) && self.checkNext(&TokenKind::ColonEqual)
+    }
+
+    fn parseShortDeclaration(&mut self, doc: Option<String>) -> Result<Stmt, ParserError> {
+        let start = self.peek().span;
+        let name = match &self.advance().kind {
+            TokenKind::Identifier(name) => name.clone(),
+            _ => unreachable!("checked by parseStatement"),
+        };
+        self.advance();
         let initializer = self.parseExpression()?;
-        self.consume(
-            &TokenKind::Semicolon,
-            "Expected ';' after variable declaration.",
-        )?;
+        self.consume(&TokenKind::Semicolon)?;
+        Ok(Stmt::VarDecl {
+            doc,
+            name,
+            ty: None,
+            initializer,
+            span: self.spanFrom(start),
+        })
+    }
 
-        Ok(Stmt::VarDecl { name, ty, initializer })
+    // `func(` starts a lambda, which is an expression
+    fn isFuncDeclaration(&self) -> bool {
+        self.check(&TokenKind::Func) && !self.checkNext(&TokenKind::LeftParen)
     }
 
-    fn parseFuncDeclaration(&mut self) -> Result<Stmt, ParserError> {
+    fn parseFuncDeclaration(&mut self, doc: Option<String>) -> Result<Stmt, ParserError> {
         let name = match &self.peek().kind {
             TokenKind::Identifier(name) => name.clone(),
             _ => return Err(self.error("Expected function name.")),
         };
-        self.advance();
+        let span = self.advance().span;
 
-        self.consume(&TokenKind::LeftParen, "Expected '(' after function name.")?;
+        self.consume(&TokenKind::LeftParen)?;
+        let params = self.parseParameters()?;
+        let return_ty = self.parseTypeAnnotation()?;
+
+        self.consume(&TokenKind::LeftBrace)?;
+
+        let body = self.parseBlock();
+        self.consume(&TokenKind::RightBrace)?;
+
+        Ok(Stmt::FuncDecl {
+            doc,
+            name,
+            params,
+            return_ty,
+            body,
+            span,
+        })
+    }
+
+    // `struct Name { field: type, ... }`, where types are optional and a
+    // trailing comma is allowed
+    fn parseStructDeclaration(&mut self, doc: Option<String>) -> Result<Stmt, ParserError> {
+        let name = match &self.peek().kind {
+            TokenKind::Identifier(name) => name.clone(),
+            _ => return Err(self.error("Expected struct name.")),
+        };
+        let span = self.advance().span;
+        self.consume(&TokenKind::LeftBrace)?;
+
+        let mut fields: Vec<Field> = Vec::new();
+        while !self.check(&TokenKind::RightBrace) {
+            let doc = self.docLines();
+            let field_name = match (&self.peek().kind, &doc) {
+                (TokenKind::Identifier(name), _) => name.clone(),
+                (_, Some((_, span))) => return Err(ParserError::MisplacedDocComment { span: *span }),
+                _ => return Err(self.error("Expected field name.")),
+            };
+            let field_span = self.advance().span;
+            let ty = self.parseTypeAnnotation()?;
+            // reported without leaving the struct, so the rest of it parses
+            if fields.iter().any(|field| field.name == field_name) {
+                self.errors.push(ParserError::new(format!("Duplicate field '{}'.", field_name), field_span));
+            } else {
+                fields.push(Field {
+                    doc: doc.map(|(doc, _)| doc),
+                    name: field_name,
+                    ty,
+                    span: field_span,
+                });
+            }
+            if !self.matchToken(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightBrace)?;
+
+        Ok(Stmt::Struct {
+            doc,
+            name,
+            fields,
+            span,
+        })
+    }
+
+    // `impl Name { func method(self, ...) { ... } ... }`
+    fn parseImplBlock(&mut self) -> Result<Stmt, ParserError> {
+        let name = match &self.peek().kind {
+            TokenKind::Identifier(name) => name.clone(),
+            _ => return Err(self.error("Expected struct name after 'impl'.")),
+        };
+        let span = self.advance().span;
+        self.consume(&TokenKind::LeftBrace)?;
+
+        let mut methods = Vec::new();
+        while !self.check(&TokenKind::RightBrace) && !self.isAtEnd() {
+            let doc = self.parseDocComment()?;
+            if !self.isFuncDeclaration() {
+                return Err(self.error("Expected method declaration."));
+            }
+            self.advance();
+            let method = self.parseFuncDeclaration(doc)?;
+            if let Stmt::FuncDecl { params, span, .. } = &method {
+                if params.is_empty() {
+                    return Err(ParserError::new(
+                        "Methods take their receiver as the first parameter.".to_string(),
+                        *span,
+                    ));
+                }
+            }
+            methods.push(method);
+        }
+        self.consume(&TokenKind::RightBrace)?;
 
+        Ok(Stmt::Impl { name, methods, span })
+    }
+
+    // the parameters after a '(', up to and including the ')'
+    fn parseParameters(&mut self) -> Result<Vec<Parameter>, ParserError> {
         let mut params = Vec::new();
         if !self.check(&TokenKind::RightParen) {
             loop {
@@ -83,13 +327,14 @@ impl Parser {
                     TokenKind::Identifier(name) => name.clone(),
                     _ => return Err(self.error("Expected parameter name.")),
                 };
-                self.advance();
+                let param_span = self.advance().span;
 
                 let param_ty = self.parseTypeAnnotation()?;
 
                 params.push(Parameter {
                     name: param_name,
                     ty: param_ty,
+                    span: param_span,
                 });
 
                 if !self.matchToken(&TokenKind::Comma) {
@@ -97,41 +342,79 @@ impl Parser {
                 }
             }
         }
-        self.consume(&TokenKind::RightParen, "Expected ')' after parameters.")?;
+        self.consume(&TokenKind::RightParen)?;
+        Ok(params)
+    }
 
+    // `func(params) { body }`, a function without a name
+    fn parseLambda(&mut self) -> Result<Expr, ParserError> {
+        let start = self.advance().span;
+        self.consume(&TokenKind::LeftParen)?;
+        let params = self.parseParameters()?;
         let return_ty = self.parseTypeAnnotation()?;
 
-        self.consume(&TokenKind::LeftBrace, "Expected '{' before function body.")?;
-
-        let mut body = Vec::new();
-        while !self.check(&TokenKind::RightBrace) && !self.isAtEnd() {
-            if let Some(stmt) = self.parseStatement()? {
-                body.push(stmt);
-            }
-        }
-        self.consume(&TokenKind::RightBrace, "Expected '}' after function body.")?;
+        self.consume(&TokenKind::LeftBrace)?;
+        let body = self.parseBlock();
+        self.consume(&TokenKind::RightBrace)?;
 
-        Ok(Stmt::FuncDecl {
-            name,
+        Ok(Expr::Lambda {
             params,
             return_ty,
             body,
+            span: self.spanFrom(start),
         })
     }
 
+    // `(params) => value`, which returns `value`
+    fn parseArrowLambda(&mut self) -> Result<Expr, ParserError> {
+        let start = self.advance().span;
+        let params = self.parseParameters()?;
+        self.consume(&TokenKind::FatArrow)?;
+        let value = self.parseExpression()?;
+        let span = value.span();
+        Ok(Expr::Lambda {
+            params,
+            return_ty: None,
+            body: vec![Stmt::Return { value, span }],
+            span: self.spanFrom(start),
+        })
+    }
+
+    // whether the '(' at the current token opens an arrow lambda's
+    // parameters rather than a grouping
+    fn isArrowLambda(&self) -> bool {
+        let rest = &self.tokens[self.current + 1..];
+        let end = rest
+            .iter()
+            .position(|token| !matches!(token.kind, TokenKind::Identifier(_) | TokenKind::Comma | TokenKind::Colon))
+            .unwrap_or(rest.len());
+        matches!(rest.get(end).map(|token| &token.kind), Some(TokenKind::RightParen))
+            && matches!(rest.get(end + 1).map(|token| &token.kind), Some(TokenKind::FatArrow))
+    }
+
+    // conditions need no parentheses; `if (x) {` still parses, as a
+    // parenthesized expression
     fn parseIfStatement(&mut self) -> Result<Stmt, ParserError> {
-        self.consume(&TokenKind::LeftParen, "Expected '(' after 'if'.")?;
+        let start = self.previous().span;
         let condition = self.parseExpression()?;
-        self.consume(&TokenKind::RightParen, "Expected ')' after condition.")?;
 
-        self.consume(&TokenKind::LeftBrace, "Expected '{' after condition.")?;
-        let then_branch = self.parseBlock()?;
-        self.consume(&TokenKind::RightBrace, "Expected '}' after then branch.")?;
+        self.consume(&TokenKind::LeftBrace)?;
+        let then_branch = self.parseBlock();
+        self.consume(&TokenKind::RightBrace)?;
 
+        // `else if` is an `if` statement alone in the else branch
         let else_branch = if self.matchToken(&TokenKind::Else) {
-            self.consume(&TokenKind::LeftBrace, "Expected '{' after 'else'.")?;
-            let block = self.parseBlock()?;
-            self.consume(&TokenKind::RightBrace, "Expected '}' after else branch.")?;
+            if self.matchToken(&TokenKind::If) {
+                return Ok(Stmt::If {
+                    condition,
+                    then_branch,
+                    else_branch: Some(vec![self.parseIfStatement()?]),
+                    span: self.spanFrom(start),
+                });
+            }
+            self.consume(&TokenKind::LeftBrace)?;
+            let block = self.parseBlock();
+            self.consume(&TokenKind::RightBrace)?;
             Some(block)
         } else {
             None
@@ -141,25 +424,27 @@ impl Parser {
             condition,
             then_branch,
             else_branch,
+            span: self.spanFrom(start),
         })
     }
 
     fn parseWhileStatement(&mut self) -> Result<Stmt, ParserError> {
-        self.consume(&TokenKind::LeftParen, "Expected '(' after 'while'.")?;
+        let start = self.previous().span;
         let condition = self.parseExpression()?;
-        self.consume(&TokenKind::RightParen, "Expected ')' after condition.")?;
 
-        self.consume(&TokenKind::LeftBrace, "Expected '{' after condition.")?;
-        let body = self.parseBlock()?;
-        self.consume(&TokenKind::RightBrace, "Expected '}' after while body.")?;
+        self.consume(&TokenKind::LeftBrace)?;
+        let body = self.parseBlock();
+        self.consume(&TokenKind::RightBrace)?;
 
         Ok(Stmt::While {
             condition,
             body,
+            span: self.spanFrom(start),
         })
     }
 
     fn parseForStatement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.previous().span;
         // Parse variable name
         let variable = match self.peek().kind {
             TokenKind::Identifier(ref name) => name.clone(),
@@ -167,63 +452,111 @@ impl Parser {
         };
         self.advance();
 
-        self.consume(&TokenKind::In, "Expected 'in' after variable name.")?;
-        match self.peek().kind {
-            TokenKind::Identifier(ref name) if name == "range" => {
-                self.advance();
-            }
-            _ => return Err(self.error("Expected 'range' after 'in'.")),
+        self.consume(&TokenKind::In)?;
+        let isRange = matches!(self.peek().kind, TokenKind::Identifier(ref name) if name == "range")
+            && self.checkNext(&TokenKind::LeftParen);
+        if !isRange {
+            let iterable = self.parseExpression()?;
+            self.consume(&TokenKind::LeftBrace)?;
+            let body = self.parseBlock();
+            self.consume(&TokenKind::RightBrace)?;
+            return Ok(Stmt::ForEach {
+                variable,
+                iterable,
+                body,
+                span: self.spanFrom(keyword),
+            });
         }
-        self.consume(&TokenKind::LeftParen, "Expected '(' after 'range'.")?;
+        self.advance();
+        self.consume(&TokenKind::LeftParen)?;
 
         let start = self.parseExpression()?;
-        self.consume(&TokenKind::Comma, "Expected ',' after start value.")?;
+        self.consume(&TokenKind::Comma)?;
         let end = self.parseExpression()?;
 
-        self.consume(&TokenKind::RightParen, "Expected ')' after end value.")?;
-        self.consume(&TokenKind::LeftBrace, "Expected '{' after range.")?;
+        self.consume(&TokenKind::RightParen)?;
+        self.consume(&TokenKind::LeftBrace)?;
 
-        let body = self.parseBlock()?;
+        let body = self.parseBlock();
 
-        self.consume(&TokenKind::RightBrace, "Expected '}' after for body.")?;
+        self.consume(&TokenKind::RightBrace)?;
 
         Ok(Stmt::For {
             variable,
             start,
             end,
             body,
+            span: self.spanFrom(keyword),
         })
     }
 
     fn parseBreakStatement(&mut self) -> Result<Stmt, ParserError> {
-        self.consume(&TokenKind::Semicolon, "Expected ';' after 'break'.")?;
-        Ok(Stmt::Break)
+        let start = self.previous().span;
+        self.consume(&TokenKind::Semicolon)?;
+        Ok(Stmt::Break {
+            span: self.spanFrom(start),
+        })
     }
 
     fn parseContinueStatement(&mut self) -> Result<Stmt, ParserError> {
-        self.consume(&TokenKind::Semicolon, "Expected ';' after 'continue'.")?;
-        Ok(Stmt::Continue)
+        let start = self.previous().span;
+        self.consume(&TokenKind::Semicolon)?;
+        Ok(Stmt::Continue {
+            span: self.spanFrom(start),
+        })
     }
 
-    fn parseBlock(&mut self) -> Result<Vec<Stmt>, ParserError> {
+    fn parseBlock(&mut self) -> Vec<Stmt> {
         let mut statements = Vec::new();
         while !self.check(&TokenKind::RightBrace) && !self.isAtEnd() {
-            if let Some(stmt) = self.parseStatement()? {
+            if let Some(stmt) = self.parseStatementOrRecover() {
                 statements.push(stmt);
             }
         }
-        Ok(statements)
+        statements
     }
 
     fn parseReturnStatement(&mut self) -> Result<Stmt, ParserError> {
-        let expr = self.parseExpression()?;
-        self.consume(&TokenKind::Semicolon, "Expected ';' after return value.")?;
-        Ok(Stmt::Return(expr))
+        let start = self.previous().span;
+        let value = self.parseExpression()?;
+        self.consume(&TokenKind::Semicolon)?;
+        Ok(Stmt::Return {
+            value,
+            span: self.spanFrom(start),
+        })
+    }
+
+    // `import "path";` or `import a, b from "path";`
+    fn parseImportStatement(&mut self) -> Result<Stmt, ParserError> {
+        let mut names = None;
+        if let TokenKind::Identifier(_) = self.peek().kind {
+            let mut listed = Vec::new();
+            loop {
+                let name = match &self.peek().kind {
+                    TokenKind::Identifier(name) => name.clone(),
+                    _ => return Err(self.error("Expected a name to import.")),
+                };
+                let span = self.advance().span;
+                listed.push(ImportedName { name, span });
+                if !self.matchToken(&TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(&TokenKind::From)?;
+            names = Some(listed);
+        }
+        let path = match &self.peek().kind {
+            TokenKind::StringLiteral(path) => path.clone(),
+            _ => return Err(self.error("Expected a file path string after 'import'.")),
+        };
+        let span = self.advance().span;
+        self.consume(&TokenKind::Semicolon)?;
+        Ok(Stmt::Import { path, names, span })
     }
 
     fn parseExpressionStatement(&mut self) -> Result<Stmt, ParserError> {
         let expr = self.parseExpression()?;
-        self.consume(&TokenKind::Semicolon, "Expected ';' after expression.")?;
+        self.consume(&TokenKind::Semicolon)?;
         Ok(Stmt::ExprStmt(expr))
     }
 
@@ -231,10 +564,21 @@ impl Parser {
         if self.matchToken(&TokenKind::Colon) {
             match &self.peek().kind {
                 TokenKind::Identifier(name) => {
-                    let ty = Type { name: name.clone() };
+                    let ty = Type {
+                        name: name.clone(),
+                        span: self.peek().span,
+                    };
                     self.advance();
                     Ok(Some(ty))
                 }
+                // `null` is a keyword, but also names the type of its value
+                TokenKind::Null => {
+                    let span = self.advance().span;
+                    Ok(Some(Type {
+                        name: "null".to_string(),
+                        span,
+                    }))
+                }
                 _ => Err(self.error("Expected type name after ':'")),
             }
         } else {
@@ -243,104 +587,193 @@ impl Parser {
     }
 
     fn parseUnary(&mut self) -> Result<Expr, ParserError> {
-        match &self.peek().kind {
-            TokenKind::Minus => {
-                self.advance();
-                let expr = self.parseUnary()?;
-                Ok(Expr::Binary {
-                    left: Box::new(Expr::Literal(Literal::Number(0.0))),
-                    op: TokenKind::Minus,
-                    right: Box::new(expr),
+        match self.peek().kind.clone() {
+            op @ (TokenKind::Minus | TokenKind::Bang | TokenKind::Tilde) => {
+                let start = self.advance().span;
+                let right = self.parseUnary()?;
+                let span = start.merge(right.span());
+                Ok(Expr::Unary {
+                    op,
+                    right: Box::new(right),
+                    span,
                 })
             }
-            _ => self.parsePrimary(),
+            // `++x` and `--x` give the updated value
+            op @ (TokenKind::PlusPlus | TokenKind::MinusMinus) => {
+                let start = self.advance().span;
+                let target = self.parseCall()?;
+                self.update(target, op, true, start)
+            }
+            _ => self.parseCall(),
+        }
+    }
+
+    fn update(&self, target: Expr, op: TokenKind, prefix: bool, start: Span) -> Result<Expr, ParserError> {
+        match Target::from_expr(target) {
+            Ok(target) => Ok(Expr::Update {
+                target,
+                op,
+                prefix,
+                span: self.spanFrom(start),
+            }),
+            Err(target) => Err(ParserError::new(
+                format!("Invalid '{}' target.", op.lexeme().unwrap_or_default()),
+                target.span(),
+            )),
         }
     }
 
     fn parsePrimary(&mut self) -> Result<Expr, ParserError> {
         match self.peek().kind.clone() {
             TokenKind::Identifier(name) => {
+                // calls, indexing and property access are handled by parseCall
+                let span = self.advance().span;
+                Ok(Expr::Variable { name, span })
+            }
+            // grouping leaves no trace in the tree, whose shape already
+            // says what the parentheses did
+            TokenKind::LeftParen if self.isArrowLambda() => self.parseArrowLambda(),
+            TokenKind::Func => self.parseLambda(),
+            TokenKind::LeftParen => {
                 self.advance();
-                if self.matchToken(&TokenKind::LeftParen) {
-                    // Function call
-                    let callee = Expr::Variable(name);
-                    let mut args = Vec::new();
-
-                    if !self.check(&TokenKind::RightParen) {
-                        loop {
-                            args.push(self.parseExpression()?);
-                            if !self.matchToken(&TokenKind::Comma) {
-                                break;
-                            }
-                        }
+                let expr = self.parseExpression()?;
+                self.consume(&TokenKind::RightParen)?;
+                Ok(expr)
+            }
+            TokenKind::LeftBracket => {
+                let start = self.advance().span;
+                let mut elements = Vec::new();
+                while !self.check(&TokenKind::RightBracket) {
+                    elements.push(self.parseExpression()?);
+                    if !self.matchToken(&TokenKind::Comma) {
+                        break;
                     }
-                    self.consume(&TokenKind::RightParen, "Expected ')' after function arguments.")?;
-                    Ok(Expr::Call {
-                        callee: Box::new(callee),
-                        args,
-                    })
-                } else {
-                    Ok(Expr::Variable(name))
                 }
+                self.consume(&TokenKind::RightBracket)?;
+                Ok(Expr::Array {
+                    elements,
+                    span: self.spanFrom(start),
+                })
+            }
+            // blocks only follow statement keywords, which consume their own
+            // braces, so a '{' reaching expression position starts a dictionary
+            TokenKind::LeftBrace => {
+                let start = self.advance().span;
+                let mut entries = Vec::new();
+                while !self.check(&TokenKind::RightBrace) {
+                    let key = self.parseExpression()?;
+                    self.consume(&TokenKind::Colon)?;
+                    let value = self.parseExpression()?;
+                    entries.push((key, value));
+                    if !self.matchToken(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.consume(&TokenKind::RightBrace)?;
+                Ok(Expr::Dict {
+                    entries,
+                    span: self.spanFrom(start),
+                })
             }
             TokenKind::StringLiteral(value) => {
-                self.advance();
-                Ok(Expr::Literal(Literal::String(value)))
+                let span = self.advance().span;
+                Ok(Expr::Literal {
+                    value: Literal::String(value),
+                    span,
+                })
             }
-            TokenKind::NumberLiteral(value) => {
-                self.advance();
-                Ok(Expr::Literal(Literal::Number(value)))
+            TokenKind::InterpolatedString(segments) => {
+                let span = self.advance().span;
+                let mut parts = Vec::new();
+                for segment in segments {
+                    match segment {
+                        StringSegment::Text(text) => parts.push(Expr::Literal {
+                            value: Literal::String(text),
+                            span,
+                        }),
+                        StringSegment::Code(tokens) => {
+                            let mut parser = Parser::new(tokens);
+                            parts.push(parser.parseExpression()?);
+                            parser.consume(&TokenKind::RightBrace)?;
+                        }
+                    }
+                }
+                Ok(Expr::Interpolation { parts, span })
+            }
+            TokenKind::IntLiteral(value) => {
+                let span = self.advance().span;
+                Ok(Expr::Literal {
+                    value: Literal::Int(value),
+                    span,
+                })
+            }
+            TokenKind::BigIntLiteral(value) => {
+                let span = self.advance().span;
+                Ok(Expr::Literal {
+                    value: Literal::BigInt(value),
+                    span,
+                })
+            }
+            TokenKind::FloatLiteral(value) => {
+                let span = self.advance().span;
+                Ok(Expr::Literal {
+                    value: Literal::Float(value),
+                    span,
+                })
             }
             TokenKind::True => {
-                self.advance();
-                Ok(Expr::Literal(Literal::Bool(true)))
+                let span = self.advance().span;
+                Ok(Expr::Literal {
+                    value: Literal::Bool(true),
+                    span,
+                })
             }
             TokenKind::False => {
-                self.advance();
-                Ok(Expr::Literal(Literal::Bool(false)))
+                let span = self.advance().span;
+                Ok(Expr::Literal {
+                    value: Literal::Bool(false),
+                    span,
+                })
             }
             TokenKind::Null => {
-                self.advance();
-                Ok(Expr::Literal(Literal::Null))
+                let span = self.advance().span;
+                Ok(Expr::Literal {
+                    value: Literal::Null,
+                    span,
+                })
             }
             _ => Err(self.error("Expected expression.")),
         }
     }
 
-    fn parseAdditive(&mut self) -> Result<Expr, ParserError> {
-        let mut expr = self.parseUnary()?;
+    // `=` and the compound `+=`, `-=`, `*=`, `/=` and `%=`, which apply
+    // their operator to the target's value and the right-hand side; the
+    // target is a variable, `a[i]` or `obj.field`
+    fn parseAssignment(&mut self) -> Result<Expr, ParserError> {
+        let start = self.peek().span;
+        let expr = self.parseLogicOr()?;
 
-        while matches!(self.peek().kind, TokenKind::Plus | TokenKind::Minus) {
-            let op = self.peek().kind.clone();
-            self.advance();
-            let right = self.parseUnary()?;
+        let op = match self.peek().kind {
+            TokenKind::Equal => None,
+            TokenKind::PlusEqual => Some(TokenKind::Plus),
+            TokenKind::MinusEqual => Some(TokenKind::Minus),
+            TokenKind::StarEqual => Some(TokenKind::Star),
+            TokenKind::SlashEqual => Some(TokenKind::Slash),
+            TokenKind::PercentEqual => Some(TokenKind::Percent),
+            _ => return Ok(expr),
+        };
+        self.advance();
+        let value = self.parseAssignment()?;
 
-            expr = Expr::Binary {
-                left: Box::new(expr),
+        match Target::from_expr(expr) {
+            Ok(target) => Ok(Expr::Assign {
+                target,
                 op,
-                right: Box::new(right),
-            };
+                value: Box::new(value),
+                span: self.spanFrom(start),
+            }),
+            Err(target) => Err(ParserError::new("Invalid assignment target.".to_string(), target.span())),
         }
-        Ok(expr)
-    }
-    
-    fn parseAssignment(&mut self) -> Result<Expr, ParserError> {
-        let expr = self.parseLogicOr()?;
-    
-        if self.matchToken(&TokenKind::Equal) {
-            let value = self.parseAssignment()?;
-    
-            if let Expr::Variable(name) = expr {
-                return Ok(Expr::Assign {
-                    name,
-                    value: Box::new(value),
-                });
-            }
-    
-            return Err(self.error("Invalid assignment target."));
-        }
-    
-        Ok(expr)
     }
 
     fn parseLogicOr(&mut self) -> Result<Expr, ParserError> {
@@ -348,7 +781,8 @@ impl Parser {
     while self.matchToken(&TokenKind::OrOr) {
         let op = self.previous().kind.clone();
         let right = self.parseLogicAnd()?;
-        expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right) };
+        let span = expr.span().merge(right.span());
+        expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right), span };
     }
     Ok(expr)
 }
@@ -358,7 +792,8 @@ fn parseLogicAnd(&mut self) -> Result<Expr, ParserError> {
     while self.matchToken(&TokenKind::AndAnd) {
         let op = self.previous().kind.clone();
         let right = self.parseEquality()?;
-        expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right) };
+        let span = expr.span().merge(right.span());
+        expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right), span };
     }
     Ok(expr)
 }
@@ -368,18 +803,23 @@ fn parseEquality(&mut self) -> Result<Expr, ParserError> {
         let op = self.peek().kind.clone();
         self.advance();
         let right = self.parseComparison()?;
-        expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right) };
+        let span = expr.span().merge(right.span());
+        expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right), span };
     }
     Ok(expr)
 }
 
 fn parseComparison(&mut self) -> Result<Expr, ParserError> {
     let mut expr = self.parseTerm()?;
-    while matches!(self.peek().kind, TokenKind::Greater | TokenKind::Less) {
+    while matches!(
+        self.peek().kind,
+        TokenKind::Greater | TokenKind::GreaterEqual | TokenKind::Less | TokenKind::LessEqual
+    ) {
         let op = self.peek().kind.clone();
         self.advance();
         let right = self.parseTerm()?;
-        expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right) };
+        let span = expr.span().merge(right.span());
+        expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right), span };
     }
     Ok(expr)
 }
@@ -399,21 +839,23 @@ fn parseComparison(&mut self) -> Result<Expr, ParserError> {
             false
         }
     }
-    fn consume(&mut self, kind: &TokenKind, message: &str) -> Result<(), ParserError> {
+    fn consume(&mut self, kind: &TokenKind) -> Result<(), ParserError> {
         if self.check(kind) {
             self.advance();
             Ok(())
+        } else if self.isAtEnd() {
+            Err(ParserError::UnexpectedEOF {
+                expected: vec![kind.clone()],
+                span: self.peek().span,
+            })
+        } else if let TokenKind::DocComment(_) = self.peek().kind {
+            Err(ParserError::MisplacedDocComment { span: self.peek().span })
         } else {
-            Err(self.error(message))
-        }
-    }
-    fn consumeIdentifier(&mut self, message: &str) -> Result<(), ParserError> {
-        match &self.peek().kind {
-            TokenKind::Identifier(_) => {
-                self.advance();
-                Ok(())
-            }
-            _ => Err(self.error(message)),
+            Err(ParserError::UnexpectedToken {
+                expected: vec![kind.clone()],
+                found: self.peek().kind.clone(),
+                span: self.peek().span,
+            })
         }
     }
     fn check(&self, kind: &TokenKind) -> bool {
@@ -422,6 +864,13 @@ fn parseComparison(&mut self) -> Result<Expr, ParserError> {
         }
         std::mem::discriminant(&self.peek().kind) == std::mem::discriminant(kind)
     }
+    // like check, but for the token after the current one
+    fn checkNext(&self, kind: &TokenKind) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => std::mem::discriminant(&token.kind) == std::mem::discriminant(kind),
+            None => false,
+        }
+    }
     fn advance(&mut self) -> &Token {
         if !self.isAtEnd() {
             self.current += 1;
@@ -434,6 +883,10 @@ fn parseComparison(&mut self) -> Result<Expr, ParserError> {
     fn peek(&self) -> &Token {
         &self.tokens[self.current]
     }
+    // span covering everything from `start` up to the last consumed token
+    fn spanFrom(&self, start: Span) -> Span {
+        Span::new(start.start, self.previous().span.end)
+    }
     fn previous(&self) -> &Token {
         if self.current == 0 {
             &self.tokens[0]
@@ -443,6 +896,7 @@ fn parseComparison(&mut self) -> Result<Expr, ParserError> {
     }
 
 fn parseCall(&mut self) -> Result<Expr, ParserError> {
+        let start = self.peek().span;
         let mut expr = self.parsePrimary()?;
     
         loop {
@@ -456,16 +910,34 @@ fn parseCall(&mut self) -> Result<Expr, ParserError> {
                         }
                     }
                 }
-                self.consume(&TokenKind::RightParen, "Expected ')' after arguments.")?;
-                expr = Expr::Call { callee: Box::new(expr), args };
+                self.consume(&TokenKind::RightParen)?;
+                expr = Expr::Call {
+                    callee: Box::new(expr),
+                    args,
+                    span: self.spanFrom(start),
+                };
+            }
+            else if self.matchToken(&TokenKind::LeftBracket) {
+                let index = self.parseExpression()?;
+                self.consume(&TokenKind::RightBracket)?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    index: Box::new(index),
+                    span: self.spanFrom(start),
+                };
+            }
+            // `x++` and `x--` give the value from before the update
+            else if matches!(self.peek().kind, TokenKind::PlusPlus | TokenKind::MinusMinus) {
+                let op = self.advance().kind.clone();
+                return self.update(expr, op, false, start);
             }
             else if self.matchToken(&TokenKind::Dot) {
                 let name = match &self.peek().kind {
                     TokenKind::Identifier(n) => n.clone(),
                     _ => return Err(self.error("Expected property name after '.'")),
                 };
-                self.advance();
-                expr = Expr::Get { object: Box::new(expr), name };
+                let span = expr.span().merge(self.advance().span);
+                expr = Expr::Get { object: Box::new(expr), name, span };
             }
             else {
                 break;
@@ -481,25 +953,32 @@ fn parseCall(&mut self) -> Result<Expr, ParserError> {
             let op = self.peek().kind.clone();
             self.advance();
             let right = self.parseFactor()?;
-            expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            let span = expr.span().merge(right.span());
+            expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right), span };
         }
         Ok(expr)
     }
     
     fn parseFactor(&mut self) -> Result<Expr, ParserError> {
         let mut expr = self.parseUnary()?;
-        while matches!(self.peek().kind, TokenKind::Star | TokenKind::Slash) {
+        while matches!(self.peek().kind, TokenKind::Star | TokenKind::Slash | TokenKind::Percent) {
             let op = self.peek().kind.clone();
             self.advance();
             let right = self.parseUnary()?;
-            expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right) };
+            let span = expr.span().merge(right.span());
+            expr = Expr::Binary { left: Box::new(expr), op, right: Box::new(right), span };
         }
         Ok(expr)
     }
     
 
     //error
+    // a doc comment only belongs before a declaration, so wherever else
+    // the parser stops at one, that is the error rather than `message`
     fn error(&self, message: &str) -> ParserError {
+        if let TokenKind::DocComment(_) = self.peek().kind {
+            return ParserError::MisplacedDocComment { span: self.peek().span };
+        }
         ParserError::Custom {
             message: message.to_string(),
             span: self.peek().span,
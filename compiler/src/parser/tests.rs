@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::Path;
+
+use super::error::ParserError;
+use super::Parser;
+use crate::ast::{Expr, Stmt};
+use crate::formatter::Formatter;
+use crate::lexer::{Lexer, TokenKind};
+
+fn parse(source: &str) -> Result<Vec<Stmt>, Vec<ParserError>> {
+    let (tokens, lexErrors) = Lexer::new(source).tokenize();
+    assert!(lexErrors.is_empty(), "lexer errors: {:?}", lexErrors);
+    match Parser::new(tokens).parse() {
+        (statements, errors) if errors.is_empty() => Ok(statements),
+        (_, errors) => Err(errors),
+    }
+}
+
+// spans differ between equivalent sources, so trees are compared by
+// their canonical layout
+fn layout(source: &str) -> String {
+    Formatter::new().format(&parse(source).expect("source parses"))
+}
+
+#[test]
+fn parses_every_sample_program() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let mut failures = Vec::new();
+    let mut count = 0;
+    for entry in fs::read_dir(&dir).expect("src/tests exists") {
+        let path = entry.expect("directory entry").path();
+        if path.extension().is_none_or(|ext| ext != "rey") {
+            continue;
+        }
+        count += 1;
+        let source = fs::read_to_string(&path).expect("sample is readable");
+        if let Err(errors) = parse(&source) {
+            let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            failures.push(format!("{}: {}", path.display(), messages.join("; ")));
+        }
+    }
+    assert!(count > 0, "no samples found in {}", dir.display());
+    assert!(failures.is_empty(), "samples failed to parse:\n{}", failures.join("\n"));
+}
+
+#[test]
+fn if_condition_parentheses_are_optional() {
+    assert_eq!(layout("if x < 1 { y(); }"), layout("if (x < 1) { y(); }"));
+}
+
+#[test]
+fn while_condition_parentheses_are_optional() {
+    assert_eq!(layout("while i < 5 { i++; }"), layout("while (i < 5) { i++; }"));
+}
+
+#[test]
+fn parenthesized_condition_can_continue() {
+    let statements = parse("if (a) || b { }").expect("source parses");
+    match &statements[..] {
+        [Stmt::If {
+            condition: Expr::Binary { op: TokenKind::OrOr, .. },
+            ..
+        }] => {}
+        other => panic!("unexpected tree: {:?}", other),
+    }
+}
+
+#[test]
+fn else_if_nests_in_the_else_branch() {
+    let statements = parse("if a { x(); } else if b { y(); } else { z(); }").expect("source parses");
+    let else_branch = match &statements[..] {
+        [Stmt::If {
+            else_branch: Some(else_branch),
+            ..
+        }] => else_branch,
+        other => panic!("unexpected tree: {:?}", other),
+    };
+    match &else_branch[..] {
+        [Stmt::If {
+            else_branch: Some(last),
+            ..
+        }] => assert_eq!(last.len(), 1),
+        other => panic!("unexpected else branch: {:?}", other),
+    }
+    assert_eq!(
+        layout("if a { x(); } else { if b { y(); } else { z(); } }"),
+        "if a {\n    x();\n} else if b {\n    y();\n} else {\n    z();\n}\n"
+    );
+}
+
+#[test]
+fn condition_needs_a_block() {
+    let errors = parse("if x y();").expect_err("a body without braces is rejected");
+    assert_eq!(errors[0].to_string(), "Expected '{', found identifier 'y'");
+}
+
+#[test]
+fn duplicate_field_is_the_only_error() {
+    let errors = parse("struct P { x: int, x: int }\nvar p = 1;").expect_err("a duplicate field is rejected");
+    let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(messages, ["Duplicate field 'x'."]);
+}
+
+#[test]
+fn doc_comment_inside_an_expression_is_misplaced() {
+    let errors = parse("var x = [1, /// doc\n2];").expect_err("the doc comment is rejected");
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], ParserError::MisplacedDocComment { .. }), "got {:?}", errors[0]);
+}
+
+#[test]
+fn doc_comments_document_struct_fields() {
+    let statements = parse("struct P {\n    /// the x\n    x: int,\n    y,\n}").expect("field doc comments parse");
+    match &statements[0] {
+        Stmt::Struct { fields, .. } => {
+            assert_eq!(fields[0].doc.as_deref(), Some("the x"));
+            assert_eq!(fields[1].doc, None);
+        }
+        other => panic!("expected a struct, got {:?}", other),
+    }
+}
+
+#[test]
+fn doc_comment_after_the_last_field_is_misplaced() {
+    let errors = parse("struct P { x, /// doc\n}\nvar p = 1;").expect_err("the doc comment is rejected");
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], ParserError::MisplacedDocComment { .. }), "got {:?}", errors[0]);
+}
+
+#[test]
+fn doc_comment_inside_a_dict_is_the_only_error() {
+    let errors = parse("var d = {\n    /// a key\n    \"a\": 1,\n};\nprintln(d);").expect_err("the doc comment is rejected");
+    assert_eq!(errors.len(), 1, "got {:?}", errors);
+    assert!(matches!(errors[0], ParserError::MisplacedDocComment { .. }), "got {:?}", errors[0]);
+}
+
+#[test]
+fn every_syntax_error_is_reported() {
+    let source = "var a = ;\nfunc f( {\n    return 1;\n}\nvar b = 2 +;\nif x { var c = ; }\nvar ok = 1;";
+    let errors = parse(source).expect_err("the source is rejected");
+    let found: Vec<(String, usize)> = errors
+        .iter()
+        .map(|err| (err.to_string(), source[..err.span().start].lines().count()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("Expected expression.".to_string(), 1),
+            ("Expected parameter name.".to_string(), 2),
+            ("Expected expression.".to_string(), 5),
+            ("Expected expression.".to_string(), 6),
+        ]
+    );
+}
+
+#[test]
+fn statements_around_errors_still_parse() {
+    let (tokens, _) = Lexer::new("var a = 1;\nvar b = ;\nvar c = 3;").tokenize();
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert_eq!(errors.len(), 1);
+    let names: Vec<&str> = statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::VarDecl { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["a", "c"]);
+}
+
+#[test]
+fn expression_spans_cover_their_source_text() {
+    let source = "var total = price * rate - discount(items[0].code, 2);";
+    let statements = parse(source).expect("source parses");
+    let initializer = match &statements[0] {
+        Stmt::VarDecl { initializer, .. } => initializer,
+        other => panic!("expected a variable, got {:?}", other),
+    };
+    let text = |expr: &Expr| &source[expr.span().start..expr.span().end];
+    assert_eq!(text(initializer), "price * rate - discount(items[0].code, 2)");
+    match initializer {
+        Expr::Binary { left, right, .. } => {
+            assert_eq!(text(left), "price * rate");
+            assert_eq!(text(right), "discount(items[0].code, 2)");
+        }
+        other => panic!("expected a binary expression, got {:?}", other),
+    }
+}
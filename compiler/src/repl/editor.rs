@@ -0,0 +1,278 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+// most recent entries kept when loading the history file
+const HISTORY_LIMIT: usize = 1000;
+
+// reads input lines for the REPL
+// on a terminal, lines are edited in raw mode with cursor movement and
+// history recall; otherwise (pipes, dumb terminals) plain lines are read
+pub struct LineEditor {
+    history: Vec<String>,
+    path: Option<PathBuf>,
+    interactive: bool,
+}
+
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    ClearLine,
+    Interrupt,
+    EndOfInput,
+    Ignored,
+}
+
+impl LineEditor {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let mut history: Vec<String> = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        if history.len() > HISTORY_LIMIT {
+            history.drain(..history.len() - HISTORY_LIMIT);
+        }
+        let interactive = io::stdin().is_terminal()
+            && io::stdout().is_terminal()
+            && std::env::var("TERM").map_or(true, |term| term != "dumb");
+        Self {
+            history,
+            path,
+            interactive,
+        }
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    // records a line and appends it to the history file, if there is one
+    pub fn addHistory(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().map(String::as_str) == Some(line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if let Some(path) = &self.path {
+            // losing history is not worth interrupting the session for
+            if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    // None at end of input; Ctrl-C abandons the line and returns an empty one
+    pub fn readLine(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        if self.interactive {
+            if let Some(saved) = Self::enterRawMode() {
+                let result = self.readEdited(prompt);
+                Self::restoreMode(&saved);
+                return result;
+            }
+        }
+        print!("{}", prompt);
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end_matches(['\n', '\r']).to_string()))
+    }
+
+    fn readEdited(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let mut line: Vec<char> = Vec::new();
+        let mut cursor = 0;
+        // position in history while recalling; history.len() is the new line
+        let mut recalled = self.history.len();
+        let mut draft: Vec<char> = Vec::new();
+
+        Self::redraw(prompt, &line, cursor)?;
+        loop {
+            match Self::readKey()? {
+                Key::Char(ch) => {
+                    line.insert(cursor, ch);
+                    cursor += 1;
+                }
+                Key::Enter => {
+                    print!("\r\n");
+                    io::stdout().flush()?;
+                    return Ok(Some(line.into_iter().collect()));
+                }
+                Key::Backspace if cursor > 0 => {
+                    cursor -= 1;
+                    line.remove(cursor);
+                }
+                Key::Delete if cursor < line.len() => {
+                    line.remove(cursor);
+                }
+                Key::Left if cursor > 0 => cursor -= 1,
+                Key::Right if cursor < line.len() => cursor += 1,
+                Key::Home => cursor = 0,
+                Key::End => cursor = line.len(),
+                Key::ClearLine => {
+                    line.clear();
+                    cursor = 0;
+                }
+                Key::Up if recalled > 0 => {
+                    if recalled == self.history.len() {
+                        draft = line.clone();
+                    }
+                    recalled -= 1;
+                    line = self.history[recalled].chars().collect();
+                    cursor = line.len();
+                }
+                Key::Down if recalled < self.history.len() => {
+                    recalled += 1;
+                    line = match self.history.get(recalled) {
+                        Some(entry) => entry.chars().collect(),
+                        None => draft.clone(),
+                    };
+                    cursor = line.len();
+                }
+                Key::Interrupt => {
+                    print!("^C\r\n");
+                    io::stdout().flush()?;
+                    return Ok(Some(String::new()));
+                }
+                Key::EndOfInput if line.is_empty() => {
+                    print!("\r\n");
+                    io::stdout().flush()?;
+                    return Ok(None);
+                }
+                _ => {}
+            }
+            Self::redraw(prompt, &line, cursor)?;
+        }
+    }
+
+    fn redraw(prompt: &str, line: &[char], cursor: usize) -> io::Result<()> {
+        let text: String = line.iter().collect();
+        let mut out = io::stdout();
+        write!(out, "\r{}{}\x1b[K", prompt, text)?;
+        if cursor < line.len() {
+            write!(out, "\x1b[{}D", line.len() - cursor)?;
+        }
+        out.flush()
+    }
+
+    fn readKey() -> io::Result<Key> {
+        let byte = match Self::readByte()? {
+            Some(byte) => byte,
+            None => return Ok(Key::EndOfInput),
+        };
+        let key = match byte {
+            b'\r' | b'\n' => Key::Enter,
+            0x7f | 0x08 => Key::Backspace,
+            0x01 => Key::Home,
+            0x05 => Key::End,
+            0x02 => Key::Left,
+            0x06 => Key::Right,
+            0x10 => Key::Up,
+            0x0e => Key::Down,
+            0x15 => Key::ClearLine,
+            0x03 => Key::Interrupt,
+            0x04 => Key::EndOfInput,
+            0x1b => Self::readEscape()?,
+            byte if byte < 0x20 => Key::Ignored,
+            byte => Self::readChar(byte)?,
+        };
+        Ok(key)
+    }
+
+    // ESC [ sequences sent by cursor and editing keys
+    fn readEscape() -> io::Result<Key> {
+        if Self::readByte()? != Some(b'[') {
+            return Ok(Key::Ignored);
+        }
+        let key = match Self::readByte()? {
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            Some(digit @ b'0'..=b'9') => {
+                // ESC [ n ~, e.g. 3~ for Delete
+                let mut code = vec![digit];
+                while let Some(byte) = Self::readByte()? {
+                    if byte == b'~' {
+                        break;
+                    }
+                    code.push(byte);
+                }
+                match code.as_slice() {
+                    b"3" => Key::Delete,
+                    b"1" | b"7" => Key::Home,
+                    b"4" | b"8" => Key::End,
+                    _ => Key::Ignored,
+                }
+            }
+            _ => Key::Ignored,
+        };
+        Ok(key)
+    }
+
+    // decodes the rest of a UTF-8 sequence starting with `first`
+    fn readChar(first: u8) -> io::Result<Key> {
+        let width = match first {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        let mut bytes = vec![first];
+        for _ in 1..width {
+            match Self::readByte()? {
+                Some(byte) => bytes.push(byte),
+                None => break,
+            }
+        }
+        Ok(match std::str::from_utf8(&bytes).ok().and_then(|text| text.chars().next()) {
+            Some(ch) => Key::Char(ch),
+            None => Key::Ignored,
+        })
+    }
+
+    fn readByte() -> io::Result<Option<u8>> {
+        let mut byte = [0u8; 1];
+        match io::stdin().lock().read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    // the terminal is switched with stty so no platform bindings are needed;
+    // returns the previous settings, or None when stty is unavailable
+    fn enterRawMode() -> Option<String> {
+        let saved = Self::stty(&["-g"])?;
+        Self::stty(&["raw", "-echo"])?;
+        Some(saved.trim().to_string())
+    }
+
+    fn restoreMode(saved: &str) {
+        let _ = Self::stty(&[saved]);
+    }
+
+    fn stty(args: &[&str]) -> Option<String> {
+        let output = Command::new("stty")
+            .args(args)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if output.status.success() {
+            String::from_utf8(output.stdout).ok()
+        } else {
+            None
+        }
+    }
+}
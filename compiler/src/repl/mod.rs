@@ -0,0 +1,7 @@
+pub mod editor;
+pub mod repl;
+
+pub use repl::Repl;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,271 @@
+use std::env;
+use std::fs;
+use std::mem;
+use std::path::PathBuf;
+
+use super::editor::LineEditor;
+use crate::ast::Stmt;
+use crate::checker::TypeChecker;
+use crate::diagnostics::{Diagnostic, RenderMode, Renderer, SourceFile, SourceMap};
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+use crate::lexer::error::LexerError;
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::modules::Loader;
+use crate::parser::Parser;
+
+const PROMPT: &str = "rey> ";
+const CONTINUATION_PROMPT: &str = "...> ";
+const HISTORY_FILE: &str = ".rey_history";
+
+const HELP: &str = "\
+Enter statements or expressions; expression values are echoed.
+Input continues over several lines while braces, brackets, parentheses
+or a string are left open. A blank line submits it as it is.
+Imports are resolved relative to the working directory, or to the file
+for :load.
+
+  :ast <code>     show the syntax tree of <code>
+  :tokens <code>  show the tokens of <code>
+  :env            list global bindings
+  :load <file>    run a file in the current session
+  :history        show previous input
+  :reset          forget all bindings
+  :help           show this message
+  :quit           leave (Ctrl-D also works)";
+
+// interactive session; bindings persist across inputs until :reset
+pub struct Repl {
+    interpreter: Interpreter,
+    checker: TypeChecker,
+    editor: LineEditor,
+    mode: RenderMode,
+    // every entry and file run so far, so errors raised later in code they
+    // declared still point into them
+    sources: SourceMap,
+}
+
+impl Repl {
+    pub fn new(mode: RenderMode) -> Self {
+        let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        Self {
+            interpreter: Interpreter::new(),
+            checker: TypeChecker::new(),
+            editor: LineEditor::new(history),
+            mode,
+            sources: SourceMap::new(),
+        }
+    }
+
+    pub fn run(&mut self) {
+        println!("Rey REPL. Type :help for commands, :quit to leave.");
+        while let Some(input) = self.readInput() {
+            let trimmed = input.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if let Some(command) = trimmed.strip_prefix(':') {
+                if !self.runCommand(command) {
+                    break;
+                }
+                continue;
+            }
+            self.evaluate("<repl>", &input, false);
+        }
+    }
+
+    // reads one entry, continuing onto more lines while it is incomplete;
+    // None at end of input
+    fn readInput(&mut self) -> Option<String> {
+        let mut input = String::new();
+        loop {
+            let prompt = if input.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+            let line = match self.editor.readLine(prompt) {
+                Ok(Some(line)) => line,
+                Ok(None) | Err(_) => {
+                    return if input.is_empty() { None } else { Some(input) };
+                }
+            };
+            self.editor.addHistory(&line);
+            if !input.is_empty() && line.trim().is_empty() {
+                return Some(input);
+            }
+            if !input.is_empty() {
+                input.push('\n');
+            }
+            input.push_str(&line);
+            if input.trim_start().starts_with(':') || !Self::is_incomplete(&input) {
+                return Some(input);
+            }
+        }
+    }
+
+    // true while a string, block comment or any bracket pair is still
+    // open, or a doc comment still waits for its declaration
+    pub fn is_incomplete(source: &str) -> bool {
+        let (tokens, errors) = Lexer::new(source).tokenize();
+        if errors.iter().any(|err| {
+            matches!(
+                err,
+                LexerError::UnterminatedString { .. }
+                    | LexerError::UnterminatedInterpolation { .. }
+                    | LexerError::UnterminatedComment { .. }
+            )
+        }) {
+            return true;
+        }
+        if tokens.len() > 1 && matches!(tokens[tokens.len() - 2].kind, TokenKind::DocComment(_)) {
+            return true;
+        }
+        let mut depth = 0i32;
+        for token in &tokens {
+            match token.kind {
+                TokenKind::LeftBrace | TokenKind::LeftParen | TokenKind::LeftBracket => depth += 1,
+                TokenKind::RightBrace | TokenKind::RightParen | TokenKind::RightBracket => depth -= 1,
+                _ => {}
+            }
+        }
+        depth > 0
+    }
+
+    // returns false when the session should end
+    fn runCommand(&mut self, command: &str) -> bool {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+        match name {
+            "quit" | "q" | "exit" => return false,
+            "help" | "h" => println!("{}", HELP),
+            "tokens" => {
+                if let Some(tokens) = self.lex("<repl>", argument, 0) {
+                    for token in tokens {
+                        println!("{:?}", token);
+                    }
+                }
+            }
+            "ast" => {
+                if let Some(statements) = self.parse("<repl>", argument, 0) {
+                    for stmt in statements {
+                        println!("{:#?}", stmt);
+                    }
+                }
+            }
+            "env" => {
+                for (name, value) in self.interpreter.globals() {
+                    println!("{} = {}", name, Self::inspect(&value));
+                }
+            }
+            "load" => match fs::read_to_string(argument) {
+                Ok(source) => self.evaluate(argument, &source, true),
+                Err(err) => eprintln!("Cannot read '{}': {}", argument, err),
+            },
+            "history" => {
+                for (i, line) in self.editor.history().iter().enumerate() {
+                    println!("{:>5}  {}", i + 1, line);
+                }
+            }
+            "reset" => {
+                self.interpreter = Interpreter::new();
+                self.checker = TypeChecker::new();
+                self.sources = SourceMap::new();
+                println!("Session reset.");
+            }
+            _ => eprintln!("Unknown command ':{}'. Type :help for a list.", name),
+        }
+        true
+    }
+
+    // runs one entry through the same stages as a file, echoing the value
+    // of a trailing expression; imports are resolved relative to the file
+    // when the entry was read from one, otherwise the working directory
+    fn evaluate(&mut self, name: &str, source: &str, from_file: bool) {
+        // a lone expression may leave off its semicolon
+        let completed = format!("{};", source.trim_end());
+        let source = if !Self::parses(source) && Self::parses(&completed) {
+            completed
+        } else {
+            source.to_string()
+        };
+        let base = self.sources.next_base();
+        let statements = match self.parse(name, &source, base) {
+            Some(statements) => statements,
+            None => return,
+        };
+        self.sources.add(SourceFile::new(name, &source).with_base(base));
+
+        if let Err(errors) = self.checker.check(&statements) {
+            for err in errors {
+                self.report(err.to_diagnostic());
+            }
+            return;
+        }
+
+        let sources = mem::replace(&mut self.sources, SourceMap::new());
+        let (graph, diagnostics) = Loader::with_sources(sources).load_parsed(name, statements, from_file);
+        let result = if diagnostics.is_empty() {
+            self.interpreter.interpret_line(&graph).map_err(|err| vec![err.to_diagnostic()])
+        } else {
+            Err(diagnostics)
+        };
+        self.sources = graph.sources;
+        match result {
+            Ok(Some(Value::Null)) | Ok(None) => {}
+            Ok(Some(value)) => println!("{}", Self::inspect(&value)),
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    self.report(diagnostic);
+                }
+            }
+        }
+    }
+
+    fn parses(source: &str) -> bool {
+        let (tokens, errors) = Lexer::new(source).tokenize();
+        errors.is_empty() && Parser::new(tokens).parse().1.is_empty()
+    }
+
+    // spans start at `base`, where the source will sit in the session
+    fn lex(&self, name: &str, source: &str, base: usize) -> Option<Vec<Token>> {
+        let (tokens, errors) = Lexer::with_offset(source, base).tokenize();
+        if errors.is_empty() {
+            return Some(tokens);
+        }
+        for err in errors {
+            self.reportAt(name, source, base, err.to_diagnostic());
+        }
+        None
+    }
+
+    fn parse(&self, name: &str, source: &str, base: usize) -> Option<Vec<Stmt>> {
+        let tokens = self.lex(name, source, base)?;
+        let (statements, errors) = Parser::new(tokens).parse();
+        if errors.is_empty() {
+            return Some(statements);
+        }
+        for err in errors {
+            self.reportAt(name, source, base, err.to_diagnostic());
+        }
+        None
+    }
+
+    // for a source not yet added to the session
+    fn reportAt(&self, name: &str, source: &str, base: usize, diagnostic: Diagnostic) {
+        let file = SourceFile::new(name, source).with_base(base);
+        eprint!("{}", Renderer::new(&file, self.mode).render(&diagnostic));
+    }
+
+    // renders against whichever of the session's files the diagnostic is in
+    fn report(&self, diagnostic: Diagnostic) {
+        let offset = diagnostic.primary_span().map_or(0, |span| span.start);
+        eprint!("{}", Renderer::new(self.sources.find(offset), self.mode).render(&diagnostic));
+    }
+
+    // echoed strings are quoted so they can be told apart from other values
+    fn inspect(value: &Value) -> String {
+        match value {
+            Value::String(s) => format!("{:?}", s),
+            other => other.to_string(),
+        }
+    }
+}
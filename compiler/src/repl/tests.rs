@@ -0,0 +1,22 @@
+use super::Repl;
+
+#[test]
+fn open_groups_continue_the_input() {
+    for source in ["func f() {", "var a = [1,", "println(1,", "if x { while y {\n}"] {
+        assert!(Repl::is_incomplete(source), "{:?}", source);
+    }
+}
+
+#[test]
+fn open_strings_and_comments_continue_the_input() {
+    for source in ["var s = \"abc", "var s = \"\"\"\nline", "var s = \"{x", "/* still /* nested */", "/// doc"] {
+        assert!(Repl::is_incomplete(source), "{:?}", source);
+    }
+}
+
+#[test]
+fn complete_input_is_submitted() {
+    for source in ["var a = 1;", "1 + 2", "func f() {\n}", "var s = \"{x}\";", "// just a comment", "}"] {
+        assert!(!Repl::is_incomplete(source), "{:?}", source);
+    }
+}
@@ -0,0 +1,61 @@
+use std::rc::Rc;
+
+use super::function::FunctionProto;
+use super::opcode::OpCode;
+use crate::bigint::BigInt;
+use crate::lexer::span::Span;
+
+// values known at compile time, referenced by index from the code
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
+    String(String),
+    Function(Rc<FunctionProto>),
+    // a struct declaration, from which `OpCode::Struct` makes its type
+    Struct { name: String, fields: Vec<String> },
+}
+
+// compiled code of one function
+// `spans` runs parallel to `code` so errors can point back at the source;
+// an instruction's errors point at its opcode's span unless its operands
+// note otherwise
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chunk {
+    pub code: Vec<u8>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<Constant>,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, byte: u8, span: Span) {
+        self.code.push(byte);
+        self.spans.push(span);
+    }
+
+    pub fn writeOp(&mut self, op: OpCode, span: Span) {
+        self.write(op as u8, span);
+    }
+
+    pub fn writeU16(&mut self, value: u16, span: Span) {
+        for byte in value.to_le_bytes() {
+            self.write(byte, span);
+        }
+    }
+
+    pub fn readU16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.code[offset], self.code[offset + 1]])
+    }
+
+    // overwrites the u16 operand at `offset`, used to patch jump targets
+    pub fn patchU16(&mut self, offset: usize, value: u16) {
+        let [low, high] = value.to_le_bytes();
+        self.code[offset] = low;
+        self.code[offset + 1] = high;
+    }
+}
@@ -0,0 +1,1153 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::chunk::{Chunk, Constant};
+use super::error::CompileError;
+use super::function::{Capture, FunctionProto};
+use super::opcode::OpCode;
+use crate::ast::{Expr, Literal, Parameter, Stmt, Target};
+use crate::interpreter::std::StdLib;
+use crate::lexer::span::Span;
+use crate::lexer::TokenKind;
+use crate::modules::{moduleName, ModuleGraph};
+
+// a compiled program: the top-level code plus the names of the globals
+// it refers to, in global index order
+// each imported file keeps its globals apart, so a name can appear more
+// than once in `globals`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub script: Rc<FunctionProto>,
+    pub globals: Vec<String>,
+    // the imported files, in the order they run
+    pub modules: Vec<ModuleInfo>,
+    // the global the program's own file keeps `main` in, if it uses the name
+    pub main: Option<u16>,
+}
+
+// what the VM needs to build the value `import "path"` binds
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleInfo {
+    pub name: String,
+    // each export and the global holding it
+    pub exports: Vec<(String, u16)>,
+}
+
+// where a function keeps one of its own variables
+#[derive(Debug, Clone, Copy)]
+enum Storage {
+    Slot(u16),
+    Cell(u16),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Variable {
+    Global(u16),
+    Slot(u16),
+    Cell(u16),
+    Upvalue(u16),
+}
+
+struct LoopState {
+    // where `continue` jumps to
+    start: usize,
+    // jump operands to patch with the loop exit
+    breaks: Vec<usize>,
+}
+
+// the function currently being compiled
+struct FunctionState {
+    name: String,
+    arity: usize,
+    span: Span,
+    chunk: Chunk,
+    // variables are function-scoped, as in the tree walker: every name
+    // declared anywhere in the body is known from the start
+    locals: HashMap<String, Storage>,
+    local_count: usize,
+    // names of the variables in cells, by cell index
+    cells: Vec<String>,
+    captures: Vec<Capture>,
+    upvalues: HashMap<String, u16>,
+    loops: Vec<LoopState>,
+    // top-level code keeps its variables in globals
+    script: bool,
+}
+
+impl FunctionState {
+    fn new(name: &str, arity: usize, span: Span, script: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            arity,
+            span,
+            chunk: Chunk::new(),
+            locals: HashMap::new(),
+            local_count: arity,
+            cells: Vec::new(),
+            captures: Vec::new(),
+            upvalues: HashMap::new(),
+            loops: Vec::new(),
+            script,
+        }
+    }
+}
+
+// compiles a syntax tree into bytecode for the VM
+pub struct Compiler {
+    functions: Vec<FunctionState>,
+    globals: Vec<String>,
+    // keyed by the module declaring the global and its name
+    global_slots: HashMap<(usize, String), u16>,
+    modules: Vec<ModuleInfo>,
+    // the module being compiled and the modules its imports refer to
+    module: usize,
+    imports: HashMap<String, usize>,
+    errors: Vec<CompileError>,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self {
+            functions: Vec::new(),
+            globals: Vec::new(),
+            global_slots: HashMap::new(),
+            modules: Vec::new(),
+            module: 0,
+            imports: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    // the top-level code of each imported file becomes a function that the
+    // script calls first, in the order the files have to run
+    pub fn compile(mut self, graph: &ModuleGraph) -> Result<Program, Vec<CompileError>> {
+        let span = Span::new(0, 0);
+        let (root, dependencies) = graph.modules.split_last().expect("a program has at least its own file");
+        self.functions.push(FunctionState::new("<script>", 0, span, true));
+        for (index, module) in dependencies.iter().enumerate() {
+            self.module = index;
+            self.imports = module.imports.clone();
+            let start = module.statements.first().map_or(span, Stmt::span);
+            self.functions.push(FunctionState::new(&format!("<module {}>", module.name), 0, start, true));
+            self.topLevel(&module.statements, span);
+            let proto = self.finishFunction();
+            let constant = self.constant(Constant::Function(proto), span);
+            self.emitOp(OpCode::Closure, span);
+            self.emitU16(constant, span);
+            self.emitOp(OpCode::Call, span);
+            self.emitByte(0, span);
+            self.emitOp(OpCode::Pop, span);
+
+            let exports = module.exports.iter().map(|name| (name.clone(), self.global(name, span))).collect();
+            self.modules.push(ModuleInfo {
+                name: module.name.clone(),
+                exports,
+            });
+        }
+        self.module = dependencies.len();
+        self.imports = root.imports.clone();
+        self.topLevel(&root.statements, span);
+        let script = self.finishFunction();
+        let main = self.global_slots.get(&(self.module, "main".to_string())).copied();
+
+        if self.errors.is_empty() {
+            Ok(Program {
+                script,
+                globals: self.globals,
+                modules: self.modules,
+                main,
+            })
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn topLevel(&mut self, statements: &[Stmt], span: Span) {
+        self.statements(statements);
+        // the implicit return belongs to the end of the code
+        let end = statements.last().map(Stmt::span).unwrap_or(span);
+        self.emitOp(OpCode::Null, end);
+        self.emitOp(OpCode::Return, end);
+    }
+
+    fn function(&mut self, name: &str, params: &[Parameter], body: &[Stmt], span: Span) -> Rc<FunctionProto> {
+        let mut state = FunctionState::new(name, params.len(), span, false);
+        let captured = capturedNames(body);
+        let uncertain = uncertainNames(params, body);
+        let mut prologue = Vec::new();
+
+        // arguments arrive in the first slots; captured ones are then
+        // copied into cells so closures can share them
+        for (slot, param) in params.iter().enumerate() {
+            if captured.contains(&param.name) {
+                let cell = state.cells.len() as u16;
+                state.cells.push(param.name.clone());
+                state.locals.insert(param.name.clone(), Storage::Cell(cell));
+                prologue.push((slot as u16, cell));
+            } else {
+                state.locals.insert(param.name.clone(), Storage::Slot(slot as u16));
+            }
+        }
+        for name in declaredNames(body) {
+            if state.locals.contains_key(&name) {
+                continue;
+            }
+            let storage = if captured.contains(&name) || uncertain.contains(&name) {
+                state.cells.push(name.clone());
+                Storage::Cell(state.cells.len() as u16 - 1)
+            } else {
+                state.local_count += 1;
+                Storage::Slot(state.local_count as u16 - 1)
+            };
+            state.locals.insert(name, storage);
+        }
+        if state.local_count > u16::MAX as usize || state.cells.len() > u16::MAX as usize {
+            self.errors.push(CompileError::TooMany {
+                what: "local variables",
+                span,
+            });
+        }
+
+        self.functions.push(state);
+        for (slot, cell) in prologue {
+            self.emitOp(OpCode::GetLocal, span);
+            self.emitU16(slot, span);
+            self.emitOp(OpCode::DefineCell, span);
+            self.emitU16(cell, span);
+        }
+        self.statements(body);
+        let end = body.last().map(Stmt::span).unwrap_or(span);
+        self.emitOp(OpCode::Null, end);
+        self.emitOp(OpCode::Return, end);
+        self.finishFunction()
+    }
+
+    fn finishFunction(&mut self) -> Rc<FunctionProto> {
+        let state = self.functions.pop().expect("a function is being compiled");
+        let mut upvalue_names = vec![String::new(); state.captures.len()];
+        for (name, index) in state.upvalues {
+            upvalue_names[index as usize] = name;
+        }
+        Rc::new(FunctionProto {
+            name: state.name,
+            arity: state.arity,
+            local_count: state.local_count,
+            cell_names: state.cells,
+            captures: state.captures,
+            upvalue_names,
+            chunk: state.chunk,
+            span: state.span,
+        })
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl {
+                name,
+                initializer,
+                span,
+                ..
+            } => {
+                self.expression(initializer);
+                self.define(name, *span);
+            }
+            Stmt::FuncDecl {
+                name,
+                params,
+                body,
+                span,
+                ..
+            } => {
+                let proto = self.function(name, params, body, *span);
+                let index = self.constant(Constant::Function(proto), *span);
+                self.emitOp(OpCode::Closure, *span);
+                self.emitU16(index, *span);
+                self.define(name, *span);
+            }
+            Stmt::Struct { name, fields, span, .. } => {
+                let fields = fields.iter().map(|field| field.name.clone()).collect();
+                let index = self.constant(
+                    Constant::Struct {
+                        name: name.clone(),
+                        fields,
+                    },
+                    *span,
+                );
+                self.emitOp(OpCode::Struct, *span);
+                self.emitU16(index, *span);
+                self.define(name, *span);
+            }
+            // the type stays on the stack while each method is added to it
+            Stmt::Impl { name, methods, span } => {
+                self.load(name, *span);
+                for method in methods {
+                    if let Stmt::FuncDecl {
+                        name,
+                        params,
+                        body,
+                        span,
+                        ..
+                    } = method
+                    {
+                        let proto = self.function(name, params, body, *span);
+                        let index = self.constant(Constant::Function(proto), *span);
+                        self.emitOp(OpCode::Closure, *span);
+                        self.emitU16(index, *span);
+                        let name = self.constant(Constant::String(name.clone()), *span);
+                        self.emitOp(OpCode::Method, *span);
+                        self.emitU16(name, *span);
+                    }
+                }
+                self.emitOp(OpCode::Pop, *span);
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                span,
+            } => {
+                self.expression(condition);
+                let otherwise = self.emitJump(OpCode::JumpIfFalse, *span);
+                self.statements(then_branch);
+                match else_branch {
+                    Some(else_branch) => {
+                        let end = self.emitJump(OpCode::Jump, *span);
+                        self.patchJump(otherwise, *span);
+                        self.statements(else_branch);
+                        self.patchJump(end, *span);
+                    }
+                    None => self.patchJump(otherwise, *span),
+                }
+            }
+            Stmt::While { condition, body, span } => {
+                let start = self.current().chunk.code.len();
+                self.expression(condition);
+                let exit = self.emitJump(OpCode::JumpIfFalse, *span);
+                self.loopBody(start, body, *span);
+                self.patchJump(exit, *span);
+                self.finishLoop(*span);
+            }
+            Stmt::For {
+                variable,
+                start,
+                end,
+                body,
+                span,
+            } => {
+                // the bounds are evaluated once, into hidden slots
+                let counter = self.hiddenSlot(*span);
+                let limit = self.hiddenSlot(*span);
+                self.expression(start);
+                self.emitOp(OpCode::RangeBound, start.span());
+                self.emitByte(0, start.span());
+                self.setSlot(counter, *span);
+                self.expression(end);
+                self.emitOp(OpCode::RangeBound, end.span());
+                self.emitByte(1, end.span());
+                self.setSlot(limit, *span);
+
+                let top = self.current().chunk.code.len();
+                self.emitOp(OpCode::RangeNext, *span);
+                self.emitU16(counter, *span);
+                self.emitU16(limit, *span);
+                let exit = self.emitPlaceholder(*span);
+                self.define(variable, *span);
+                self.loopBody(top, body, *span);
+                self.patchJump(exit, *span);
+                self.finishLoop(*span);
+            }
+            Stmt::ForEach {
+                variable,
+                iterable,
+                body,
+                span,
+            } => {
+                let items = self.hiddenSlot(*span);
+                let position = self.hiddenSlot(*span);
+                self.expression(iterable);
+                self.emitOp(OpCode::IterSnapshot, iterable.span());
+                self.setSlot(items, *span);
+                let zero = self.constant(Constant::Int(0), *span);
+                self.emitOp(OpCode::Constant, *span);
+                self.emitU16(zero, *span);
+                self.setSlot(position, *span);
+
+                let top = self.current().chunk.code.len();
+                self.emitOp(OpCode::ForEachNext, *span);
+                self.emitU16(items, *span);
+                self.emitU16(position, *span);
+                let exit = self.emitPlaceholder(*span);
+                self.define(variable, *span);
+                self.loopBody(top, body, *span);
+                self.patchJump(exit, *span);
+                self.finishLoop(*span);
+            }
+            Stmt::Break { span } => {
+                if self.current().loops.is_empty() {
+                    self.errors.push(CompileError::BreakOutsideLoop { span: *span });
+                    return;
+                }
+                let jump = self.emitJump(OpCode::Jump, *span);
+                if let Some(state) = self.current().loops.last_mut() {
+                    state.breaks.push(jump);
+                }
+            }
+            Stmt::Continue { span } => {
+                let start = match self.current().loops.last() {
+                    Some(state) => state.start,
+                    None => {
+                        self.errors.push(CompileError::ContinueOutsideLoop { span: *span });
+                        return;
+                    }
+                };
+                self.emitJumpTo(start, *span);
+            }
+            Stmt::Return { value, span } => {
+                self.expression(value);
+                self.emitOp(OpCode::Return, *span);
+            }
+            Stmt::ExprStmt(expr) => {
+                self.expression(expr);
+                self.emitOp(OpCode::Pop, expr.span());
+            }
+            // the loader has resolved every import of the files it compiles
+            Stmt::Import { path, names, span } => {
+                let module = self.imports[path];
+                match names {
+                    // the exports' values when the import runs, like the tree walker
+                    Some(names) => {
+                        for imported in names {
+                            let global = self.globalIn(module, &imported.name, imported.span);
+                            self.emitOp(OpCode::GetGlobal, imported.span);
+                            self.emitU16(global, imported.span);
+                            self.define(&imported.name, imported.span);
+                        }
+                    }
+                    None => {
+                        let index = self.operand(module, "imported files", *span);
+                        self.emitOp(OpCode::Module, *span);
+                        self.emitU16(index, *span);
+                        if let Some(name) = moduleName(path) {
+                            self.define(&name, *span);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // compiles a loop body that jumps back to `start` when it finishes
+    fn loopBody(&mut self, start: usize, body: &[Stmt], span: Span) {
+        self.current().loops.push(LoopState {
+            start,
+            breaks: Vec::new(),
+        });
+        self.statements(body);
+        self.emitJumpTo(start, span);
+    }
+
+    // points every `break` of the innermost loop at the current position
+    fn finishLoop(&mut self, span: Span) {
+        if let Some(state) = self.current().loops.pop() {
+            for jump in state.breaks {
+                self.patchJump(jump, span);
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Literal { value, span } => match value {
+                Literal::Int(n) => {
+                    let index = self.constant(Constant::Int(*n), *span);
+                    self.emitOp(OpCode::Constant, *span);
+                    self.emitU16(index, *span);
+                }
+                Literal::BigInt(n) => {
+                    let index = self.constant(Constant::BigInt(n.clone()), *span);
+                    self.emitOp(OpCode::Constant, *span);
+                    self.emitU16(index, *span);
+                }
+                Literal::Float(n) => {
+                    let index = self.constant(Constant::Float(*n), *span);
+                    self.emitOp(OpCode::Constant, *span);
+                    self.emitU16(index, *span);
+                }
+                Literal::String(s) => {
+                    let index = self.constant(Constant::String(s.clone()), *span);
+                    self.emitOp(OpCode::Constant, *span);
+                    self.emitU16(index, *span);
+                }
+                Literal::Bool(true) => self.emitOp(OpCode::True, *span),
+                Literal::Bool(false) => self.emitOp(OpCode::False, *span),
+                Literal::Null => self.emitOp(OpCode::Null, *span),
+            },
+            Expr::Variable { name, span } => self.load(name, *span),
+            Expr::Assign { target, op, value, span } => {
+                self.targetOperands(target);
+                if let Some(op) = op {
+                    self.loadTarget(target);
+                    self.expression(value);
+                    self.emitOp(binaryOp(op), *span);
+                } else {
+                    self.expression(value);
+                }
+                self.storeTarget(target);
+            }
+            // a postfix update keeps a copy of the old value below the
+            // target's operands as its result
+            Expr::Update { target, op, prefix, span } => {
+                let operands = self.targetOperands(target);
+                self.loadTarget(target);
+                if !prefix {
+                    self.emitOp(OpCode::Dup, *span);
+                    if operands > 0 {
+                        self.emitOp(OpCode::Bury, *span);
+                        self.emitByte(operands + 1, *span);
+                    }
+                }
+                let one = self.constant(Constant::Int(1), *span);
+                self.emitOp(OpCode::Constant, *span);
+                self.emitU16(one, *span);
+                let step = if *op == TokenKind::PlusPlus { OpCode::Add } else { OpCode::Subtract };
+                self.emitOp(step, *span);
+                self.storeTarget(target);
+                if !prefix {
+                    self.emitOp(OpCode::Pop, *span);
+                }
+            }
+            Expr::Binary {
+                left,
+                op: op @ (TokenKind::AndAnd | TokenKind::OrOr),
+                right,
+                ..
+            } => {
+                let (jump, which) = if *op == TokenKind::AndAnd { (OpCode::And, 0) } else { (OpCode::Or, 1) };
+                self.expression(left);
+                let decided = self.emitJump(jump, left.span());
+                self.expression(right);
+                self.emitOp(OpCode::CheckBool, right.span());
+                self.emitByte(which, right.span());
+                self.patchJump(decided, right.span());
+            }
+            Expr::Binary { left, op, right, span } => {
+                self.expression(left);
+                self.expression(right);
+                self.emitOp(binaryOp(op), *span);
+            }
+            Expr::Unary { op, right, span } => {
+                self.expression(right);
+                let op = match op {
+                    TokenKind::Minus => OpCode::Negate,
+                    TokenKind::Tilde => OpCode::Complement,
+                    _ => OpCode::Not,
+                };
+                self.emitOp(op, *span);
+            }
+            Expr::Call { callee, args, span } => match callee.as_ref() {
+                Expr::Get { object, name, .. } => {
+                    self.expression(object);
+                    let argc = self.arguments(args, *span);
+                    let name = self.constant(Constant::String(name.clone()), *span);
+                    self.emitOp(OpCode::Invoke, *span);
+                    self.emitU16(name, callee.span());
+                    self.emitByte(argc, callee.span());
+                }
+                // built-ins win over any binding with the same name, as in the tree walker
+                Expr::Variable { name, .. } if StdLib::is_builtin(name) => {
+                    let argc = self.arguments(args, *span);
+                    let name = self.constant(Constant::String(name.clone()), *span);
+                    self.emitOp(OpCode::CallBuiltin, *span);
+                    self.emitU16(name, *span);
+                    self.emitByte(argc, *span);
+                }
+                _ => {
+                    self.expression(callee);
+                    let argc = self.arguments(args, *span);
+                    self.emitOp(OpCode::Call, *span);
+                    self.emitByte(argc, callee.span());
+                }
+            },
+            Expr::Get { object, name, span } => {
+                self.expression(object);
+                let name = self.constant(Constant::String(name.clone()), *span);
+                self.emitOp(OpCode::GetProperty, *span);
+                self.emitU16(name, *span);
+            }
+            Expr::Array { elements, span } => {
+                for element in elements {
+                    self.expression(element);
+                }
+                let count = self.operand(elements.len(), "array elements", *span);
+                self.emitOp(OpCode::Array, *span);
+                self.emitU16(count, *span);
+            }
+            Expr::Lambda { params, body, span, .. } => {
+                let proto = self.function("<lambda>", params, body, *span);
+                let index = self.constant(Constant::Function(proto), *span);
+                self.emitOp(OpCode::Closure, *span);
+                self.emitU16(index, *span);
+            }
+            Expr::Interpolation { parts, span } => {
+                for part in parts {
+                    self.expression(part);
+                }
+                let count = self.operand(parts.len(), "string parts", *span);
+                self.emitOp(OpCode::Interpolate, *span);
+                self.emitU16(count, *span);
+            }
+            Expr::Dict { entries, span } => {
+                // each key is checked before its value runs, as in the tree walker
+                for (key, value) in entries {
+                    self.expression(key);
+                    self.emitOp(OpCode::CheckKey, key.span());
+                    self.expression(value);
+                }
+                let count = self.operand(entries.len(), "dictionary entries", *span);
+                self.emitOp(OpCode::Dict, *span);
+                self.emitU16(count, *span);
+            }
+            Expr::Index { object, index, .. } => {
+                self.expression(object);
+                self.expression(index);
+                self.checkIndexable(object, 1);
+                self.emitOp(OpCode::Index, index.span());
+            }
+        }
+    }
+
+    // pushes the object of a field target, or the object and index of an
+    // element target, and returns how many values that is
+    fn targetOperands(&mut self, target: &Target) -> u8 {
+        match target {
+            Target::Variable { .. } => 0,
+            Target::Index { object, index, .. } => {
+                self.expression(object);
+                self.expression(index);
+                2
+            }
+            Target::Field { object, .. } => {
+                self.expression(object);
+                1
+            }
+        }
+    }
+
+    // pushes the target's current value, keeping its operands below
+    fn loadTarget(&mut self, target: &Target) {
+        match target {
+            Target::Variable { name, span } => self.load(name, *span),
+            Target::Index { object, index, .. } => {
+                self.emitOp(OpCode::Dup2, index.span());
+                self.checkIndexable(object, 1);
+                self.emitOp(OpCode::Index, index.span());
+            }
+            Target::Field { name, span, .. } => {
+                self.emitOp(OpCode::Dup, *span);
+                let name = self.constant(Constant::String(name.clone()), *span);
+                self.emitOp(OpCode::GetProperty, *span);
+                self.emitU16(name, *span);
+            }
+        }
+    }
+
+    // stores the value on top of the stack into the target, consuming its
+    // operands and leaving the value
+    fn storeTarget(&mut self, target: &Target) {
+        match target {
+            Target::Variable { name, span } => self.assign(name, *span),
+            Target::Index { object, index, .. } => {
+                self.checkIndexable(object, 2);
+                self.emitOp(OpCode::SetIndex, index.span());
+            }
+            Target::Field { name, span, .. } => {
+                let name = self.constant(Constant::String(name.clone()), *span);
+                self.emitOp(OpCode::SetProperty, *span);
+                self.emitU16(name, *span);
+            }
+        }
+    }
+
+    // errors pointing at `object` unless the value `depth` below the top,
+    // which it evaluated to, can be indexed
+    fn checkIndexable(&mut self, object: &Expr, depth: u8) {
+        self.emitOp(OpCode::CheckIndexable, object.span());
+        self.emitByte(depth, object.span());
+    }
+
+    fn arguments(&mut self, args: &[Expr], span: Span) -> u8 {
+        for arg in args {
+            self.expression(arg);
+        }
+        if args.len() > u8::MAX as usize {
+            self.errors.push(CompileError::TooMany {
+                what: "arguments",
+                span,
+            });
+        }
+        args.len() as u8
+    }
+
+    // stores the value on top of the stack into a declared name and pops it
+    fn define(&mut self, name: &str, span: Span) {
+        match self.resolve(name, span) {
+            Variable::Global(i) => {
+                self.emitOp(OpCode::DefineGlobal, span);
+                self.emitU16(i, span);
+            }
+            Variable::Cell(i) => {
+                self.emitOp(OpCode::DefineCell, span);
+                self.emitU16(i, span);
+            }
+            _ => {
+                self.assign(name, span);
+                self.emitOp(OpCode::Pop, span);
+            }
+        }
+    }
+
+    // stores the value on top of the stack into `name`, leaving it there
+    fn load(&mut self, name: &str, span: Span) {
+        let (op, index) = match self.resolve(name, span) {
+            Variable::Global(i) => (OpCode::GetGlobal, i),
+            Variable::Slot(i) => (OpCode::GetLocal, i),
+            Variable::Cell(i) => (OpCode::GetCell, i),
+            Variable::Upvalue(i) => (OpCode::GetUpvalue, i),
+        };
+        self.emitOp(op, span);
+        self.emitU16(index, span);
+    }
+
+    fn assign(&mut self, name: &str, span: Span) {
+        let (op, index) = match self.resolve(name, span) {
+            Variable::Global(i) => (OpCode::SetGlobal, i),
+            Variable::Slot(i) => (OpCode::SetLocal, i),
+            Variable::Cell(i) => (OpCode::SetCell, i),
+            Variable::Upvalue(i) => (OpCode::SetUpvalue, i),
+        };
+        self.emitOp(op, span);
+        self.emitU16(index, span);
+    }
+
+    fn setSlot(&mut self, slot: u16, span: Span) {
+        self.emitOp(OpCode::SetLocal, span);
+        self.emitU16(slot, span);
+        self.emitOp(OpCode::Pop, span);
+    }
+
+    // a stack slot no source name refers to, for loop bookkeeping
+    fn hiddenSlot(&mut self, span: Span) -> u16 {
+        let state = self.current();
+        state.local_count += 1;
+        let slot = state.local_count - 1;
+        self.operand(slot, "local variables", span)
+    }
+
+    fn resolve(&mut self, name: &str, span: Span) -> Variable {
+        let depth = self.functions.len() - 1;
+        match self.resolveIn(depth, name, span) {
+            Some(variable) => variable,
+            None => Variable::Global(self.global(name, span)),
+        }
+    }
+
+    // looks `name` up in the function at `depth`, capturing it from the
+    // enclosing functions if needed; None means it is a global
+    fn resolveIn(&mut self, depth: usize, name: &str, span: Span) -> Option<Variable> {
+        let state = &self.functions[depth];
+        if state.script {
+            return None;
+        }
+        match state.locals.get(name) {
+            Some(Storage::Slot(i)) => return Some(Variable::Slot(*i)),
+            Some(Storage::Cell(i)) => return Some(Variable::Cell(*i)),
+            None => {}
+        }
+        if let Some(&i) = state.upvalues.get(name) {
+            return Some(Variable::Upvalue(i));
+        }
+
+        let capture = match self.resolveIn(depth - 1, name, span)? {
+            Variable::Cell(i) => Capture::Cell(i),
+            Variable::Upvalue(i) => Capture::Upvalue(i),
+            // capturedNames puts every captured local in a cell
+            Variable::Slot(_) | Variable::Global(_) => return None,
+        };
+        let index = self.operand(self.functions[depth].captures.len(), "captured variables", span);
+        let state = &mut self.functions[depth];
+        state.captures.push(capture);
+        state.upvalues.insert(name.to_string(), index);
+        Some(Variable::Upvalue(index))
+    }
+
+    fn global(&mut self, name: &str, span: Span) -> u16 {
+        self.globalIn(self.module, name, span)
+    }
+
+    fn globalIn(&mut self, module: usize, name: &str, span: Span) -> u16 {
+        let key = (module, name.to_string());
+        if let Some(&index) = self.global_slots.get(&key) {
+            return index;
+        }
+        let index = self.operand(self.globals.len(), "global variables", span);
+        self.globals.push(name.to_string());
+        self.global_slots.insert(key, index);
+        index
+    }
+
+    fn current(&mut self) -> &mut FunctionState {
+        self.functions.last_mut().expect("a function is being compiled")
+    }
+
+    fn constant(&mut self, constant: Constant, span: Span) -> u16 {
+        let constants = &mut self.current().chunk.constants;
+        // names and small numbers repeat a lot, so reuse identical entries
+        let index = match constants.iter().position(|existing| match (existing, &constant) {
+            (Constant::Int(a), Constant::Int(b)) => a == b,
+            (Constant::BigInt(a), Constant::BigInt(b)) => a == b,
+            (Constant::Float(a), Constant::Float(b)) => a.to_bits() == b.to_bits(),
+            (Constant::String(a), Constant::String(b)) => a == b,
+            _ => false,
+        }) {
+            Some(index) => index,
+            None => {
+                constants.push(constant);
+                constants.len() - 1
+            }
+        };
+        self.operand(index, "constants", span)
+    }
+
+    fn operand(&mut self, value: usize, what: &'static str, span: Span) -> u16 {
+        if value > u16::MAX as usize {
+            self.errors.push(CompileError::TooMany { what, span });
+        }
+        value as u16
+    }
+
+    fn emitOp(&mut self, op: OpCode, span: Span) {
+        self.current().chunk.writeOp(op, span);
+    }
+
+    fn emitByte(&mut self, byte: u8, span: Span) {
+        self.current().chunk.write(byte, span);
+    }
+
+    fn emitU16(&mut self, value: u16, span: Span) {
+        self.current().chunk.writeU16(value, span);
+    }
+
+    // emits a jump whose target is filled in by patchJump
+    fn emitJump(&mut self, op: OpCode, span: Span) -> usize {
+        self.emitOp(op, span);
+        self.emitPlaceholder(span)
+    }
+
+    fn emitPlaceholder(&mut self, span: Span) -> usize {
+        let offset = self.current().chunk.code.len();
+        self.emitU16(u16::MAX, span);
+        offset
+    }
+
+    fn emitJumpTo(&mut self, target: usize, span: Span) {
+        let target = self.operand(target, "bytes of code", span);
+        self.emitOp(OpCode::Jump, span);
+        self.emitU16(target, span);
+    }
+
+    // makes the jump operand at `offset` point at the current position
+    fn patchJump(&mut self, offset: usize, span: Span) {
+        let here = self.current().chunk.code.len();
+        let target = self.operand(here, "bytes of code", span);
+        self.current().chunk.patchU16(offset, target);
+    }
+}
+
+fn binaryOp(op: &TokenKind) -> OpCode {
+    match op {
+        TokenKind::Plus => OpCode::Add,
+        TokenKind::Minus => OpCode::Subtract,
+        TokenKind::Star => OpCode::Multiply,
+        TokenKind::Slash => OpCode::Divide,
+        TokenKind::Percent => OpCode::Modulo,
+        TokenKind::EqualEqual => OpCode::Equal,
+        TokenKind::BangEqual => OpCode::NotEqual,
+        TokenKind::Less => OpCode::Less,
+        TokenKind::LessEqual => OpCode::LessEqual,
+        TokenKind::Greater => OpCode::Greater,
+        TokenKind::GreaterEqual => OpCode::GreaterEqual,
+        other => unreachable!("the parser does not build binary {}", other),
+    }
+}
+
+// names a function body declares, in order, not counting nested functions' own
+fn declaredNames(body: &[Stmt]) -> Vec<String> {
+    fn walk(statements: &[Stmt], names: &mut Vec<String>) {
+        for stmt in statements {
+            let name = match stmt {
+                Stmt::VarDecl { name, .. } | Stmt::FuncDecl { name, .. } | Stmt::Struct { name, .. } => Some(name),
+                Stmt::For { variable, .. } | Stmt::ForEach { variable, .. } => Some(variable),
+                _ => None,
+            };
+            if let Some(name) = name {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+            match stmt {
+                Stmt::If {
+                    then_branch,
+                    else_branch,
+                    ..
+                } => {
+                    walk(then_branch, names);
+                    if let Some(else_branch) = else_branch {
+                        walk(else_branch, names);
+                    }
+                }
+                Stmt::While { body, .. } | Stmt::For { body, .. } | Stmt::ForEach { body, .. } => {
+                    walk(body, names)
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut names = Vec::new();
+    walk(body, &mut names);
+    names
+}
+
+// locals a function may read or assign before their declaration has run,
+// which is an error as in the tree walker; these live in cells, which know
+// whether they are set, while every other local is set before its first use
+fn uncertainNames(params: &[Parameter], body: &[Stmt]) -> HashSet<String> {
+    let declared: HashSet<String> = declaredNames(body).into_iter().collect();
+    let mut set = params.iter().map(|param| param.name.clone()).collect();
+    let mut uncertain = HashSet::new();
+    uncertainIn(body, &declared, &mut set, &mut uncertain);
+    uncertain
+}
+
+// walks `statements` in order, with `set` holding the locals set on every
+// path that reaches the current statement
+fn uncertainIn(statements: &[Stmt], declared: &HashSet<String>, set: &mut HashSet<String>, uncertain: &mut HashSet<String>) {
+    let check = |used: HashSet<String>, set: &HashSet<String>, uncertain: &mut HashSet<String>| {
+        uncertain.extend(used.into_iter().filter(|name| declared.contains(name) && !set.contains(name)));
+    };
+    let uses = |expr: &Expr| {
+        let mut used = HashSet::new();
+        exprUses(expr, &mut used, false);
+        used
+    };
+    for stmt in statements {
+        match stmt {
+            Stmt::VarDecl { name, initializer, .. } => {
+                check(uses(initializer), set, uncertain);
+                set.insert(name.clone());
+            }
+            // the body only runs once the function is called; what it
+            // uses from here is captured, and so already in a cell
+            Stmt::FuncDecl { name, .. } | Stmt::Struct { name, .. } => {
+                set.insert(name.clone());
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                check(uses(condition), set, uncertain);
+                let mut then_set = set.clone();
+                uncertainIn(then_branch, declared, &mut then_set, uncertain);
+                let mut else_set = set.clone();
+                uncertainIn(else_branch.as_deref().unwrap_or_default(), declared, &mut else_set, uncertain);
+                set.extend(then_set.intersection(&else_set).cloned().collect::<Vec<_>>());
+            }
+            // a loop body may not run at all, so nothing it sets is certain
+            // afterwards, its variable included
+            Stmt::While { condition, body, .. } => {
+                check(uses(condition), set, uncertain);
+                uncertainIn(body, declared, &mut set.clone(), uncertain);
+            }
+            Stmt::For {
+                variable,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                check(uses(start), set, uncertain);
+                check(uses(end), set, uncertain);
+                let mut body_set = set.clone();
+                body_set.insert(variable.clone());
+                uncertainIn(body, declared, &mut body_set, uncertain);
+            }
+            Stmt::ForEach {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                check(uses(iterable), set, uncertain);
+                let mut body_set = set.clone();
+                body_set.insert(variable.clone());
+                uncertainIn(body, declared, &mut body_set, uncertain);
+            }
+            Stmt::Import { path, names, .. } => match names {
+                Some(names) => set.extend(names.iter().map(|imported| imported.name.clone())),
+                None => set.extend(moduleName(path)),
+            },
+            Stmt::Impl { .. } | Stmt::Return { .. } | Stmt::ExprStmt(_) | Stmt::Break { .. } | Stmt::Continue { .. } => {
+                let mut used = HashSet::new();
+                stmtUses(stmt, &mut used, false);
+                check(used, set, uncertain);
+            }
+        }
+    }
+}
+
+// locals of a function that nested functions refer to; these live in
+// cells so that every closure sees the same variable
+fn capturedNames(body: &[Stmt]) -> HashSet<String> {
+    let mut used = HashSet::new();
+    for stmt in body {
+        stmtUses(stmt, &mut used, true);
+    }
+    used
+}
+
+// names used by a function but declared outside it
+fn freeVariables(params: &[Parameter], body: &[Stmt]) -> HashSet<String> {
+    let mut used = HashSet::new();
+    for stmt in body {
+        stmtUses(stmt, &mut used, false);
+    }
+    for name in declaredNames(body) {
+        used.remove(&name);
+    }
+    for param in params {
+        used.remove(&param.name);
+    }
+    used
+}
+
+// collects the names `stmt` refers to; with `nested_only`, only those
+// referred to from inside nested functions, lambdas included
+fn stmtUses(stmt: &Stmt, used: &mut HashSet<String>, nested_only: bool) {
+    let expr = |expr: &Expr, used: &mut HashSet<String>| exprUses(expr, used, nested_only);
+    match stmt {
+        Stmt::VarDecl { initializer, .. } => expr(initializer, used),
+        Stmt::FuncDecl { params, body, .. } => used.extend(freeVariables(params, body)),
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            expr(condition, used);
+            for stmt in then_branch.iter().chain(else_branch.iter().flatten()) {
+                stmtUses(stmt, used, nested_only);
+            }
+        }
+        Stmt::While { condition, body, .. } => {
+            expr(condition, used);
+            for stmt in body {
+                stmtUses(stmt, used, nested_only);
+            }
+        }
+        Stmt::For { start, end, body, .. } => {
+            expr(start, used);
+            expr(end, used);
+            for stmt in body {
+                stmtUses(stmt, used, nested_only);
+            }
+        }
+        Stmt::ForEach { iterable, body, .. } => {
+            expr(iterable, used);
+            for stmt in body {
+                stmtUses(stmt, used, nested_only);
+            }
+        }
+        Stmt::Impl { name, methods, .. } => {
+            if !nested_only {
+                used.insert(name.clone());
+            }
+            for method in methods {
+                stmtUses(method, used, nested_only);
+            }
+        }
+        Stmt::Return { value, .. } => expr(value, used),
+        Stmt::ExprStmt(value) => expr(value, used),
+        Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Import { .. } | Stmt::Struct { .. } => {}
+    }
+}
+
+fn exprUses(expr: &Expr, used: &mut HashSet<String>, nested_only: bool) {
+    match expr {
+        Expr::Literal { .. } => {}
+        Expr::Variable { name, .. } => {
+            if !nested_only {
+                used.insert(name.clone());
+            }
+        }
+        Expr::Lambda { params, body, .. } => used.extend(freeVariables(params, body)),
+        Expr::Update { target, .. } => targetUses(target, used, nested_only),
+        Expr::Assign { target, value, .. } => {
+            targetUses(target, used, nested_only);
+            exprUses(value, used, nested_only);
+        }
+        Expr::Binary { left, right, .. } => {
+            exprUses(left, used, nested_only);
+            exprUses(right, used, nested_only);
+        }
+        Expr::Unary { right, .. } => exprUses(right, used, nested_only),
+        Expr::Call { callee, args, .. } => {
+            exprUses(callee, used, nested_only);
+            for arg in args {
+                exprUses(arg, used, nested_only);
+            }
+        }
+        Expr::Get { object, .. } => exprUses(object, used, nested_only),
+        Expr::Array { elements, .. } => {
+            for element in elements {
+                exprUses(element, used, nested_only);
+            }
+        }
+        Expr::Interpolation { parts, .. } => {
+            for part in parts {
+                exprUses(part, used, nested_only);
+            }
+        }
+        Expr::Dict { entries, .. } => {
+            for (key, value) in entries {
+                exprUses(key, used, nested_only);
+                exprUses(value, used, nested_only);
+            }
+        }
+        Expr::Index { object, index, .. } => {
+            exprUses(object, used, nested_only);
+            exprUses(index, used, nested_only);
+        }
+    }
+}
+
+fn targetUses(target: &Target, used: &mut HashSet<String>, nested_only: bool) {
+    match target {
+        Target::Variable { name, .. } => {
+            if !nested_only {
+                used.insert(name.clone());
+            }
+        }
+        Target::Index { object, index, .. } => {
+            exprUses(object, used, nested_only);
+            exprUses(index, used, nested_only);
+        }
+        Target::Field { object, .. } => exprUses(object, used, nested_only),
+    }
+}
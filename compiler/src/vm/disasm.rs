@@ -0,0 +1,139 @@
+use std::fmt::Write;
+
+use super::chunk::Constant;
+use super::compiler::Program;
+use super::function::{Capture, FunctionProto};
+use super::opcode::OpCode;
+use crate::ast::floatText;
+use crate::diagnostics::SourceMap;
+
+// renders a program's bytecode, one function after another, with the
+// source line each run of instructions came from
+pub fn disassemble(program: &Program, sources: &SourceMap) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "globals: {}", program.globals.join(", "));
+    for (index, module) in program.modules.iter().enumerate() {
+        let exports: Vec<String> = module
+            .exports
+            .iter()
+            .map(|(name, global)| format!("{} (global {})", name, global))
+            .collect();
+        let _ = writeln!(out, "module {} {}: {}", index, module.name, exports.join(", "));
+    }
+    disassembleFunction(&program.script, program, sources, &mut out);
+    out
+}
+
+fn disassembleFunction(function: &FunctionProto, program: &Program, sources: &SourceMap, out: &mut String) {
+    out.push('\n');
+    let _ = writeln!(
+        out,
+        "== {} ({} params, {} locals, {} cells, {} upvalues) ==",
+        function.name,
+        function.arity,
+        function.local_count,
+        function.cell_names.len(),
+        function.captures.len()
+    );
+    let chunk = &function.chunk;
+    let mut file = None;
+    let mut line = 0;
+    let mut ip = 0;
+    while ip < chunk.code.len() {
+        let source = sources.find(chunk.spans[ip].start);
+        let position = source.start(chunk.spans[ip]);
+        // a program's script also starts the files it imports
+        if sources.files().len() > 1 && file != Some(source.base()) {
+            let _ = writeln!(out, "; in {}", source.name);
+        }
+        if position.line != line || file != Some(source.base()) {
+            file = Some(source.base());
+            line = position.line;
+            if source.is_detached() {
+                let _ = writeln!(out, "; line {}", line);
+            } else {
+                let _ = writeln!(out, "; {:>4} | {}", line, source.line(line).trim());
+            }
+        }
+
+        let op = match OpCode::from_byte(chunk.code[ip]) {
+            Some(op) => op,
+            None => {
+                let _ = writeln!(out, "{:04}  <unknown opcode {}>", ip, chunk.code[ip]);
+                ip += 1;
+                continue;
+            }
+        };
+        let operands = describeOperands(op, function, program, ip);
+        let instruction = format!("{:04}  {:<14}{}", ip, format!("{:?}", op), operands);
+        let _ = writeln!(out, "{}", instruction.trim_end());
+        if op == OpCode::Closure {
+            if let Some(Constant::Function(nested)) = chunk.constants.get(chunk.readU16(ip + 1) as usize) {
+                for capture in &nested.captures {
+                    match capture {
+                        Capture::Cell(i) => {
+                            let _ = writeln!(out, "      | capture cell {}", i);
+                        }
+                        Capture::Upvalue(i) => {
+                            let _ = writeln!(out, "      | capture upvalue {}", i);
+                        }
+                    }
+                }
+            }
+        }
+        ip += 1 + op.operand_width();
+    }
+
+    // nested functions follow the function that contains them
+    for constant in &chunk.constants {
+        if let Constant::Function(nested) = constant {
+            disassembleFunction(nested, program, sources, out);
+        }
+    }
+}
+
+fn describeOperands(op: OpCode, function: &FunctionProto, program: &Program, ip: usize) -> String {
+    let chunk = &function.chunk;
+    let a = || chunk.readU16(ip + 1) as usize;
+    let byte = |offset: usize| chunk.code[ip + offset];
+    let constant = |index: usize| match chunk.constants.get(index) {
+        Some(Constant::Int(n)) => format!("{}", n),
+        Some(Constant::BigInt(n)) => format!("{}", n),
+        Some(Constant::Float(n)) => floatText(*n),
+        Some(Constant::String(s)) => format!("{:?}", s),
+        Some(Constant::Function(nested)) => format!("<fn {}>", nested.name),
+        Some(Constant::Struct { name, .. }) => format!("<struct {}>", name),
+        None => "<missing>".to_string(),
+    };
+    let global = |index: usize| program.globals.get(index).cloned().unwrap_or_else(|| "<missing>".to_string());
+
+    match op {
+        _ if op.operand_width() == 0 => String::new(),
+        OpCode::Constant
+        | OpCode::Closure
+        | OpCode::GetProperty
+        | OpCode::SetProperty
+        | OpCode::Struct
+        | OpCode::Method => format!("{:<6}{}", a(), constant(a())),
+        OpCode::GetGlobal | OpCode::SetGlobal | OpCode::DefineGlobal => format!("{:<6}{}", a(), global(a())),
+        OpCode::Jump | OpCode::JumpIfFalse | OpCode::And | OpCode::Or => format!("-> {:04}", a()),
+        OpCode::Module => format!(
+            "{:<6}{}",
+            a(),
+            program.modules.get(a()).map_or("<missing>", |module| module.name.as_str())
+        ),
+        OpCode::Call => format!("{} args", byte(1)),
+        OpCode::CallBuiltin | OpCode::Invoke => format!("{:<6}{} ({} args)", a(), constant(a()), byte(3)),
+        OpCode::Bury => format!("under {}", byte(1)),
+        OpCode::CheckIndexable => format!("under {}", byte(1)),
+        OpCode::RangeBound => (if byte(1) == 0 { "start" } else { "end" }).to_string(),
+        OpCode::CheckBool => (if byte(1) == 0 { "&&" } else { "||" }).to_string(),
+        OpCode::RangeNext | OpCode::ForEachNext => format!(
+            "slots {}, {} else -> {:04}",
+            a(),
+            chunk.readU16(ip + 3),
+            chunk.readU16(ip + 5)
+        ),
+        _ => format!("{}", a()),
+    }
+}
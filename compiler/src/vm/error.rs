@@ -0,0 +1,68 @@
+use std::fmt;
+
+use crate::diagnostics::Diagnostic;
+use crate::lexer::span::Span;
+
+// programs the tree walker would only reject while running, or that do
+// not fit the bytecode format
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompileError {
+    BreakOutsideLoop { span: Span },
+
+    ContinueOutsideLoop { span: Span },
+
+    // `what` is e.g. "constants" or "local variables"
+    TooMany { what: &'static str, span: Span },
+}
+
+impl CompileError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string());
+        match self {
+            CompileError::BreakOutsideLoop { span } | CompileError::ContinueOutsideLoop { span } => {
+                diagnostic.with_primary(*span, "not inside a loop")
+            }
+            CompileError::TooMany { span, .. } => diagnostic
+                .with_primary(*span, "in this function")
+                .with_note("a function can use at most 65536 of each"),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::BreakOutsideLoop { .. } => write!(f, "'break' outside of loop"),
+            CompileError::ContinueOutsideLoop { .. } => write!(f, "'continue' outside of loop"),
+            CompileError::TooMany { what, .. } => write!(f, "Too many {} in one function", what),
+        }
+    }
+}
+
+// reasons a `.reyc` file cannot be loaded
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    NotBytecode,
+
+    UnsupportedVersion { found: u16 },
+
+    Truncated,
+
+    Malformed { message: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotBytecode => write!(f, "not a compiled rey module"),
+            LoadError::UnsupportedVersion { found } => write!(
+                f,
+                "bytecode version {} is not supported (expected {}); recompile the source",
+                found,
+                super::module::VERSION
+            ),
+            LoadError::Truncated => write!(f, "the module is truncated"),
+            LoadError::Malformed { message } => write!(f, "malformed module: {}", message),
+        }
+    }
+}
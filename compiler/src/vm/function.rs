@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::chunk::Chunk;
+use crate::interpreter::value::Value;
+use crate::lexer::span::Span;
+
+// a variable shared between the frame that declared it and every closure
+// that captured it; None until its declaration has run
+pub type Cell = Rc<RefCell<Option<Value>>>;
+
+// where a closure finds each captured variable when it is created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    // a cell of the enclosing frame
+    Cell(u16),
+    // an upvalue of the enclosing closure
+    Upvalue(u16),
+}
+
+// a compiled function: its code and the frame layout it expects
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionProto {
+    pub name: String,
+    pub arity: usize,
+    // stack slots including the parameters
+    pub local_count: usize,
+    // names of the variables kept in cells, by cell index
+    pub cell_names: Vec<String>,
+    pub captures: Vec<Capture>,
+    // names of the captured variables, by upvalue index
+    pub upvalue_names: Vec<String>,
+    pub chunk: Chunk,
+    pub span: Span,
+}
+
+// a function value created at runtime: a prototype plus the variables
+// it captured from the scopes around it
+pub struct Closure {
+    pub proto: Rc<FunctionProto>,
+    pub upvalues: Vec<Cell>,
+}
+
+// captured cells may refer back to the closure, so only the prototype
+// is shown
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("name", &self.proto.name)
+            .finish_non_exhaustive()
+    }
+}
+
+// closures are equal only if they are the same value
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
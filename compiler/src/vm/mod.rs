@@ -0,0 +1,16 @@
+pub mod chunk;
+pub mod compiler;
+pub mod disasm;
+pub mod error;
+pub mod function;
+pub mod module;
+pub mod opcode;
+pub mod vm;
+
+pub use compiler::{Compiler, Program};
+pub use disasm::disassemble;
+pub use module::Module;
+pub use vm::Vm;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,708 @@
+use std::rc::Rc;
+
+use super::chunk::{Chunk, Constant};
+use super::compiler::{ModuleInfo, Program};
+use super::error::LoadError;
+use super::function::{Capture, FunctionProto};
+use super::opcode::OpCode;
+use crate::bigint::BigInt;
+use crate::diagnostics::{SourceFile, SourceMap};
+use crate::lexer::span::Span;
+
+// layout of a `.reyc` file, all integers little-endian:
+//
+//   magic "REYC", version u16
+//   sources (u32 count; each a name (string), checksum u64, base u32 and
+//     line starts (u32 count, u32 each)), the program's own file last
+//   globals (u32 count, string each)
+//   imported modules (u32 count; each a name (string) and exports (u32
+//     count; name (string) and u16 global each))
+//   main: u8 1 and the u16 global holding it, or u8 0
+//   functions (u32 count, function each), the top-level script last
+//
+// a string is a u32 byte length followed by UTF-8; a function is
+//
+//   name (string), arity u16, local count u16, span (u32 start, u32 end)
+//   cell names (u16 count, string each)
+//   captures (u16 count; u8 kind, 0 cell or 1 upvalue, u16 index and the
+//     variable's name (string) each)
+//   constants (u16 count; u8 tag then 0: i64, 1: string, 2: u32 index of an
+//     earlier function, 3: f64, 4: big int as a u8 sign, 1 for negative, and
+//     u32 limb count then u32 limbs, least significant first, 5: struct as
+//     its name and u16 field count then field names)
+//   code (u32 length, bytes)
+//   spans (u32 count; u32 code offset, u32 start, u32 end each), one entry
+//     wherever the span changes
+pub const MAGIC: &[u8; 4] = b"REYC";
+pub const VERSION: u16 = 11;
+
+// one of the files a module was compiled from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Source {
+    pub name: String,
+    // tells whether a file on disk is still the source this was built from
+    pub checksum: u64,
+    // where the file's spans start
+    pub base: usize,
+    pub line_starts: Vec<usize>,
+}
+
+// a compiled program with enough about its sources to report errors
+// without re-reading them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub sources: Vec<Source>,
+    pub program: Program,
+}
+
+impl Module {
+    pub fn new(sources: &SourceMap, program: Program) -> Self {
+        let sources = sources
+            .files()
+            .iter()
+            .map(|file| Source {
+                name: file.name.clone(),
+                checksum: checksum(&file.text),
+                base: file.base(),
+                line_starts: file.line_starts().to_vec(),
+            })
+            .collect();
+        Self { sources, program }
+    }
+
+    // the sources to render errors against: each original file that is
+    // unchanged, otherwise just its line layout
+    pub fn sources(&self, read: impl Fn(&str) -> Option<String>) -> SourceMap {
+        let mut map = SourceMap::new();
+        for source in &self.sources {
+            let file = match read(&source.name) {
+                Some(text) if checksum(&text) == source.checksum => SourceFile::new(&source.name, &text),
+                _ => SourceFile::detached(&source.name, source.line_starts.clone()),
+            };
+            map.add(file.with_base(source.base));
+        }
+        map
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Writer { bytes: Vec::new() };
+        out.bytes.extend_from_slice(MAGIC);
+        out.u16(VERSION);
+        out.u32(self.sources.len());
+        for source in &self.sources {
+            out.string(&source.name);
+            out.u64(source.checksum);
+            out.u32(source.base);
+            out.u32(source.line_starts.len());
+            for start in &source.line_starts {
+                out.u32(*start);
+            }
+        }
+        out.u32(self.program.globals.len());
+        for name in &self.program.globals {
+            out.string(name);
+        }
+        out.u32(self.program.modules.len());
+        for module in &self.program.modules {
+            out.string(&module.name);
+            out.u32(module.exports.len());
+            for (name, global) in &module.exports {
+                out.string(name);
+                out.u16(*global);
+            }
+        }
+        match self.program.main {
+            Some(global) => {
+                out.u8(1);
+                out.u16(global);
+            }
+            None => out.u8(0),
+        }
+
+        let mut functions = Vec::new();
+        collectFunctions(&self.program.script, &mut functions);
+        out.u32(functions.len());
+        for function in &functions {
+            out.function(function, &functions);
+        }
+        out.bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Module, LoadError> {
+        let mut input = Reader { bytes, offset: 0 };
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(LoadError::NotBytecode);
+        }
+        input.offset = MAGIC.len();
+        let version = input.u16()?;
+        if version != VERSION {
+            return Err(LoadError::UnsupportedVersion { found: version });
+        }
+
+        let source_count = input.u32()?;
+        let mut sources: Vec<Source> = Vec::new();
+        for _ in 0..source_count {
+            let name = input.string()?;
+            let checksum = input.u64()?;
+            let base = input.u32()?;
+            if sources.last().is_some_and(|last| last.base >= base) {
+                return Err(malformed("sources do not start at increasing offsets"));
+            }
+            let line_count = input.u32()?;
+            let mut line_starts = Vec::new();
+            for _ in 0..line_count {
+                let start = input.u32()?;
+                if line_starts.last().is_some_and(|last| *last >= start) || (line_starts.is_empty() && start != 0) {
+                    return Err(malformed("line starts are not increasing from 0"));
+                }
+                line_starts.push(start);
+            }
+            if line_starts.is_empty() {
+                return Err(malformed("the line table is empty"));
+            }
+            sources.push(Source {
+                name,
+                checksum,
+                base,
+                line_starts,
+            });
+        }
+        if sources.is_empty() {
+            return Err(malformed("there are no sources"));
+        }
+
+        let global_count = input.u32()?;
+        let mut globals = Vec::new();
+        for _ in 0..global_count {
+            globals.push(input.string()?);
+        }
+
+        let module_count = input.u32()?;
+        let mut modules = Vec::new();
+        for _ in 0..module_count {
+            let name = input.string()?;
+            let mut exports = Vec::new();
+            for _ in 0..input.u32()? {
+                let export = input.string()?;
+                let global = input.u16()?;
+                if global as usize >= globals.len() {
+                    return Err(malformed(&format!("module '{}' exports a missing global", name)));
+                }
+                exports.push((export, global));
+            }
+            modules.push(ModuleInfo { name, exports });
+        }
+        let main = match input.u8()? {
+            0 => None,
+            1 => {
+                let global = input.u16()?;
+                if global as usize >= globals.len() {
+                    return Err(malformed("main refers to a missing global"));
+                }
+                Some(global)
+            }
+            flag => return Err(malformed(&format!("unknown main flag {}", flag))),
+        };
+
+        let function_count = input.u32()?;
+        let mut functions: Vec<Rc<FunctionProto>> = Vec::new();
+        for _ in 0..function_count {
+            let function = input.function(&functions)?;
+            validate(&function, globals.len(), modules.len()).map_err(|message| {
+                malformed(&format!("function '{}': {}", function.name, message))
+            })?;
+            functions.push(Rc::new(function));
+        }
+        if input.offset != bytes.len() {
+            return Err(malformed("unexpected data after the last function"));
+        }
+        let script = functions.pop().ok_or_else(|| malformed("there is no top-level code"))?;
+
+        Ok(Module {
+            sources,
+            program: Program {
+                script,
+                globals,
+                modules,
+                main,
+            },
+        })
+    }
+}
+
+// FNV-1a, which is plenty to notice an edited source file
+fn checksum(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn malformed(message: &str) -> LoadError {
+    LoadError::Malformed {
+        message: message.to_string(),
+    }
+}
+
+// every function reachable from `function`, nested ones before the
+// functions containing them
+fn collectFunctions(function: &Rc<FunctionProto>, functions: &mut Vec<Rc<FunctionProto>>) {
+    for constant in &function.chunk.constants {
+        if let Constant::Function(nested) = constant {
+            collectFunctions(nested, functions);
+        }
+    }
+    functions.push(function.clone());
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    // sizes are usize in memory; the compiler keeps them far below u32::MAX
+    fn u32(&mut self, value: usize) {
+        self.bytes.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn function(&mut self, function: &FunctionProto, functions: &[Rc<FunctionProto>]) {
+        self.string(&function.name);
+        self.u16(function.arity as u16);
+        self.u16(function.local_count as u16);
+        self.u32(function.span.start);
+        self.u32(function.span.end);
+
+        self.u16(function.cell_names.len() as u16);
+        for name in &function.cell_names {
+            self.string(name);
+        }
+
+        self.u16(function.captures.len() as u16);
+        for (capture, name) in function.captures.iter().zip(&function.upvalue_names) {
+            match capture {
+                Capture::Cell(index) => {
+                    self.u8(0);
+                    self.u16(*index);
+                }
+                Capture::Upvalue(index) => {
+                    self.u8(1);
+                    self.u16(*index);
+                }
+            }
+            self.string(name);
+        }
+
+        let chunk = &function.chunk;
+        self.u16(chunk.constants.len() as u16);
+        for constant in &chunk.constants {
+            match constant {
+                Constant::Int(n) => {
+                    self.u8(0);
+                    self.bytes.extend_from_slice(&n.to_le_bytes());
+                }
+                Constant::String(s) => {
+                    self.u8(1);
+                    self.string(s);
+                }
+                Constant::Function(nested) => {
+                    self.u8(2);
+                    let index = functions
+                        .iter()
+                        .position(|candidate| Rc::ptr_eq(candidate, nested))
+                        .expect("nested functions are collected first");
+                    self.u32(index);
+                }
+                Constant::Float(n) => {
+                    self.u8(3);
+                    self.bytes.extend_from_slice(&n.to_le_bytes());
+                }
+                Constant::BigInt(n) => {
+                    self.u8(4);
+                    let (negative, limbs) = n.parts();
+                    self.u8(negative as u8);
+                    self.u32(limbs.len());
+                    for &limb in limbs {
+                        self.u32(limb as usize);
+                    }
+                }
+                Constant::Struct { name, fields } => {
+                    self.u8(5);
+                    self.string(name);
+                    self.u16(fields.len() as u16);
+                    for field in fields {
+                        self.string(field);
+                    }
+                }
+            }
+        }
+
+        self.u32(chunk.code.len());
+        self.bytes.extend_from_slice(&chunk.code);
+
+        let mut runs = Vec::new();
+        for (offset, span) in chunk.spans.iter().enumerate() {
+            if offset == 0 || chunk.spans[offset - 1] != *span {
+                runs.push((offset, *span));
+            }
+        }
+        self.u32(runs.len());
+        for (offset, span) in runs {
+            self.u32(offset);
+            self.u32(span.start);
+            self.u32(span.end);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], LoadError> {
+        let end = self.offset.checked_add(count).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or(LoadError::Truncated)?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, LoadError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<usize, LoadError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn u64(&mut self) -> Result<u64, LoadError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn string(&mut self) -> Result<String, LoadError> {
+        let length = self.u32()?;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| malformed("a string is not valid UTF-8"))
+    }
+
+    fn function(&mut self, functions: &[Rc<FunctionProto>]) -> Result<FunctionProto, LoadError> {
+        let name = self.string()?;
+        let arity = self.u16()? as usize;
+        let local_count = self.u16()? as usize;
+        let span = Span::new(self.u32()?, self.u32()?);
+        if arity > local_count {
+            return Err(malformed(&format!("function '{}' has fewer locals than parameters", name)));
+        }
+
+        let mut cell_names = Vec::new();
+        for _ in 0..self.u16()? {
+            cell_names.push(self.string()?);
+        }
+        let mut captures = Vec::new();
+        let mut upvalue_names = Vec::new();
+        for _ in 0..self.u16()? {
+            let capture = match self.u8()? {
+                0 => Capture::Cell(self.u16()?),
+                1 => Capture::Upvalue(self.u16()?),
+                kind => return Err(malformed(&format!("unknown capture kind {}", kind))),
+            };
+            captures.push(capture);
+            upvalue_names.push(self.string()?);
+        }
+
+        let mut chunk = Chunk::new();
+        for _ in 0..self.u16()? {
+            let constant = match self.u8()? {
+                0 => {
+                    let mut bytes = [0; 8];
+                    bytes.copy_from_slice(self.take(8)?);
+                    Constant::Int(i64::from_le_bytes(bytes))
+                }
+                1 => Constant::String(self.string()?),
+                2 => {
+                    let index = self.u32()?;
+                    let nested = functions
+                        .get(index)
+                        .ok_or_else(|| malformed("a function constant refers to a later function"))?;
+                    Constant::Function(nested.clone())
+                }
+                3 => {
+                    let mut bytes = [0; 8];
+                    bytes.copy_from_slice(self.take(8)?);
+                    Constant::Float(f64::from_le_bytes(bytes))
+                }
+                4 => {
+                    let negative = self.u8()? == 1;
+                    let mut limbs = Vec::new();
+                    for _ in 0..self.u32()? {
+                        limbs.push(self.u32()? as u32);
+                    }
+                    Constant::BigInt(BigInt::from_parts(negative, limbs))
+                }
+                5 => {
+                    let name = self.string()?;
+                    let mut fields = Vec::new();
+                    for _ in 0..self.u16()? {
+                        fields.push(self.string()?);
+                    }
+                    Constant::Struct { name, fields }
+                }
+                tag => return Err(malformed(&format!("unknown constant tag {}", tag))),
+            };
+            chunk.constants.push(constant);
+        }
+
+        let length = self.u32()?;
+        chunk.code = self.take(length)?.to_vec();
+
+        // expand the runs back into one span per byte
+        let run_count = self.u32()?;
+        let mut runs = Vec::new();
+        for _ in 0..run_count {
+            runs.push((self.u32()?, Span::new(self.u32()?, self.u32()?)));
+        }
+        if chunk.code.is_empty() || runs.first().map(|(offset, _)| *offset) != Some(0) {
+            return Err(malformed(&format!("function '{}' has no span for its first instruction", name)));
+        }
+        for (index, (offset, span)) in runs.iter().enumerate() {
+            let end = runs.get(index + 1).map(|(next, _)| *next).unwrap_or(chunk.code.len());
+            if end <= *offset || end > chunk.code.len() {
+                return Err(malformed(&format!("function '{}' has a broken span table", name)));
+            }
+            chunk.spans.extend(std::iter::repeat_n(*span, end - offset));
+        }
+
+        Ok(FunctionProto {
+            name,
+            arity,
+            local_count,
+            cell_names,
+            captures,
+            upvalue_names,
+            chunk,
+            span,
+        })
+    }
+}
+
+// checks that the VM can run `function` without reading out of bounds:
+// every operand names something that exists, every jump lands on an
+// instruction and the stack never underflows
+fn validate(function: &FunctionProto, global_count: usize, module_count: usize) -> Result<(), String> {
+    let chunk = &function.chunk;
+    let code = &chunk.code;
+    let constants = &chunk.constants;
+    let mut starts = vec![false; code.len()];
+    let mut targets = Vec::new();
+    let mut last = None;
+
+    let check = |ok: bool, what: &str, offset: usize| {
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("{} out of range at offset {}", what, offset))
+        }
+    };
+
+    let mut ip = 0;
+    while ip < code.len() {
+        starts[ip] = true;
+        let op = OpCode::from_byte(code[ip]).ok_or_else(|| format!("unknown opcode {} at offset {}", code[ip], ip))?;
+        if ip + 1 + op.operand_width() > code.len() {
+            return Err(format!("{:?} at offset {} is cut off", op, ip));
+        }
+        let a = || chunk.readU16(ip + 1) as usize;
+        let b = || chunk.readU16(ip + 3) as usize;
+        let c = || chunk.readU16(ip + 5) as usize;
+        let is_string = |index: usize| matches!(constants.get(index), Some(Constant::String(_)));
+
+        match op {
+            OpCode::Constant => check(
+                matches!(constants.get(a()), Some(Constant::Int(_) | Constant::BigInt(_) | Constant::Float(_) | Constant::String(_))),
+                "constant",
+                ip,
+            )?,
+            OpCode::GetGlobal | OpCode::SetGlobal | OpCode::DefineGlobal => check(a() < global_count, "global", ip)?,
+            OpCode::Module => check(a() < module_count, "module", ip)?,
+            OpCode::GetLocal | OpCode::SetLocal => check(a() < function.local_count, "local slot", ip)?,
+            OpCode::GetCell | OpCode::SetCell | OpCode::DefineCell => check(a() < function.cell_names.len(), "cell", ip)?,
+            OpCode::GetUpvalue | OpCode::SetUpvalue => check(a() < function.captures.len(), "upvalue", ip)?,
+            OpCode::Jump | OpCode::JumpIfFalse | OpCode::And | OpCode::Or => targets.push(a()),
+            OpCode::CallBuiltin | OpCode::Invoke | OpCode::GetProperty | OpCode::SetProperty | OpCode::Method => {
+                check(is_string(a()), "name constant", ip)?
+            }
+            OpCode::Struct => check(matches!(constants.get(a()), Some(Constant::Struct { .. })), "struct constant", ip)?,
+            OpCode::Closure => match constants.get(a()) {
+                Some(Constant::Function(nested)) => {
+                    for capture in &nested.captures {
+                        match capture {
+                            Capture::Cell(i) => check((*i as usize) < function.cell_names.len(), "captured cell", ip)?,
+                            Capture::Upvalue(i) => {
+                                check((*i as usize) < function.captures.len(), "captured upvalue", ip)?
+                            }
+                        }
+                    }
+                }
+                _ => check(false, "function constant", ip)?,
+            },
+            OpCode::RangeBound => check(code[ip + 1] <= 1, "range bound", ip)?,
+            OpCode::RangeNext | OpCode::ForEachNext => {
+                check(a() < function.local_count && b() < function.local_count, "local slot", ip)?;
+                targets.push(c());
+            }
+            _ => {}
+        }
+        last = Some(op);
+        ip += 1 + op.operand_width();
+    }
+
+    for target in targets {
+        if target >= code.len() || !starts[target] {
+            return Err(format!("a jump lands inside an instruction or past the end (offset {})", target));
+        }
+    }
+    // the VM never runs off the end because every function ends in Return
+    if last != Some(OpCode::Return) {
+        return Err("the code does not end with Return".to_string());
+    }
+    checkStack(chunk)
+}
+
+// follows every path through the code, requiring each instruction to be
+// reached with the same stack height and enough values for what it pops
+fn checkStack(chunk: &Chunk) -> Result<(), String> {
+    let mut heights: Vec<Option<usize>> = vec![None; chunk.code.len()];
+    let mut pending = vec![(0, 0)];
+    while let Some((ip, height)) = pending.pop() {
+        match heights[ip] {
+            Some(known) if known == height => continue,
+            Some(known) => {
+                return Err(format!(
+                    "stack height at offset {} is both {} and {}",
+                    ip, known, height
+                ))
+            }
+            None => heights[ip] = Some(height),
+        }
+
+        let op = OpCode::from_byte(chunk.code[ip]).expect("opcodes are checked first");
+        let (pops, pushes) = stackEffect(op, chunk, ip);
+        if height < pops {
+            return Err(format!("{:?} at offset {} pops more than the stack holds", op, ip));
+        }
+        let after = height - pops + pushes;
+        let next = ip + 1 + op.operand_width();
+        match op {
+            OpCode::Return => {}
+            OpCode::Jump => pending.push((chunk.readU16(ip + 1) as usize, after)),
+            OpCode::JumpIfFalse => {
+                pending.push((chunk.readU16(ip + 1) as usize, after));
+                pending.push((next, after));
+            }
+            // the operand is only popped when it doesn't decide the result
+            OpCode::And | OpCode::Or => {
+                pending.push((chunk.readU16(ip + 1) as usize, height));
+                pending.push((next, after));
+            }
+            // the loop value is only pushed when the loop goes on
+            OpCode::RangeNext | OpCode::ForEachNext => {
+                pending.push((chunk.readU16(ip + 5) as usize, height));
+                pending.push((next, after));
+            }
+            _ => pending.push((next, after)),
+        }
+    }
+    Ok(())
+}
+
+// values an instruction pops and pushes
+fn stackEffect(op: OpCode, chunk: &Chunk, ip: usize) -> (usize, usize) {
+    let a = || chunk.readU16(ip + 1) as usize;
+    match op {
+        OpCode::Constant
+        | OpCode::Null
+        | OpCode::True
+        | OpCode::False
+        | OpCode::GetGlobal
+        | OpCode::GetLocal
+        | OpCode::GetCell
+        | OpCode::GetUpvalue
+        | OpCode::Closure
+        | OpCode::Module
+        | OpCode::Struct
+        | OpCode::RangeNext
+        | OpCode::ForEachNext => (0, 1),
+        OpCode::Pop
+        | OpCode::DefineGlobal
+        | OpCode::DefineCell
+        | OpCode::JumpIfFalse
+        | OpCode::And
+        | OpCode::Or
+        | OpCode::Return => (1, 0),
+        OpCode::Dup => (1, 2),
+        OpCode::Dup2 => (2, 4),
+        OpCode::Bury | OpCode::CheckIndexable => (chunk.code[ip + 1] as usize + 1, chunk.code[ip + 1] as usize + 1),
+        OpCode::SetGlobal
+        | OpCode::SetLocal
+        | OpCode::SetCell
+        | OpCode::SetUpvalue
+        | OpCode::Negate
+        | OpCode::Not
+        | OpCode::Complement
+        | OpCode::CheckBool
+        | OpCode::GetProperty
+        | OpCode::RangeBound
+        | OpCode::CheckKey
+        | OpCode::IterSnapshot => (1, 1),
+        OpCode::Add
+        | OpCode::Subtract
+        | OpCode::Multiply
+        | OpCode::Divide
+        | OpCode::Modulo
+        | OpCode::Equal
+        | OpCode::NotEqual
+        | OpCode::Less
+        | OpCode::LessEqual
+        | OpCode::Greater
+        | OpCode::GreaterEqual
+        | OpCode::Index
+        | OpCode::SetProperty
+        | OpCode::Method => (2, 1),
+        OpCode::SetIndex => (3, 1),
+        OpCode::Jump => (0, 0),
+        // the callee or receiver sits below the arguments
+        OpCode::Call => (chunk.code[ip + 1] as usize + 1, 1),
+        OpCode::Invoke => (chunk.code[ip + 3] as usize + 1, 1),
+        OpCode::CallBuiltin => (chunk.code[ip + 3] as usize, 1),
+        OpCode::Array | OpCode::Interpolate => (a(), 1),
+        OpCode::Dict => (a() * 2, 1),
+    }
+}
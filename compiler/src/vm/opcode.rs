@@ -0,0 +1,211 @@
+// one byte per instruction, followed by its operands
+// operands are little-endian u16 unless noted, so a chunk can hold up to
+// 65536 constants, locals and bytes of code
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+    // push constants[a]
+    Constant,
+    Null,
+    True,
+    False,
+    Pop,
+    // pushes a copy of the top value
+    Dup,
+    // pushes a copy of the top two values, in order
+    Dup2,
+    // depth (u8): moves the top value below the `depth` values under it
+    Bury,
+
+    // a: global index
+    GetGlobal,
+    SetGlobal,
+    DefineGlobal,
+    // a: stack slot relative to the frame
+    GetLocal,
+    SetLocal,
+    // a: index into the frame's cells, which hold captured locals and
+    // those that may be used before they are declared
+    GetCell,
+    SetCell,
+    DefineCell,
+    // a: index into the running closure's upvalues
+    GetUpvalue,
+    SetUpvalue,
+
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Negate,
+    Not,
+    // bitwise not, for `~`
+    Complement,
+
+    // a: absolute target
+    Jump,
+    // a: absolute target, taken when the popped value is falsy
+    JumpIfFalse,
+    // a: absolute target; the left operand of `&&` or `||` on top must be
+    // a bool, which stays as the result and jumps when it decides it, and
+    // is popped otherwise
+    And,
+    Or,
+    // which (u8): 0 for `&&`, 1 for `||`; the right operand on top must be a bool
+    CheckBool,
+
+    // argc (u8); the operand's span is the callee's, reported when it
+    // is not callable
+    Call,
+    // a: name constant, argc (u8)
+    CallBuiltin,
+    // a: method name constant, argc (u8); receiver sits below the
+    // arguments; the operands' span is the callee's, as for Call
+    Invoke,
+    // a: property name constant
+    GetProperty,
+    // a: property name constant; pops the value and object, pushes the value
+    SetProperty,
+    // a: function constant; captures are listed in the function itself
+    Closure,
+    Return,
+    // a: index of an imported file in the program's module table
+    Module,
+    // a: struct constant; pushes a new struct type
+    Struct,
+    // a: method name constant; pops a closure and adds it to the struct
+    // type below it, which stays
+    Method,
+
+    // a: element count
+    Array,
+    // a: entry count, keys and values interleaved on the stack
+    Dict,
+    // the key on top must be able to key a dictionary
+    CheckKey,
+    // a: part count; joins the parts as println shows them
+    Interpolate,
+    // depth (u8): the value `depth` below the top must be indexable
+    CheckIndexable,
+    Index,
+    SetIndex,
+
+    // which (u8): 0 for a range start, 1 for its end; truncates to a whole number
+    RangeBound,
+    // a: counter slot, b: limit slot, c: exit target
+    RangeNext,
+    // replaces an array or dictionary with an array of what to iterate over
+    IterSnapshot,
+    // a: items slot, b: position slot, c: exit target
+    ForEachNext,
+}
+
+// every opcode in discriminant order, for decoding
+const OPCODES: [OpCode; 58] = [
+    OpCode::Constant,
+    OpCode::Null,
+    OpCode::True,
+    OpCode::False,
+    OpCode::Pop,
+    OpCode::Dup,
+    OpCode::Dup2,
+    OpCode::Bury,
+    OpCode::GetGlobal,
+    OpCode::SetGlobal,
+    OpCode::DefineGlobal,
+    OpCode::GetLocal,
+    OpCode::SetLocal,
+    OpCode::GetCell,
+    OpCode::SetCell,
+    OpCode::DefineCell,
+    OpCode::GetUpvalue,
+    OpCode::SetUpvalue,
+    OpCode::Add,
+    OpCode::Subtract,
+    OpCode::Multiply,
+    OpCode::Divide,
+    OpCode::Modulo,
+    OpCode::Equal,
+    OpCode::NotEqual,
+    OpCode::Less,
+    OpCode::LessEqual,
+    OpCode::Greater,
+    OpCode::GreaterEqual,
+    OpCode::Negate,
+    OpCode::Not,
+    OpCode::Complement,
+    OpCode::Jump,
+    OpCode::JumpIfFalse,
+    OpCode::And,
+    OpCode::Or,
+    OpCode::CheckBool,
+    OpCode::Call,
+    OpCode::CallBuiltin,
+    OpCode::Invoke,
+    OpCode::GetProperty,
+    OpCode::SetProperty,
+    OpCode::Closure,
+    OpCode::Return,
+    OpCode::Module,
+    OpCode::Struct,
+    OpCode::Method,
+    OpCode::Array,
+    OpCode::Dict,
+    OpCode::CheckKey,
+    OpCode::Interpolate,
+    OpCode::CheckIndexable,
+    OpCode::Index,
+    OpCode::SetIndex,
+    OpCode::RangeBound,
+    OpCode::RangeNext,
+    OpCode::IterSnapshot,
+    OpCode::ForEachNext,
+];
+
+impl OpCode {
+    pub fn from_byte(byte: u8) -> Option<OpCode> {
+        OPCODES.get(byte as usize).copied()
+    }
+
+    // bytes of operands following the opcode
+    pub fn operand_width(self) -> usize {
+        match self {
+            OpCode::Null
+            | OpCode::True
+            | OpCode::False
+            | OpCode::Pop
+            | OpCode::Dup
+            | OpCode::Dup2
+            | OpCode::Add
+            | OpCode::Subtract
+            | OpCode::Multiply
+            | OpCode::Divide
+            | OpCode::Modulo
+            | OpCode::Equal
+            | OpCode::NotEqual
+            | OpCode::Less
+            | OpCode::LessEqual
+            | OpCode::Greater
+            | OpCode::GreaterEqual
+            | OpCode::Negate
+            | OpCode::Not
+            | OpCode::Complement
+            | OpCode::Return
+            | OpCode::Index
+            | OpCode::SetIndex
+            | OpCode::CheckKey
+            | OpCode::IterSnapshot => 0,
+            OpCode::Call | OpCode::Bury | OpCode::RangeBound | OpCode::CheckBool | OpCode::CheckIndexable => 1,
+            OpCode::CallBuiltin | OpCode::Invoke => 3,
+            OpCode::RangeNext | OpCode::ForEachNext => 6,
+            _ => 2,
+        }
+    }
+}
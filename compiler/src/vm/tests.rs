@@ -0,0 +1,214 @@
+use std::rc::Rc;
+
+use super::error::LoadError;
+use super::module::{Module, VERSION};
+use super::opcode::OpCode;
+use super::{Compiler, Vm};
+use crate::diagnostics::SourceFile;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::Interpreter;
+use crate::modules::{Loader, ModuleGraph};
+
+const PROGRAM: &str = r#"
+struct Point { x, y }
+var big = 123456789012345678901234567890;
+var half = 0.5;
+func counter() {
+    var count = 0;
+    return func() { count += 1; return count; };
+}
+func main(args) {
+    var next = counter();
+    var total = 0;
+    for i in range(0, 3) {
+        if i % 2 == 0 { total += next(); } else { continue; }
+    }
+    var p = Point(total, half);
+    var double = (x) => x * 2;
+    println("{big}", p.x, double(p.y), { "k": [1, 2] });
+    for item in [total] { println(item); }
+}
+"#;
+
+fn load(source: &str) -> ModuleGraph {
+    let (graph, diagnostics) = Loader::new().load(&SourceFile::new("test.rey", source), false);
+    assert!(diagnostics.is_empty(), "load errors: {:?}", diagnostics);
+    graph
+}
+
+fn compile(source: &str) -> Module {
+    let graph = load(source);
+    let program = Compiler::new().compile(&graph).expect("program compiles");
+    Module::new(&graph.sources, program)
+}
+
+// what `main` returns, or the runtime error and the text it points at,
+// the same on both backends
+fn runAt(source: &str) -> Result<String, (String, String)> {
+    let graph = load(source);
+    let error = |err: RuntimeError| (err.to_string(), source[err.span().start..err.span().end].to_string());
+    let mut interpreter = Interpreter::new();
+    let walked = interpreter
+        .interpret_modules(&graph)
+        .and_then(|()| interpreter.call_main(&[]))
+        .map(|value| value.to_string())
+        .map_err(error);
+    let program = Compiler::new().compile(&graph).expect("program compiles");
+    let compiled = Vm::new().run(&program, &[]).map(|value| value.to_string()).map_err(error);
+    assert_eq!(walked, compiled, "the backends disagree");
+    walked
+}
+
+fn run(source: &str) -> Result<String, String> {
+    runAt(source).map_err(|(message, _)| message)
+}
+
+// the module encoded after `edit` has changed the top-level code
+fn withScriptCode(module: &Module, edit: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+    let mut module = module.clone();
+    let script = Rc::make_mut(&mut module.program.script);
+    edit(&mut script.chunk.code);
+    let span = script.chunk.spans[0];
+    script.chunk.spans.resize(script.chunk.code.len(), span);
+    module.encode()
+}
+
+fn malformed(bytes: &[u8]) -> String {
+    match Module::decode(bytes) {
+        Err(LoadError::Malformed { message }) => message,
+        other => panic!("expected a malformed module, got {:?}", other),
+    }
+}
+
+// offset of the first `op` instruction in `code`
+fn find(code: &[u8], op: OpCode) -> usize {
+    let mut ip = 0;
+    while ip < code.len() {
+        let current = OpCode::from_byte(code[ip]).expect("compiled code is valid");
+        if current == op {
+            return ip;
+        }
+        ip += 1 + current.operand_width();
+    }
+    panic!("no {:?} in the code", op);
+}
+
+#[test]
+fn modules_round_trip() {
+    let module = compile(PROGRAM);
+    let bytes = module.encode();
+    assert_eq!(Module::decode(&bytes), Ok(module.clone()));
+    // encoding is deterministic, so a decoded module writes the same bytes
+    assert_eq!(Module::decode(&bytes).map(|decoded| decoded.encode()), Ok(bytes));
+}
+
+#[test]
+fn every_truncation_is_rejected() {
+    let bytes = compile(PROGRAM).encode();
+    for length in 0..bytes.len() {
+        let expected = if length < 4 { LoadError::NotBytecode } else { LoadError::Truncated };
+        assert_eq!(Module::decode(&bytes[..length]), Err(expected), "cut off after {} bytes", length);
+    }
+}
+
+#[test]
+fn trailing_bytes_are_rejected() {
+    let mut bytes = compile(PROGRAM).encode();
+    bytes.push(0);
+    assert_eq!(malformed(&bytes), "unexpected data after the last function");
+}
+
+#[test]
+fn other_files_are_not_bytecode() {
+    assert_eq!(Module::decode(b"func main() {}"), Err(LoadError::NotBytecode));
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let mut bytes = compile(PROGRAM).encode();
+    bytes[4..6].copy_from_slice(&(VERSION - 1).to_le_bytes());
+    assert_eq!(Module::decode(&bytes), Err(LoadError::UnsupportedVersion { found: VERSION - 1 }));
+}
+
+#[test]
+fn jumps_out_of_range_are_rejected() {
+    let module = compile("var x = 1;\nif x > 0 { println(x); }");
+    let bytes = withScriptCode(&module, |code| {
+        let jump = find(code, OpCode::JumpIfFalse);
+        code[jump + 1..jump + 3].copy_from_slice(&u16::MAX.to_le_bytes());
+    });
+    assert!(malformed(&bytes).contains("a jump lands inside an instruction or past the end"));
+}
+
+#[test]
+fn jumps_into_an_instruction_are_rejected() {
+    let module = compile("var x = 1;\nif x > 0 { println(x); }");
+    let bytes = withScriptCode(&module, |code| {
+        let jump = find(code, OpCode::JumpIfFalse);
+        // the middle of the jump's own operand
+        code[jump + 1..jump + 3].copy_from_slice(&(jump as u16 + 1).to_le_bytes());
+    });
+    assert!(malformed(&bytes).contains("a jump lands inside an instruction or past the end"));
+}
+
+#[test]
+fn unknown_opcodes_are_rejected() {
+    let module = compile("println(1);");
+    let bytes = withScriptCode(&module, |code| code[0] = u8::MAX);
+    assert_eq!(malformed(&bytes), "function '<script>': unknown opcode 255 at offset 0");
+}
+
+#[test]
+fn stack_underflow_is_rejected() {
+    let module = compile("println(1);");
+    let bytes = withScriptCode(&module, |code| *code = vec![OpCode::Pop as u8, OpCode::Null as u8, OpCode::Return as u8]);
+    assert!(malformed(&bytes).contains("Pop at offset 0 pops more than the stack holds"));
+}
+
+#[test]
+fn code_must_end_with_return() {
+    let module = compile("println(1);");
+    let bytes = withScriptCode(&module, |code| *code = vec![OpCode::Null as u8, OpCode::Pop as u8]);
+    assert!(malformed(&bytes).contains("the code does not end with Return"));
+}
+
+#[test]
+fn locals_are_undefined_until_declared() {
+    let undefined = |name: &str| Err(format!("Undefined variable '{}'", name));
+    assert_eq!(run("func main() { println(y); var y = 1; }"), undefined("y"));
+    assert_eq!(run("func main() { y = 2; var y = 1; return y; }"), undefined("y"));
+    assert_eq!(run("func main() { if false { var z = 1; } return z; }"), undefined("z"));
+    assert_eq!(run("func main() { for i in range(0, 0) {} return i; }"), undefined("i"));
+    // a closure reading a variable of its function that isn't declared yet
+    assert_eq!(run("func main() { var g = () => y; g(); var y = 1; }"), undefined("y"));
+}
+
+#[test]
+fn locals_declared_on_every_path_are_defined() {
+    let source = r#"
+func main() {
+    if true { var w = 2; } else { var w = 3; }
+    for i in range(0, 2) { var k = i; }
+    var n = 0;
+    while n < 3 { if n > 0 { n += last; } var last = n; n += 1; }
+    return "{w} {k} {n}";
+}
+"#;
+    assert_eq!(run(source), Ok("2 1 4".to_string()));
+}
+
+#[test]
+fn errors_point_at_the_same_code() {
+    let error = |message: &str, at: &str| Err((message.to_string(), at.to_string()));
+    assert_eq!(runAt("var x = 1;\nx(2);"), error("Can only call functions, got int", "x"));
+    assert_eq!(runAt("struct P { f }\nP(1).f();"), error("Can only call functions, got int", "P(1).f"));
+    assert_eq!(runAt("func main() { y = 2; }"), error("Undefined variable 'y'", "y"));
+    assert_eq!(runAt("var s = \"abc\";\ns[0] = \"x\";"), error("Cannot index into string", "s"));
+    assert_eq!(runAt("var s = \"abc\";\ns[0] += \"x\";"), error("Cannot index into string", "s"));
+    assert_eq!(runAt("println(\"abc\"[0]);"), error("Cannot index into string", "\"abc\""));
+    assert_eq!(runAt("var a = [1];\na[5] = 0;"), error("Index 5 is out of bounds for an array of length 1", "5"));
+    assert_eq!(
+        runAt("var d = {\"a\": 1, [1]: 2};"),
+        error("Dictionary keys must be strings, numbers or bools, got array", "[1]")
+    );
+}
@@ -0,0 +1,605 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::chunk::Constant;
+use super::compiler::Program;
+use super::function::{Capture, Cell, Closure};
+use super::opcode::OpCode;
+use crate::interpreter::dict::Dict;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::methods;
+use crate::interpreter::module::{Module, ModuleScope};
+use crate::interpreter::operators;
+use crate::interpreter::std::StdLib;
+use crate::interpreter::structs::StructType;
+use crate::interpreter::function::MAX_CALL_DEPTH;
+use crate::interpreter::value::Value;
+use crate::lexer::span::Span;
+use crate::lexer::TokenKind;
+
+// one running function call
+struct Frame {
+    closure: Rc<Closure>,
+    ip: usize,
+    // stack index of the frame's first slot; the callee sits just below it
+    base: usize,
+    cells: Vec<Cell>,
+}
+
+// runs programs produced by the compiler on a value stack
+pub struct Vm {
+    stack: Vec<Value>,
+    // callers of the running frame
+    frames: Vec<Frame>,
+    // None until the global is defined
+    globals: Vec<Option<Value>>,
+    names: Vec<String>,
+    // the value of each imported file
+    modules: Vec<Value>,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: Vec::new(),
+            names: Vec::new(),
+            modules: Vec::new(),
+        }
+    }
+
+    // runs the script and then `main`, if the program declared one,
+    // returning what main returned
+    pub fn run(&mut self, program: &Program, args: &[String]) -> Result<Value, RuntimeError> {
+        let builtins = StdLib::create_global_environment();
+        self.names = program.globals.clone();
+        self.globals = self.names.iter().map(|name| builtins.get(name).cloned()).collect();
+        self.modules = program
+            .modules
+            .iter()
+            .map(|module| {
+                let (names, globals) = module.exports.iter().cloned().unzip();
+                Value::Module(Rc::new(Module::new(module.name.clone(), names, ModuleScope::Globals(globals))))
+            })
+            .collect();
+        self.frames.clear();
+        self.stack.clear();
+
+        let script = Rc::new(Closure {
+            proto: program.script.clone(),
+            upvalues: Vec::new(),
+        });
+        self.stack.push(Value::Closure(script.clone()));
+        let frame = self.frame(script, 1);
+        self.execute(frame)?;
+
+        let main = match program.main.and_then(|index| self.globals[index as usize].clone()) {
+            Some(Value::Closure(main)) => main,
+            _ => return Ok(Value::Null),
+        };
+        self.stack.clear();
+        self.stack.push(Value::Closure(main.clone()));
+        match main.proto.arity {
+            0 => {}
+            1 => self
+                .stack
+                .push(Value::array(args.iter().map(|arg| Value::String(arg.clone())).collect())),
+            count => {
+                return Err(RuntimeError::MainParameters {
+                    count,
+                    span: main.proto.span,
+                })
+            }
+        }
+        let frame = self.frame(main, 1);
+        self.execute(frame)
+    }
+
+    // sets up a frame whose arguments are already on the stack
+    fn frame(&mut self, closure: Rc<Closure>, base: usize) -> Frame {
+        let proto = &closure.proto;
+        self.stack.resize(base + proto.local_count, Value::Null);
+        let cells = (0..proto.cell_names.len()).map(|_| Rc::new(RefCell::new(None))).collect();
+        Frame {
+            closure,
+            ip: 0,
+            base,
+            cells,
+        }
+    }
+
+    fn execute(&mut self, mut frame: Frame) -> Result<Value, RuntimeError> {
+        macro_rules! byte {
+            () => {{
+                let byte = frame.closure.proto.chunk.code[frame.ip];
+                frame.ip += 1;
+                byte
+            }};
+        }
+        macro_rules! short {
+            () => {{
+                let value = frame.closure.proto.chunk.readU16(frame.ip);
+                frame.ip += 2;
+                value as usize
+            }};
+        }
+
+        loop {
+            let start = frame.ip;
+            let op = OpCode::from_byte(byte!()).expect("the compiler only emits valid opcodes");
+            let span = || frame.closure.proto.chunk.spans[start];
+
+            match op {
+                OpCode::Constant => {
+                    let value = match &frame.closure.proto.chunk.constants[short!()] {
+                        Constant::Int(n) => Value::Int(*n),
+                        Constant::BigInt(n) => Value::BigInt(Rc::new(n.clone())),
+                        Constant::Float(n) => Value::Float(*n),
+                        Constant::String(s) => Value::String(s.clone()),
+                        Constant::Function(_) => unreachable!("functions are loaded with Closure"),
+                        Constant::Struct { .. } => unreachable!("struct types are made with Struct"),
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::Null => self.stack.push(Value::Null),
+                OpCode::True => self.stack.push(Value::Bool(true)),
+                OpCode::False => self.stack.push(Value::Bool(false)),
+                OpCode::Pop => {
+                    self.stack.pop();
+                }
+                OpCode::Dup => self.stack.push(self.peek().clone()),
+                OpCode::Dup2 => {
+                    let pair = self.stack[self.stack.len() - 2..].to_vec();
+                    self.stack.extend(pair);
+                }
+                OpCode::Bury => {
+                    let depth = byte!() as usize;
+                    let value = self.pop();
+                    self.stack.insert(self.stack.len() - depth, value);
+                }
+
+                OpCode::GetGlobal => {
+                    let index = short!();
+                    match &self.globals[index] {
+                        Some(value) => self.stack.push(value.clone()),
+                        None => return Err(self.undefined(index, span())),
+                    }
+                }
+                OpCode::SetGlobal => {
+                    let index = short!();
+                    if self.globals[index].is_none() {
+                        return Err(self.undefined(index, span()));
+                    }
+                    self.globals[index] = Some(self.peek().clone());
+                }
+                OpCode::DefineGlobal => {
+                    let index = short!();
+                    self.globals[index] = Some(self.pop());
+                }
+                OpCode::GetLocal => {
+                    let slot = frame.base + short!();
+                    self.stack.push(self.stack[slot].clone());
+                }
+                OpCode::SetLocal => {
+                    let slot = frame.base + short!();
+                    self.stack[slot] = self.peek().clone();
+                }
+                OpCode::GetCell => {
+                    let index = short!();
+                    match frame.cells[index].borrow().clone() {
+                        Some(value) => self.stack.push(value),
+                        None => return Err(undefinedLocal(&frame.closure.proto.cell_names[index], span())),
+                    }
+                }
+                OpCode::SetCell => {
+                    let index = short!();
+                    let mut cell = frame.cells[index].borrow_mut();
+                    if cell.is_none() {
+                        return Err(undefinedLocal(&frame.closure.proto.cell_names[index], span()));
+                    }
+                    *cell = Some(self.peek().clone());
+                }
+                OpCode::DefineCell => {
+                    let index = short!();
+                    *frame.cells[index].borrow_mut() = Some(self.pop());
+                }
+                OpCode::GetUpvalue => {
+                    let index = short!();
+                    match frame.closure.upvalues[index].borrow().clone() {
+                        Some(value) => self.stack.push(value),
+                        None => return Err(undefinedLocal(&frame.closure.proto.upvalue_names[index], span())),
+                    }
+                }
+                OpCode::SetUpvalue => {
+                    let index = short!();
+                    let mut cell = frame.closure.upvalues[index].borrow_mut();
+                    if cell.is_none() {
+                        return Err(undefinedLocal(&frame.closure.proto.upvalue_names[index], span()));
+                    }
+                    *cell = Some(self.peek().clone());
+                }
+
+                OpCode::Add
+                | OpCode::Subtract
+                | OpCode::Multiply
+                | OpCode::Divide
+                | OpCode::Modulo
+                | OpCode::Equal
+                | OpCode::NotEqual
+                | OpCode::Less
+                | OpCode::LessEqual
+                | OpCode::Greater
+                | OpCode::GreaterEqual => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let result = match (op, &left, &right) {
+                        // ints are by far the most common operands; overflow
+                        // falls through to report the error
+                        (OpCode::Add, Value::Int(l), Value::Int(r)) if l.checked_add(*r).is_some() => Value::Int(l + r),
+                        (OpCode::Subtract, Value::Int(l), Value::Int(r)) if l.checked_sub(*r).is_some() => {
+                            Value::Int(l - r)
+                        }
+                        (OpCode::Less, Value::Int(l), Value::Int(r)) => Value::Bool(l < r),
+                        (OpCode::LessEqual, Value::Int(l), Value::Int(r)) => Value::Bool(l <= r),
+                        (OpCode::Greater, Value::Int(l), Value::Int(r)) => Value::Bool(l > r),
+                        (OpCode::GreaterEqual, Value::Int(l), Value::Int(r)) => Value::Bool(l >= r),
+                        (OpCode::Equal, Value::Int(l), Value::Int(r)) => Value::Bool(l == r),
+                        (OpCode::Add, Value::Float(l), Value::Float(r)) => Value::Float(l + r),
+                        (OpCode::Subtract, Value::Float(l), Value::Float(r)) => Value::Float(l - r),
+                        (OpCode::Multiply, Value::Float(l), Value::Float(r)) => Value::Float(l * r),
+                        _ => operators::binary(left, &operator(op), right, span())?,
+                    };
+                    self.stack.push(result);
+                }
+                OpCode::Negate => {
+                    let value = match self.pop() {
+                        Value::Float(n) => Value::Float(-n),
+                        other => operators::unary(&TokenKind::Minus, other, span())?,
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::Not => {
+                    let value = operators::unary(&TokenKind::Bang, self.pop(), span())?;
+                    self.stack.push(value);
+                }
+                OpCode::Complement => {
+                    let value = operators::unary(&TokenKind::Tilde, self.pop(), span())?;
+                    self.stack.push(value);
+                }
+
+                OpCode::Jump => frame.ip = short!(),
+                OpCode::JumpIfFalse => {
+                    let target = short!();
+                    if !self.pop().is_truthy() {
+                        frame.ip = target;
+                    }
+                }
+
+                OpCode::And | OpCode::Or => {
+                    let target = short!();
+                    let op = if op == OpCode::And { TokenKind::AndAnd } else { TokenKind::OrOr };
+                    let left = operators::logical_operand(&op, self.peek(), span())?;
+                    if left == (op == TokenKind::OrOr) {
+                        frame.ip = target;
+                    } else {
+                        self.pop();
+                    }
+                }
+                OpCode::CheckBool => {
+                    let op = if byte!() == 0 { TokenKind::AndAnd } else { TokenKind::OrOr };
+                    operators::logical_operand(&op, self.peek(), span())?;
+                }
+
+                OpCode::Call => {
+                    let argc = byte!() as usize;
+                    let (span, callee_span) = (span(), frame.closure.proto.chunk.spans[start + 1]);
+                    self.call(&mut frame, argc, span, callee_span)?;
+                }
+                OpCode::CallBuiltin => {
+                    let index = short!();
+                    let name = self.name(&frame, index);
+                    let argc = byte!() as usize;
+                    let result = self.builtin(&name, argc, span())?;
+                    self.stack.push(result);
+                }
+                OpCode::Invoke => {
+                    let index = short!();
+                    let name = self.name(&frame, index);
+                    let argc = byte!() as usize;
+                    let (span, callee_span) = (span(), frame.closure.proto.chunk.spans[start + 1]);
+                    let receiver = self.stack.len() - argc - 1;
+                    // a field holding a function is called like one; a method
+                    // is put below the receiver, which becomes its first argument
+                    if let Value::Struct(instance) = &self.stack[receiver] {
+                        if let Some(function) = instance.get(&name) {
+                            self.stack[receiver] = function;
+                            self.call(&mut frame, argc, span, callee_span)?;
+                            continue;
+                        }
+                        if let Some(method) = instance.ty.method(&name) {
+                            if let Value::Closure(closure) = &method {
+                                methods::check_method_arity(closure.proto.arity, argc, span)?;
+                            }
+                            self.stack.insert(receiver, method);
+                            self.call(&mut frame, argc + 1, span, callee_span)?;
+                            continue;
+                        }
+                    }
+                    // `module.name(args)` calls the module's export instead
+                    if let Value::Module(_) = &self.stack[receiver] {
+                        self.stack[receiver] = self.property(&self.stack[receiver], &name, callee_span)?;
+                        self.call(&mut frame, argc, span, callee_span)?;
+                        continue;
+                    }
+                    let args = self.stack.split_off(self.stack.len() - argc);
+                    let receiver = self.pop();
+                    let result = methods::call_method(&receiver, &name, args, span)?;
+                    self.stack.push(result);
+                }
+                OpCode::GetProperty => {
+                    let index = short!();
+                    let name = self.name(&frame, index);
+                    let receiver = self.pop();
+                    let result = self.property(&receiver, &name, span())?;
+                    self.stack.push(result);
+                }
+                OpCode::SetProperty => {
+                    let index = short!();
+                    let name = self.name(&frame, index);
+                    let value = self.pop();
+                    let receiver = self.pop();
+                    methods::set_property(&receiver, &name, value.clone(), span())?;
+                    self.stack.push(value);
+                }
+                OpCode::Closure => {
+                    let proto = match &frame.closure.proto.chunk.constants[short!()] {
+                        Constant::Function(proto) => proto.clone(),
+                        _ => unreachable!("Closure always refers to a function constant"),
+                    };
+                    let upvalues = proto
+                        .captures
+                        .iter()
+                        .map(|capture| match capture {
+                            Capture::Cell(i) => frame.cells[*i as usize].clone(),
+                            Capture::Upvalue(i) => frame.closure.upvalues[*i as usize].clone(),
+                        })
+                        .collect();
+                    self.stack.push(Value::Closure(Rc::new(Closure { proto, upvalues })));
+                }
+                OpCode::Module => {
+                    let module = self.modules[short!()].clone();
+                    self.stack.push(module);
+                }
+                OpCode::Struct => {
+                    let ty = match &frame.closure.proto.chunk.constants[short!()] {
+                        Constant::Struct { name, fields } => StructType::new(name.clone(), fields.clone()),
+                        _ => unreachable!("Struct always refers to a struct constant"),
+                    };
+                    self.stack.push(Value::StructType(Rc::new(ty)));
+                }
+                OpCode::Method => {
+                    let index = short!();
+                    let name = self.name(&frame, index);
+                    let method = self.pop();
+                    methods::impl_target(self.peek(), span())?.add_method(name, method);
+                }
+                OpCode::Return => {
+                    let result = self.pop();
+                    let caller = match self.frames.pop() {
+                        Some(caller) => caller,
+                        None => return Ok(result),
+                    };
+                    // drop the callee, its arguments and locals
+                    self.stack.truncate(frame.base - 1);
+                    self.stack.push(result);
+                    frame = caller;
+                }
+
+                OpCode::Array => {
+                    let count = short!();
+                    let elements = self.stack.split_off(self.stack.len() - count);
+                    self.stack.push(Value::array(elements));
+                }
+                OpCode::Interpolate => {
+                    let count = short!();
+                    let parts = self.stack.split_off(self.stack.len() - count);
+                    let text: String = parts.iter().map(|part| part.to_string()).collect();
+                    self.stack.push(Value::String(text));
+                }
+                OpCode::Dict => {
+                    let count = short!();
+                    let values = self.stack.split_off(self.stack.len() - count * 2);
+                    let mut dict = Dict::new();
+                    for pair in values.chunks(2) {
+                        dict.insert(methods::dict_key(&pair[0], span())?, pair[1].clone());
+                    }
+                    self.stack.push(Value::dict(dict));
+                }
+                OpCode::CheckKey => {
+                    methods::dict_key(self.peek(), span())?;
+                }
+                OpCode::CheckIndexable => {
+                    let depth = byte!() as usize;
+                    methods::check_indexable(&self.stack[self.stack.len() - 1 - depth], span())?;
+                }
+                OpCode::Index => {
+                    let position = self.pop();
+                    let target = self.pop();
+                    let value = methods::get_index(&target, &position, span(), span())?;
+                    self.stack.push(value);
+                }
+                OpCode::SetIndex => {
+                    let value = self.pop();
+                    let position = self.pop();
+                    let target = self.pop();
+                    methods::set_index(&target, &position, value.clone(), span(), span())?;
+                    self.stack.push(value);
+                }
+
+                OpCode::RangeBound => {
+                    let which = if byte!() == 0 { "start" } else { "end" };
+                    let bound = methods::range_bound(self.pop(), which, span())?;
+                    self.stack.push(Value::Int(bound));
+                }
+                OpCode::RangeNext => {
+                    let counter = frame.base + short!();
+                    let limit = frame.base + short!();
+                    let exit = short!();
+                    match (&self.stack[counter], &self.stack[limit]) {
+                        (Value::Int(i), Value::Int(end)) if i < end => {
+                            let i = *i;
+                            self.stack[counter] = Value::Int(i + 1);
+                            self.stack.push(Value::Int(i));
+                        }
+                        _ => frame.ip = exit,
+                    }
+                }
+                OpCode::IterSnapshot => {
+                    // iterate over a snapshot so the body may modify the collection
+                    let items = match self.pop() {
+                        Value::Array(elements) => elements.borrow().clone(),
+                        Value::Dict(entries) => entries.borrow().iter().map(|(key, _)| key.to_value()).collect(),
+                        other => {
+                            return Err(RuntimeError::new(
+                                format!("Cannot iterate over {}", other.type_name()),
+                                span(),
+                            ))
+                        }
+                    };
+                    self.stack.push(Value::array(items));
+                }
+                OpCode::ForEachNext => {
+                    let items = frame.base + short!();
+                    let position = frame.base + short!();
+                    let exit = short!();
+                    let next = match (&self.stack[items], &self.stack[position]) {
+                        (Value::Array(items), Value::Int(i)) => items.borrow().get(*i as usize).cloned(),
+                        _ => None,
+                    };
+                    match next {
+                        Some(item) => {
+                            if let Value::Int(i) = &mut self.stack[position] {
+                                *i += 1;
+                            }
+                            self.stack.push(item);
+                        }
+                        None => frame.ip = exit,
+                    }
+                }
+            }
+        }
+    }
+
+    // calls the value below the top `argc` stack entries; a closure gets a
+    // new frame, which becomes the running one; `callee_span` is reported
+    // when the value is not callable
+    fn call(&mut self, frame: &mut Frame, argc: usize, span: Span, callee_span: Span) -> Result<(), RuntimeError> {
+        let base = self.stack.len() - argc;
+        match self.stack[base - 1].clone() {
+            Value::Closure(closure) => {
+                if closure.proto.arity != argc {
+                    return Err(RuntimeError::ArityMismatch {
+                        expected: closure.proto.arity,
+                        found: argc,
+                        span,
+                    });
+                }
+                if self.frames.len() >= MAX_CALL_DEPTH {
+                    return Err(RuntimeError::new("Stack overflow".to_string(), span));
+                }
+                let callee = self.frame(closure, base);
+                self.frames.push(std::mem::replace(frame, callee));
+            }
+            Value::StructType(ty) => {
+                let args = self.stack.split_off(base);
+                let instance = methods::construct(&ty, args, span)?;
+                self.stack.pop();
+                self.stack.push(instance);
+            }
+            // a built-in bound to another name
+            Value::Function(function) if StdLib::is_builtin(&function.name) => {
+                let result = self.builtin(&function.name, argc, span)?;
+                self.stack.pop();
+                self.stack.push(result);
+            }
+            other => {
+                return Err(RuntimeError::NotCallable {
+                    found: other.type_name(),
+                    span: callee_span,
+                })
+            }
+        }
+        Ok(())
+    }
+
+    // exports of compiled modules live in globals, which only the VM can read
+    fn property(&self, receiver: &Value, name: &str, span: Span) -> Result<Value, RuntimeError> {
+        if let Value::Module(module) = receiver {
+            if let ModuleScope::Globals(globals) = &module.scope {
+                let index = globals[methods::export_index(module, name, span)?] as usize;
+                return self.globals[index].clone().ok_or_else(|| self.undefined(index, span));
+            }
+        }
+        methods::get_property(receiver, name, span)
+    }
+
+    // pops `argc` arguments and calls the built-in `name` with them
+    fn builtin(&mut self, name: &str, argc: usize, span: Span) -> Result<Value, RuntimeError> {
+        let args = self.stack.split_off(self.stack.len() - argc);
+        match StdLib::call_builtin_function(name, &args) {
+            Some(result) => result.map_err(|message| RuntimeError::new(message, span)),
+            None => Err(RuntimeError::new(format!("Unknown built-in '{}'", name), span)),
+        }
+    }
+
+    fn name(&self, frame: &Frame, index: usize) -> String {
+        match &frame.closure.proto.chunk.constants[index] {
+            Constant::String(name) => name.clone(),
+            _ => unreachable!("names are string constants"),
+        }
+    }
+
+    fn undefined(&self, index: usize, span: Span) -> RuntimeError {
+        RuntimeError::UndefinedVariable {
+            name: self.names[index].clone(),
+            span,
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("the compiler keeps the stack balanced")
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack.last().expect("the compiler keeps the stack balanced")
+    }
+}
+
+// a local read or assigned before its declaration ran, which the tree
+// walker reports the same way
+fn undefinedLocal(name: &str, span: Span) -> RuntimeError {
+    RuntimeError::UndefinedVariable {
+        name: name.to_string(),
+        span,
+    }
+}
+
+// the operator the tree walker would evaluate, for everything without a
+// fast path, so both backends report the same errors
+fn operator(op: OpCode) -> TokenKind {
+    match op {
+        OpCode::Add => TokenKind::Plus,
+        OpCode::Subtract => TokenKind::Minus,
+        OpCode::Multiply => TokenKind::Star,
+        OpCode::Divide => TokenKind::Slash,
+        OpCode::Modulo => TokenKind::Percent,
+        OpCode::Equal => TokenKind::EqualEqual,
+        OpCode::NotEqual => TokenKind::BangEqual,
+        OpCode::Less => TokenKind::Less,
+        OpCode::LessEqual => TokenKind::LessEqual,
+        OpCode::Greater => TokenKind::Greater,
+        OpCode::GreaterEqual => TokenKind::GreaterEqual,
+        other => unreachable!("{:?} is not a binary operator", other),
+    }
+}